@@ -1,15 +1,18 @@
 use std::collections::VecDeque;
+use std::path::Path;
 use std::process;
 use std::time::Instant;
 
 use anyhow::Result;
 use common::command::Command;
-use common::constants::ALLIUM_GAMES_DIR;
+use common::constants::{ALLIUM_GAMES_DIR, GRID_COLUMNS};
 use common::display::color::Color;
 use common::geom;
+use common::launcher::LauncherSettings;
 use common::locale::{Locale, LocaleSettings};
 use common::resources::Resources;
-use common::view::View;
+use common::thumbnail::ThumbnailCache;
+use common::view::{Grid, ImageMode, View};
 use embedded_graphics::prelude::*;
 use enum_map::EnumMap;
 use log::{info, trace, warn};
@@ -17,13 +20,17 @@ use log::{info, trace, warn};
 use common::database::Database;
 use common::display::Display;
 use common::platform::{DefaultPlatform, Key, KeyEvent, Platform};
-use common::stylesheet::Stylesheet;
+use common::stylesheet::{ArtType, Stylesheet};
 use type_map::TypeMap;
 
 use crate::consoles::ConsoleMapper;
 use crate::entry::directory::Directory;
 use crate::entry::game::Game;
-use crate::view::{App, Toast};
+use crate::entry::lazy_image::{find_art, LazyImage};
+use crate::files::{Trash, TRASH_RETENTION};
+use crate::rdb;
+use crate::start::StartOptions;
+use crate::view::{grid_rect, image_rect, tab_rect, App, Toast};
 
 #[derive(Debug)]
 pub struct AlliumLauncher<P: Platform> {
@@ -35,30 +42,48 @@ pub struct AlliumLauncher<P: Platform> {
 }
 
 impl AlliumLauncher<DefaultPlatform> {
-    pub fn new(mut platform: DefaultPlatform) -> Result<Self> {
+    pub fn new(mut platform: DefaultPlatform, options: StartOptions) -> Result<Self> {
         let display = platform.display()?;
         let battery = platform.battery()?;
 
         let mut console_mapper = ConsoleMapper::new();
         console_mapper.load_config()?;
 
+        let database = Database::new()?;
+        if let Err(e) = Trash::new().purge(&database, TRASH_RETENTION) {
+            warn!("failed to purge trash: {}", e);
+        }
+
         let mut res = TypeMap::new();
-        res.insert(Database::new()?);
+        res.insert(database);
         res.insert(console_mapper);
         res.insert(Stylesheet::load()?);
         res.insert(Locale::new(&LocaleSettings::load()?.lang));
+        res.insert(LauncherSettings::load()?);
         res.insert(Into::<geom::Size>::into(display.size()));
         let res = Resources::new(res);
 
-        let view = App::load_or_new(display.bounding_box().into(), res.clone(), battery)?;
+        let mut view = App::load_or_new(display.bounding_box().into(), res.clone(), battery)?;
+        view.start_at(&options)?;
 
-        Ok(AlliumLauncher {
+        let launcher = AlliumLauncher {
             platform,
             display,
             res,
             view,
             toast: None,
-        })
+        };
+
+        // Nothing has been drawn yet, so no thumbnails are in use
+        let thumbnails = ThumbnailCache::new();
+        if let Err(e) = thumbnails.register_sizes(&launcher.thumbnail_sizes()) {
+            warn!("failed to register thumbnail sizes: {}", e);
+        }
+        if let Err(e) = thumbnails.evict() {
+            warn!("failed to evict thumbnails: {}", e);
+        }
+
+        Ok(launcher)
     }
 
     pub async fn run_event_loop(&mut self) -> Result<()> {
@@ -90,6 +115,8 @@ impl AlliumLauncher<DefaultPlatform> {
             if let Some(toast) = self.toast.as_mut() {
                 if toast.has_expired() {
                     self.toast = None;
+                    // Draw over where the toast was
+                    self.view.set_should_draw();
                 } else {
                     drawn |= toast.draw(&mut self.display, &self.res.get::<Stylesheet>())?;
                 }
@@ -181,6 +208,13 @@ impl AlliumLauncher<DefaultPlatform> {
                 self.display.clear(styles.background_color)?;
                 self.display.save()?;
                 self.res.insert(*styles);
+                // Thumbnails of the old size won't be drawn again
+                let sizes = self.thumbnail_sizes();
+                std::thread::spawn(move || {
+                    if let Err(e) = ThumbnailCache::new().register_sizes(&sizes) {
+                        warn!("failed to remove stale thumbnails: {}", e);
+                    }
+                });
                 self.view.save()?;
                 self.view = App::load_or_new(
                     self.display.bounding_box().into(),
@@ -205,6 +239,17 @@ impl AlliumLauncher<DefaultPlatform> {
                     self.platform.battery()?,
                 )?;
             }
+            Command::SaveLauncherSettings(settings) => {
+                trace!("saving launcher settings");
+                settings.save()?;
+                self.res.insert(settings);
+                self.view.save()?;
+                self.view = App::load_or_new(
+                    self.display.bounding_box().into(),
+                    self.res.clone(),
+                    self.platform.battery()?,
+                )?;
+            }
             Command::Redraw => {
                 trace!("redrawing");
                 self.display.load(self.display.bounding_box().into())?;
@@ -279,8 +324,45 @@ impl AlliumLauncher<DefaultPlatform> {
                     dir.populate_db(&mut queue, &database, &console_mapper, &self.res.get())?;
                 }
 
+                #[cfg(feature = "miyoo")]
+                {
+                    std::process::Command::new("show")
+                        .arg("--clear")
+                        .spawn()?
+                        .wait()?;
+                    std::process::Command::new("say")
+                        .arg(self.res.get::<Locale>().t("populating-metadata"))
+                        .spawn()?
+                        .wait()?;
+                }
+                if let Err(e) = rdb::populate_metadata(&database, &console_mapper) {
+                    warn!("failed to populate metadata: {}", e);
+                }
+
                 database.set_has_indexed(true)?;
 
+                // Art is resized in the background, so that scrolling doesn't have to
+                let images: Vec<_> = database
+                    .select_all_games()?
+                    .into_iter()
+                    .map(|game| {
+                        let image = LazyImage::from_db(&game.path, game.image, game.image_searched);
+                        (game.path, image)
+                    })
+                    .collect();
+                let art_type = self.res.get::<Stylesheet>().art_type;
+                let sizes = self.thumbnail_sizes();
+                std::thread::spawn(move || {
+                    let images = images.into_iter().filter_map(|(path, mut image)| {
+                        let art = match art_type {
+                            ArtType::Boxart => None,
+                            _ => find_art(&path, art_type),
+                        };
+                        art.or_else(|| image.image().map(Path::to_path_buf))
+                    });
+                    ThumbnailCache::new().generate(images, &sizes, ImageMode::Contain);
+                });
+
                 self.view.save()?;
                 self.view = App::load_or_new(
                     self.display.bounding_box().into(),
@@ -294,4 +376,14 @@ impl AlliumLauncher<DefaultPlatform> {
         }
         Ok(())
     }
+
+    /// Sizes that box art is drawn at with the current theme, in the list and grid layouts.
+    fn thumbnail_sizes(&self) -> Vec<geom::Size> {
+        let styles = self.res.get::<Stylesheet>();
+        let rect = tab_rect(self.display.bounding_box().into(), &styles);
+        vec![
+            image_rect(rect, &styles).size(),
+            Grid::image_size(grid_rect(rect, &styles), GRID_COLUMNS),
+        ]
+    }
 }
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use xmltree::{Element, XMLNode};
+
+/// A MAME or FBNeo romset, named by the short name of its archive, e.g. `sf2` for `sf2.zip`.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct ArcadeSet {
+    /// Title of the game, e.g. "Street Fighter II: The World Warrior (World 910522)".
+    pub title: String,
+    /// Short name of the set that this is a clone of.
+    #[serde(default)]
+    pub parent: Option<String>,
+    /// Whether this is a BIOS set, which other sets need but can't be played by itself.
+    #[serde(default)]
+    pub bios: bool,
+}
+
+impl ArcadeSet {
+    /// Whether the set is a clone or BIOS, which can be hidden from listings.
+    pub fn is_clone_or_bios(&self) -> bool {
+        self.parent.is_some() || self.bios
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ArcadeSets {
+    #[serde(default)]
+    sets: HashMap<String, ArcadeSet>,
+}
+
+impl ArcadeSets {
+    /// Loads the sets of each file that exists, either TOML like arcade.toml or a MAME/FBNeo
+    /// `.dat` file. Sets in later files replace those in earlier ones, so that users can add to
+    /// and correct the bundled list.
+    pub fn load(paths: &[&Path]) -> Result<Self> {
+        let mut sets = ArcadeSets::default();
+        for path in paths {
+            if !path.exists() {
+                continue;
+            }
+            let file = std::fs::read_to_string(path)?;
+            let other = if path.extension().is_some_and(|ext| ext == "dat") {
+                Self::from_dat(&file)
+            } else {
+                toml::from_str(&file).map_err(Into::into)
+            };
+            sets.extend(other.with_context(|| format!("Failed to parse {}.", path.display()))?);
+        }
+        Ok(sets)
+    }
+
+    /// Parses a Logiqx XML DAT file, as published by MAME and FBNeo, whose `game` or `machine`
+    /// elements are named after their set and described by their title.
+    fn from_dat(file: &str) -> Result<Self> {
+        let datafile = Element::parse(file.as_bytes())?;
+        let sets = datafile
+            .children
+            .iter()
+            .filter_map(XMLNode::as_element)
+            .filter(|e| e.name == "game" || e.name == "machine")
+            .filter_map(|e| {
+                let name = e.attributes.get("name")?;
+                let title = e.get_child("description")?.get_text()?;
+                let set = ArcadeSet {
+                    title: title.trim().to_string(),
+                    parent: e.attributes.get("cloneof").cloned(),
+                    bios: e.attributes.get("isbios").is_some_and(|b| b == "yes"),
+                };
+                Some((name.clone(), set))
+            })
+            .collect();
+        Ok(ArcadeSets { sets })
+    }
+
+    fn extend(&mut self, other: ArcadeSets) {
+        self.sets.extend(other.sets);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ArcadeSet> {
+        self.sets.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arcade_sets() {
+        let mut sets: ArcadeSets = toml::from_str(
+            r#"
+[sets]
+sf2 = { title = "Street Fighter II: The World Warrior (World 910522)" }
+sf2ua = { title = "Street Fighter II: The World Warrior (USA 910206)", parent = "sf2" }
+neogeo = { title = "Neo-Geo", bios = true }
+"#,
+        )
+        .unwrap();
+        assert!(!sets.get("sf2").unwrap().is_clone_or_bios());
+        assert!(sets.get("sf2ua").unwrap().is_clone_or_bios());
+        assert!(sets.get("neogeo").unwrap().is_clone_or_bios());
+        assert_eq!(sets.get("mslug"), None);
+
+        sets.extend(
+            toml::from_str(
+                r#"
+[sets]
+sf2ua = { title = "Street Fighter II (USA)" }
+mslug = { title = "Metal Slug" }
+"#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(sets.get("sf2ua").unwrap().title, "Street Fighter II (USA)");
+        assert!(!sets.get("sf2ua").unwrap().is_clone_or_bios());
+        assert_eq!(sets.get("mslug").unwrap().title, "Metal Slug");
+    }
+
+    #[test]
+    fn test_arcade_sets_from_dat() {
+        let sets = ArcadeSets::from_dat(
+            r#"<?xml version="1.0"?>
+<datafile>
+    <header><name>FinalBurn Neo - Arcade Games</name></header>
+    <game name="mslug">
+        <description>Metal Slug - Super Vehicle-001</description>
+        <rom name="201-p1.p1" size="2097152"/>
+    </game>
+    <game name="mslugx" cloneof="mslug" romof="neogeo">
+        <description>Metal Slug X &amp; Co.</description>
+    </game>
+    <machine name="neogeo" isbios="yes">
+        <description>Neo-Geo</description>
+    </machine>
+    <game name="nodesc"/>
+</datafile>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sets.get("mslug"),
+            Some(&ArcadeSet {
+                title: "Metal Slug - Super Vehicle-001".to_string(),
+                parent: None,
+                bios: false,
+            })
+        );
+        assert_eq!(sets.get("mslugx").unwrap().title, "Metal Slug X & Co.");
+        assert_eq!(sets.get("mslugx").unwrap().parent.as_deref(), Some("mslug"));
+        assert!(sets.get("neogeo").unwrap().bios);
+        assert_eq!(sets.get("nodesc"), None);
+    }
+
+    #[test]
+    fn test_bundled_arcade_sets() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../assets/root/.allium/config/arcade.toml");
+        let sets = ArcadeSets::load(&[&path]).unwrap();
+        assert_eq!(
+            sets.get("mslug").unwrap().title,
+            "Metal Slug - Super Vehicle-001"
+        );
+        assert!(sets.get("neogeo").unwrap().bios);
+        for (name, set) in &sets.sets {
+            if let Some(parent) = &set.parent {
+                assert!(
+                    sets.get(parent).is_some(),
+                    "{name} is a clone of unknown {parent}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,1438 @@
+use std::collections::hash_map::DefaultHasher;
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{anyhow, bail, Result};
+use common::database::{ArchiveEntry, Database};
+use flate2::read::DeflateDecoder;
+use lzma_rs::decompress::{Options, UnpackedSize};
+
+/// Largest zip central directory or 7z header that is read, which is plenty for tens of thousands
+/// of files. Anything bigger is taken to be corrupt.
+const MAX_HEADER_SIZE: u64 = 16 * 1024 * 1024;
+/// Most files, or 7z folders and streams, that an archive may have. Each is parsed into a larger
+/// struct than the bytes it takes in the header, so they are limited separately.
+const MAX_ENTRIES: u64 = 100_000;
+
+/// Returns true if the path is an archive that can be inspected.
+pub fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip") || ext.eq_ignore_ascii_case("7z"))
+}
+
+/// Identifies the contents of a file by its size and modification time, for caches to tell
+/// when it has been replaced. It fits in an SQLite integer.
+pub fn fingerprint(path: &Path) -> Result<u64> {
+    let metadata = fs::metadata(path)?;
+    let mut hasher = DefaultHasher::new();
+    metadata.len().hash(&mut hasher);
+    metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    Ok(hasher.finish() >> 1)
+}
+
+/// Lists the files in a zip or 7z archive.
+pub fn entries(path: &Path) -> Result<Vec<ArchiveEntry>> {
+    let extension = path.extension().and_then(OsStr::to_str).unwrap_or_default();
+    if extension.eq_ignore_ascii_case("zip") {
+        zip_entries(path)
+    } else if extension.eq_ignore_ascii_case("7z") {
+        sevenz_entries(path)
+    } else {
+        bail!("{} is not an archive", path.display())
+    }
+}
+
+/// Lists the files in an archive, caching the listing in the database. The cache is invalidated
+/// when the size or modification time of the archive changes.
+pub fn cached_entries(database: &Database, path: &Path) -> Result<Vec<ArchiveEntry>> {
+    let fingerprint = fingerprint(path)?;
+    if let Some(entries) = database.select_archive_entries(path, fingerprint)? {
+        return Ok(entries);
+    }
+
+    let entries = entries(path)?;
+    database.update_archive_entries(path, fingerprint, &entries)?;
+    Ok(entries)
+}
+
+/// Extracts every file in a zip or 7z archive into `dest`.
+pub fn extract(path: &Path, dest: &Path) -> Result<()> {
+    let extension = path.extension().and_then(OsStr::to_str).unwrap_or_default();
+    if extension.eq_ignore_ascii_case("zip") {
+        extract_zip(path, dest)
+    } else if extension.eq_ignore_ascii_case("7z") {
+        extract_sevenz(path, dest)
+    } else {
+        bail!("{} is not an archive", path.display())
+    }
+}
+
+/// Returns where an archived file is extracted to, refusing names that would escape `dest`.
+fn output_path(dest: &Path, name: &Path) -> Result<PathBuf> {
+    if !name.components().all(|c| matches!(c, Component::Normal(_))) {
+        bail!("refusing to extract {}", name.display());
+    }
+    Ok(dest.join(name))
+}
+
+fn create_file(path: &Path) -> io::Result<BufWriter<File>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(BufWriter::new(File::create(path)?))
+}
+
+/// Checks what was written to an archived file against its size and CRC32, if known.
+struct CheckedWriter<W> {
+    writer: W,
+    hasher: crc32fast::Hasher,
+    written: u64,
+}
+
+impl<W: Write> CheckedWriter<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            hasher: crc32fast::Hasher::new(),
+            written: 0,
+        }
+    }
+
+    /// Flushes the file, failing if it doesn't have the size and CRC32 it was stored with.
+    fn finish(mut self, path: &Path, size: u64, crc32: Option<u32>) -> Result<()> {
+        self.writer.flush()?;
+        if self.written != size {
+            bail!(
+                "{} is {} bytes, expected {}",
+                path.display(),
+                self.written,
+                size
+            );
+        }
+        if crc32.is_some_and(|crc32| crc32 != self.hasher.finalize()) {
+            bail!("{} is corrupt: CRC32 mismatch", path.display());
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for CheckedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.writer.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+fn corrupt(path: &Path) -> anyhow::Error {
+    anyhow!("{} is corrupt", path.display())
+}
+
+const ZIP_EOCD_SIGNATURE: u32 = 0x06054b50;
+const ZIP64_EOCD_SIGNATURE: u32 = 0x06064b50;
+const ZIP64_EOCD_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+const ZIP_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x02014b50;
+const ZIP_LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+/// End of central directory record is 22 bytes, followed by a comment of up to 65535 bytes.
+const ZIP_EOCD_MAX_SIZE: u64 = 22 + u16::MAX as u64;
+/// Size of the ZIP64 end of central directory locator, which comes right before the record.
+const ZIP64_EOCD_LOCATOR_SIZE: usize = 20;
+/// Size of the fixed part of a central directory header.
+const ZIP_CENTRAL_DIRECTORY_HEADER_SIZE: usize = 46;
+/// Size of the fixed part of a local header.
+const ZIP_LOCAL_HEADER_SIZE: u64 = 30;
+/// ID of the extra field holding the 64-bit sizes and offset of a ZIP64 entry.
+const ZIP64_EXTRA_FIELD: u16 = 0x0001;
+
+/// A file in a zip archive's central directory.
+struct ZipEntry {
+    entry: ArchiveEntry,
+    method: u16,
+    compressed_size: u64,
+    /// Offset of the file's local header.
+    offset: u64,
+}
+
+/// Lists the files in a zip archive by reading its central directory. Nothing is decompressed.
+pub fn zip_entries(path: &Path) -> Result<Vec<ArchiveEntry>> {
+    let mut file = File::open(path)?;
+    Ok(zip_central_directory(path, &mut file)?
+        .into_iter()
+        .map(|e| e.entry)
+        .collect())
+}
+
+/// Extracts the files in a zip archive. Only stored and deflated files are supported.
+fn extract_zip(path: &Path, dest: &Path) -> Result<()> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    for zip_entry in zip_central_directory(path, &mut file)? {
+        let output = output_path(dest, &zip_entry.entry.path)?;
+
+        if zip_entry.offset.saturating_add(ZIP_LOCAL_HEADER_SIZE) > len {
+            return Err(corrupt(path));
+        }
+        file.seek(SeekFrom::Start(zip_entry.offset))?;
+        let mut header = [0; ZIP_LOCAL_HEADER_SIZE as usize];
+        file.read_exact(&mut header)?;
+        if u32_le(&header) != ZIP_LOCAL_HEADER_SIGNATURE {
+            bail!("{} has a corrupt local header", path.display());
+        }
+        let name_len = u16_le(&header[26..]) as u64;
+        let extra_len = u16_le(&header[28..]) as u64;
+        let data_offset = zip_entry.offset + ZIP_LOCAL_HEADER_SIZE + name_len + extra_len;
+        if data_offset.saturating_add(zip_entry.compressed_size) > len {
+            return Err(corrupt(path));
+        }
+        file.seek(SeekFrom::Start(data_offset))?;
+
+        // Reading one byte more than the file's size catches files that are bigger than stored
+        let size = zip_entry.entry.size;
+        let data = (&mut file).take(zip_entry.compressed_size);
+        let mut writer = CheckedWriter::new(create_file(&output)?);
+        match zip_entry.method {
+            0 => io::copy(&mut data.take(size.saturating_add(1)), &mut writer)?,
+            8 => io::copy(
+                &mut DeflateDecoder::new(data).take(size.saturating_add(1)),
+                &mut writer,
+            )?,
+            method => bail!(
+                "{} uses unsupported compression method {}",
+                path.display(),
+                method
+            ),
+        };
+        writer.finish(&zip_entry.entry.path, size, Some(zip_entry.entry.crc32))?;
+    }
+    Ok(())
+}
+
+fn zip_central_directory(path: &Path, file: &mut File) -> Result<Vec<ZipEntry>> {
+    let len = file.metadata()?.len();
+
+    let tail_len = len.min(ZIP_EOCD_MAX_SIZE);
+    file.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = vec![0; tail_len as usize];
+    file.read_exact(&mut tail)?;
+
+    let Some(eocd) = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&i| u32_le(&tail[i..]) == ZIP_EOCD_SIGNATURE)
+    else {
+        bail!("{} is not a zip file", path.display());
+    };
+    let mut count = u16_le(&tail[eocd + 10..]) as u64;
+    let mut cd_size = u32_le(&tail[eocd + 12..]) as u64;
+    let mut cd_offset = u32_le(&tail[eocd + 16..]) as u64;
+
+    // ZIP64 archives store the real values in another record, pointed to by a locator
+    if count == u16::MAX as u64 || cd_size == u32::MAX as u64 || cd_offset == u32::MAX as u64 {
+        let locator = eocd
+            .checked_sub(ZIP64_EOCD_LOCATOR_SIZE)
+            .map(|i| &tail[i..eocd])
+            .filter(|locator| u32_le(locator) == ZIP64_EOCD_LOCATOR_SIGNATURE)
+            .ok_or_else(|| corrupt(path))?;
+        let record_offset = u64_le(&locator[8..]);
+        if record_offset.saturating_add(56) > len {
+            return Err(corrupt(path));
+        }
+        file.seek(SeekFrom::Start(record_offset))?;
+        let mut record = [0; 56];
+        file.read_exact(&mut record)?;
+        if u32_le(&record) != ZIP64_EOCD_SIGNATURE {
+            return Err(corrupt(path));
+        }
+        count = u64_le(&record[32..]);
+        cd_size = u64_le(&record[40..]);
+        cd_offset = u64_le(&record[48..]);
+    }
+
+    if count > MAX_ENTRIES
+        || cd_size > MAX_HEADER_SIZE
+        || cd_offset.checked_add(cd_size).is_none_or(|end| end > len)
+    {
+        return Err(corrupt(path));
+    }
+    file.seek(SeekFrom::Start(cd_offset))?;
+    let mut cd = vec![0; cd_size as usize];
+    file.read_exact(&mut cd)?;
+
+    let max_count = (cd.len() / ZIP_CENTRAL_DIRECTORY_HEADER_SIZE) as u64;
+    let mut entries = Vec::with_capacity(count.min(max_count) as usize);
+    let mut i = 0;
+    let mut read = 0;
+    while read < count {
+        let header = cd
+            .get(i..i + ZIP_CENTRAL_DIRECTORY_HEADER_SIZE)
+            .ok_or_else(|| corrupt(path))?;
+        if u32_le(header) != ZIP_CENTRAL_DIRECTORY_SIGNATURE {
+            bail!("{} has a corrupt central directory", path.display());
+        }
+        let method = u16_le(&header[10..]);
+        let crc32 = u32_le(&header[16..]);
+        let mut compressed_size = u32_le(&header[20..]) as u64;
+        let mut size = u32_le(&header[24..]) as u64;
+        let name_len = u16_le(&header[28..]) as usize;
+        let extra_len = u16_le(&header[30..]) as usize;
+        let comment_len = u16_le(&header[32..]) as usize;
+        let mut offset = u32_le(&header[42..]) as u64;
+
+        let name_start = i + ZIP_CENTRAL_DIRECTORY_HEADER_SIZE;
+        let (Some(name), Some(extra)) = (
+            cd.get(name_start..name_start + name_len),
+            cd.get(name_start + name_len..name_start + name_len + extra_len),
+        ) else {
+            bail!("{} has a corrupt central directory", path.display());
+        };
+
+        // ZIP64 entries store each value that didn't fit in an extra field, in this order
+        if let Some(mut values) = zip64_extra_field(extra) {
+            for value in [&mut size, &mut compressed_size, &mut offset] {
+                if *value == u32::MAX as u64 {
+                    let field = values.get(..8).ok_or_else(|| corrupt(path))?;
+                    *value = u64_le(field);
+                    values = &values[8..];
+                }
+            }
+        }
+
+        let name = String::from_utf8_lossy(name);
+        // Directories are stored as entries ending with a slash
+        if !name.ends_with('/') {
+            entries.push(ZipEntry {
+                entry: ArchiveEntry {
+                    path: PathBuf::from(name.as_ref()),
+                    crc32,
+                    size,
+                },
+                method,
+                compressed_size,
+                offset,
+            });
+        }
+        i = name_start + name_len + extra_len + comment_len;
+        read += 1;
+    }
+
+    Ok(entries)
+}
+
+/// Returns the data of the ZIP64 extra field among the extra fields of an entry, if any.
+fn zip64_extra_field(mut extra: &[u8]) -> Option<&[u8]> {
+    while extra.len() >= 4 {
+        let id = u16_le(extra);
+        let size = u16_le(&extra[2..]) as usize;
+        let data = extra.get(4..4 + size)?;
+        if id == ZIP64_EXTRA_FIELD {
+            return Some(data);
+        }
+        extra = &extra[4 + size..];
+    }
+    None
+}
+
+const SEVENZ_SIGNATURE: [u8; 6] = [b'7', b'z', 0xbc, 0xaf, 0x27, 0x1c];
+/// Signature header is 32 bytes, and all offsets are relative to its end.
+const SEVENZ_SIGNATURE_HEADER_SIZE: u64 = 32;
+/// How many times a header may be encoded. 7-Zip encodes it once.
+const SEVENZ_MAX_HEADER_ENCODINGS: usize = 4;
+/// Most input or output streams of the coders in a folder. 7-Zip allows 64.
+const SEVENZ_MAX_CODER_STREAMS: u64 = 64;
+
+/// Property IDs used in 7z headers.
+mod id {
+    pub const END: u8 = 0x00;
+    pub const HEADER: u8 = 0x01;
+    pub const ARCHIVE_PROPERTIES: u8 = 0x02;
+    pub const ADDITIONAL_STREAMS_INFO: u8 = 0x03;
+    pub const MAIN_STREAMS_INFO: u8 = 0x04;
+    pub const FILES_INFO: u8 = 0x05;
+    pub const PACK_INFO: u8 = 0x06;
+    pub const UNPACK_INFO: u8 = 0x07;
+    pub const SUBSTREAMS_INFO: u8 = 0x08;
+    pub const SIZE: u8 = 0x09;
+    pub const CRC: u8 = 0x0a;
+    pub const FOLDER: u8 = 0x0b;
+    pub const CODERS_UNPACK_SIZE: u8 = 0x0c;
+    pub const NUM_UNPACK_STREAM: u8 = 0x0d;
+    pub const EMPTY_STREAM: u8 = 0x0e;
+    pub const NAME: u8 = 0x11;
+    pub const ENCODED_HEADER: u8 = 0x17;
+}
+
+/// Lists the files in a 7z archive by reading its header. Only the header is decompressed, and
+/// only LZMA, LZMA2 and uncompressed headers are supported.
+pub fn sevenz_entries(path: &Path) -> Result<Vec<ArchiveEntry>> {
+    let mut file = File::open(path)?;
+    Ok(sevenz_header(path, &mut file)?.1)
+}
+
+/// Extracts the files in a 7z archive. Only folders with a single LZMA, LZMA2 or uncompressed
+/// coder are supported, which covers archives made with the default settings.
+fn extract_sevenz(path: &Path, dest: &Path) -> Result<()> {
+    let mut file = File::open(path)?;
+    let (streams, entries) = sevenz_header(path, &mut file)?;
+
+    // Entries are the files with a stream, so they line up with the streams and their CRCs
+    let mut entries = entries
+        .into_iter()
+        .zip(streams.streams.iter().map(|&(_, crc32)| crc32));
+    let mut pack_pos = streams.pack_pos;
+    let mut pack_sizes = streams.pack_sizes.iter();
+    for folder in &streams.folders {
+        let mut packed_size: u64 = 0;
+        for _ in 0..folder.num_packed_streams {
+            let size = pack_sizes.next().ok_or_else(|| corrupt(path))?;
+            packed_size = packed_size
+                .checked_add(*size)
+                .ok_or_else(|| corrupt(path))?;
+        }
+
+        let files = entries
+            .by_ref()
+            .take(folder.num_unpack_streams as usize)
+            .map(|(entry, crc32)| Ok((output_path(dest, &entry.path)?, entry.size, crc32)))
+            .collect::<Result<Vec<_>>>()?;
+        if !files.is_empty() {
+            let mut writer = FolderWriter::new(files);
+            decode_folder(&mut file, pack_pos, packed_size, folder, &mut writer)?;
+            writer.finish()?;
+        }
+        pack_pos = pack_pos
+            .checked_add(packed_size)
+            .ok_or_else(|| corrupt(path))?;
+    }
+    Ok(())
+}
+
+/// Reads the header of a 7z archive, decoding it first if it's compressed.
+fn sevenz_header(path: &Path, file: &mut File) -> Result<(StreamsInfo, Vec<ArchiveEntry>)> {
+    let mut signature_header = [0; SEVENZ_SIGNATURE_HEADER_SIZE as usize];
+    file.read_exact(&mut signature_header)?;
+    if signature_header[..6] != SEVENZ_SIGNATURE {
+        bail!("{} is not a 7z file", path.display());
+    }
+    let next_header_offset = u64_le(&signature_header[12..]);
+    let next_header_size = u64_le(&signature_header[20..]);
+
+    let len = file.metadata()?.len();
+    let header_start = SEVENZ_SIGNATURE_HEADER_SIZE
+        .checked_add(next_header_offset)
+        .ok_or_else(|| corrupt(path))?;
+    if next_header_size > MAX_HEADER_SIZE
+        || header_start
+            .checked_add(next_header_size)
+            .is_none_or(|end| end > len)
+    {
+        return Err(corrupt(path));
+    }
+    file.seek(SeekFrom::Start(header_start))?;
+    let mut header = vec![0; next_header_size as usize];
+    file.read_exact(&mut header)?;
+
+    for _ in 0..=SEVENZ_MAX_HEADER_ENCODINGS {
+        let mut reader = HeaderReader::new(&header);
+        match reader.byte()? {
+            id::HEADER => return reader.header(),
+            id::ENCODED_HEADER => {
+                let streams = reader.streams_info()?;
+                header = decode_header(file, &streams)?;
+            }
+            _ => bail!("{} has a corrupt header", path.display()),
+        }
+    }
+    bail!("{} has a corrupt header", path.display())
+}
+
+/// Decompresses an encoded 7z header, which is stored like a file in the archive.
+fn decode_header(file: &mut File, streams: &StreamsInfo) -> Result<Vec<u8>> {
+    let (Some(folder), Some(&packed_size)) = (streams.folders.first(), streams.pack_sizes.first())
+    else {
+        bail!("encoded header has no data");
+    };
+    if folder.unpack_size > MAX_HEADER_SIZE {
+        bail!("encoded header is too large: {} bytes", folder.unpack_size);
+    }
+
+    // Writing to a slice fails once it's full, so the header can't decode to more than its size
+    let mut header = vec![0; folder.unpack_size as usize];
+    let mut output = header.as_mut_slice();
+    decode_folder(file, streams.pack_pos, packed_size, folder, &mut output)?;
+    if !output.is_empty() {
+        bail!("unexpected end of encoded header");
+    }
+    if folder
+        .crc32
+        .is_some_and(|crc32| crc32 != crc32fast::hash(&header))
+    {
+        bail!("encoded header is corrupt: CRC32 mismatch");
+    }
+    Ok(header)
+}
+
+/// Decompresses a folder, whose packed data starts at `pack_pos`, into `output`.
+fn decode_folder(
+    file: &mut File,
+    pack_pos: u64,
+    packed_size: u64,
+    folder: &Folder,
+    output: &mut impl Write,
+) -> Result<()> {
+    let [coder] = folder.coders.as_slice() else {
+        bail!("unsupported compression: multiple coders");
+    };
+
+    let len = file.metadata()?.len();
+    let start = SEVENZ_SIGNATURE_HEADER_SIZE
+        .checked_add(pack_pos)
+        .ok_or_else(|| anyhow!("packed data is out of bounds"))?;
+    if start.checked_add(packed_size).is_none_or(|end| end > len) {
+        bail!("packed data is out of bounds");
+    }
+    file.seek(SeekFrom::Start(start))?;
+    let packed = file.take(packed_size);
+
+    match coder.id.as_slice() {
+        // Copy
+        [0x00] => {
+            io::copy(&mut BufReader::new(packed), output)?;
+        }
+        // LZMA
+        [0x03, 0x01, 0x01] => lzma_rs::lzma_decompress_with_options(
+            &mut BufReader::new(coder.properties.as_slice().chain(packed)),
+            output,
+            &Options {
+                unpacked_size: UnpackedSize::UseProvided(Some(folder.unpack_size)),
+                ..Default::default()
+            },
+        )?,
+        // LZMA2
+        [0x21] => lzma_rs::lzma2_decompress(&mut BufReader::new(packed), output)?,
+        id => bail!("unsupported compression: {:02x?}", id),
+    }
+
+    Ok(())
+}
+
+/// Writes the decompressed stream of a folder out to the files stored in it, in order.
+struct FolderWriter {
+    files: std::vec::IntoIter<(PathBuf, u64, Option<u32>)>,
+    /// The file being written and how many bytes it still needs.
+    current: Option<FolderFile>,
+}
+
+/// A file being written out of a folder.
+struct FolderFile {
+    path: PathBuf,
+    size: u64,
+    crc32: Option<u32>,
+    writer: CheckedWriter<BufWriter<File>>,
+}
+
+impl FolderFile {
+    fn create(path: PathBuf, size: u64, crc32: Option<u32>) -> io::Result<Self> {
+        Ok(Self {
+            writer: CheckedWriter::new(create_file(&path)?),
+            path,
+            size,
+            crc32,
+        })
+    }
+
+    fn remaining(&self) -> u64 {
+        self.size - self.writer.written
+    }
+
+    fn finish(self) -> Result<()> {
+        self.writer.finish(&self.path, self.size, self.crc32)
+    }
+}
+
+impl FolderWriter {
+    fn new(files: Vec<(PathBuf, u64, Option<u32>)>) -> Self {
+        Self {
+            files: files.into_iter(),
+            current: None,
+        }
+    }
+
+    /// Checks the last file and creates any remaining empty ones.
+    fn finish(mut self) -> Result<()> {
+        if let Some(file) = self.current.take() {
+            if file.remaining() > 0 {
+                bail!("unexpected end of compressed data");
+            }
+            file.finish()?;
+        }
+        for (path, size, crc32) in self.files {
+            if size > 0 {
+                bail!("unexpected end of compressed data");
+            }
+            FolderFile::create(path, size, crc32)?.finish()?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for FolderWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if let Some(file) = &mut self.current {
+                let remaining = file.remaining();
+                if remaining > 0 {
+                    let n = remaining.min(buf.len() as u64) as usize;
+                    file.writer.write_all(&buf[..n])?;
+                    return Ok(n);
+                }
+            }
+            if let Some(file) = self.current.take() {
+                file.finish().map_err(io::Error::other)?;
+            }
+            match self.files.next() {
+                Some((path, size, crc32)) => {
+                    self.current = Some(FolderFile::create(path, size, crc32)?)
+                }
+                // Anything past the last file is padding
+                None => return Ok(buf.len()),
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.current {
+            Some(file) => file.writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct StreamsInfo {
+    pack_pos: u64,
+    pack_sizes: Vec<u64>,
+    folders: Vec<Folder>,
+    /// Unpacked size and CRC of every file stored in the folders, in order.
+    streams: Vec<(u64, Option<u32>)>,
+}
+
+#[derive(Debug)]
+struct Folder {
+    coders: Vec<Coder>,
+    num_packed_streams: u64,
+    unpack_size: u64,
+    crc32: Option<u32>,
+    num_unpack_streams: u64,
+}
+
+#[derive(Debug)]
+struct Coder {
+    id: Vec<u8>,
+    properties: Vec<u8>,
+}
+
+/// Reads the structures of a 7z header.
+struct HeaderReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> HeaderReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .pos
+            .checked_add(n)
+            .and_then(|end| self.data.get(self.pos..end))
+            .ok_or_else(|| anyhow!("unexpected end of 7z header"))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn expect(&mut self, id: u8) -> Result<()> {
+        let byte = self.byte()?;
+        if byte != id {
+            bail!("expected property {:#x}, found {:#x}", id, byte);
+        }
+        Ok(())
+    }
+
+    /// Reads a variable-length number. The number of leading 1 bits in the first byte is the
+    /// number of extra bytes.
+    fn number(&mut self) -> Result<u64> {
+        let first = self.byte()?;
+        let mut mask = 0x80;
+        let mut value = 0;
+        for i in 0..8 {
+            if first & mask == 0 {
+                let high = (first & (mask - 1)) as u64;
+                return Ok(value | (high << (8 * i)));
+            }
+            value |= (self.byte()? as u64) << (8 * i);
+            mask >>= 1;
+        }
+        Ok(value)
+    }
+
+    /// Reads a number of bytes. There can't be more than the header has, which keeps corrupt
+    /// sizes from allocating huge vectors.
+    fn count(&mut self) -> Result<usize> {
+        let n = self.number()?;
+        if n > self.data.len() as u64 {
+            bail!("corrupt 7z header: {} bytes", n);
+        }
+        Ok(n as usize)
+    }
+
+    /// Reads a number of files, folders or streams, which is also limited to `MAX_ENTRIES`.
+    fn entries(&mut self) -> Result<usize> {
+        let n = self.count()?;
+        if n as u64 > MAX_ENTRIES {
+            bail!("corrupt 7z header: {} items", n);
+        }
+        Ok(n)
+    }
+
+    /// Reads a bit vector, or a byte saying that all items are defined followed by nothing.
+    fn defined(&mut self, n: usize) -> Result<Vec<bool>> {
+        if n > self.data.len().saturating_mul(8) {
+            bail!("corrupt 7z header: {} items", n);
+        }
+        if self.byte()? != 0 {
+            Ok(vec![true; n])
+        } else {
+            Ok(bits(self.bytes(n.div_ceil(8))?, n))
+        }
+    }
+
+    fn digests(&mut self, n: usize) -> Result<Vec<Option<u32>>> {
+        self.defined(n)?
+            .into_iter()
+            .map(|defined| {
+                Ok(if defined {
+                    Some(u32_le(self.bytes(4)?))
+                } else {
+                    None
+                })
+            })
+            .collect()
+    }
+
+    fn header(&mut self) -> Result<(StreamsInfo, Vec<ArchiveEntry>)> {
+        let mut streams = StreamsInfo::default();
+        let mut entries = Vec::new();
+        loop {
+            match self.byte()? {
+                id::END => break,
+                id::ARCHIVE_PROPERTIES => loop {
+                    if self.byte()? == id::END {
+                        break;
+                    }
+                    let size = self.count()?;
+                    self.bytes(size)?;
+                },
+                id::ADDITIONAL_STREAMS_INFO => {
+                    self.streams_info()?;
+                }
+                id::MAIN_STREAMS_INFO => streams = self.streams_info()?,
+                id::FILES_INFO => entries = self.files_info(&streams)?,
+                id => bail!("unexpected property {:#x} in 7z header", id),
+            }
+        }
+        Ok((streams, entries))
+    }
+
+    fn streams_info(&mut self) -> Result<StreamsInfo> {
+        let mut info = StreamsInfo::default();
+        let mut has_substreams = false;
+        loop {
+            match self.byte()? {
+                id::END => break,
+                id::PACK_INFO => {
+                    info.pack_pos = self.number()?;
+                    let n = self.entries()?;
+                    loop {
+                        match self.byte()? {
+                            id::END => break,
+                            id::SIZE => {
+                                info.pack_sizes =
+                                    (0..n).map(|_| self.number()).collect::<Result<_>>()?
+                            }
+                            id::CRC => {
+                                self.digests(n)?;
+                            }
+                            id => bail!("unexpected property {:#x} in pack info", id),
+                        }
+                    }
+                }
+                id::UNPACK_INFO => info.folders = self.unpack_info()?,
+                id::SUBSTREAMS_INFO => {
+                    info.streams = self.substreams_info(&mut info.folders)?;
+                    has_substreams = true;
+                }
+                id => bail!("unexpected property {:#x} in streams info", id),
+            }
+        }
+        if !has_substreams {
+            info.streams = info
+                .folders
+                .iter()
+                .map(|f| (f.unpack_size, f.crc32))
+                .collect();
+        }
+        Ok(info)
+    }
+
+    fn unpack_info(&mut self) -> Result<Vec<Folder>> {
+        self.expect(id::FOLDER)?;
+        let n = self.entries()?;
+        if self.byte()? != 0 {
+            bail!("external folders are not supported");
+        }
+
+        let mut folders = Vec::with_capacity(n);
+        let mut outputs = Vec::with_capacity(n);
+        for _ in 0..n {
+            let mut coders = Vec::new();
+            let mut total_in: u64 = 0;
+            let mut total_out: u64 = 0;
+            for _ in 0..self.count()? {
+                let flags = self.byte()?;
+                let id = self.bytes((flags & 0x0f) as usize)?.to_vec();
+                let (num_in, num_out) = if flags & 0x10 != 0 {
+                    (self.number()?, self.number()?)
+                } else {
+                    (1, 1)
+                };
+                total_in = total_in.saturating_add(num_in);
+                total_out = total_out.saturating_add(num_out);
+                if total_in.max(total_out) > SEVENZ_MAX_CODER_STREAMS {
+                    bail!("corrupt 7z header: too many coder streams");
+                }
+                let properties = if flags & 0x20 != 0 {
+                    let size = self.count()?;
+                    self.bytes(size)?.to_vec()
+                } else {
+                    Vec::new()
+                };
+                if flags & 0x80 != 0 {
+                    bail!("alternative coder methods are not supported");
+                }
+                coders.push(Coder { id, properties });
+            }
+
+            let num_bind_pairs = total_out.saturating_sub(1);
+            let mut bound = Vec::with_capacity(num_bind_pairs as usize);
+            for _ in 0..num_bind_pairs {
+                self.number()?;
+                bound.push(self.number()?);
+            }
+            let num_packed_streams = total_in.saturating_sub(num_bind_pairs);
+            if num_packed_streams > 1 {
+                for _ in 0..num_packed_streams {
+                    self.number()?;
+                }
+            }
+
+            // The folder's output is the one output that isn't bound to another coder's input
+            let main = (0..total_out).find(|i| !bound.contains(i)).unwrap_or(0);
+            outputs.push((total_out, main));
+            folders.push(Folder {
+                coders,
+                num_packed_streams,
+                unpack_size: 0,
+                crc32: None,
+                num_unpack_streams: 1,
+            });
+        }
+
+        self.expect(id::CODERS_UNPACK_SIZE)?;
+        for (folder, (total_out, main)) in folders.iter_mut().zip(outputs) {
+            for i in 0..total_out {
+                let size = self.number()?;
+                if i == main {
+                    folder.unpack_size = size;
+                }
+            }
+        }
+
+        loop {
+            match self.byte()? {
+                id::END => break,
+                id::CRC => {
+                    for (folder, crc32) in folders.iter_mut().zip(self.digests(n)?) {
+                        folder.crc32 = crc32;
+                    }
+                }
+                id => bail!("unexpected property {:#x} in unpack info", id),
+            }
+        }
+
+        Ok(folders)
+    }
+
+    fn substreams_info(&mut self, folders: &mut [Folder]) -> Result<Vec<(u64, Option<u32>)>> {
+        let mut property = self.byte()?;
+        if property == id::NUM_UNPACK_STREAM {
+            let mut total: u64 = 0;
+            for folder in folders.iter_mut() {
+                folder.num_unpack_streams = self.entries()? as u64;
+                total += folder.num_unpack_streams;
+                if total > MAX_ENTRIES {
+                    bail!("corrupt 7z header: {} streams", total);
+                }
+            }
+            property = self.byte()?;
+        }
+
+        let mut sizes = Vec::new();
+        for folder in folders.iter() {
+            if folder.num_unpack_streams == 0 {
+                continue;
+            }
+            let mut sum: u64 = 0;
+            if property == id::SIZE {
+                for _ in 1..folder.num_unpack_streams {
+                    let size = self.number()?;
+                    sizes.push(size);
+                    sum = sum.saturating_add(size);
+                }
+            }
+            sizes.push(folder.unpack_size.saturating_sub(sum));
+        }
+        if property == id::SIZE {
+            property = self.byte()?;
+        }
+
+        // Folders with a single stream have their CRC stored with the folder
+        let has_folder_crc = |f: &Folder| f.num_unpack_streams == 1 && f.crc32.is_some();
+        let num_unknown = folders
+            .iter()
+            .filter(|f| !has_folder_crc(f))
+            .map(|f| f.num_unpack_streams as usize)
+            .sum();
+        let mut digests = if property == id::CRC {
+            let digests = self.digests(num_unknown)?;
+            property = self.byte()?;
+            digests
+        } else {
+            vec![None; num_unknown]
+        }
+        .into_iter();
+        if property != id::END {
+            bail!("unexpected property {:#x} in substreams info", property);
+        }
+
+        let mut crcs = Vec::with_capacity(sizes.len());
+        for folder in folders.iter() {
+            if has_folder_crc(folder) {
+                crcs.push(folder.crc32);
+            } else {
+                for _ in 0..folder.num_unpack_streams {
+                    crcs.push(digests.next().flatten());
+                }
+            }
+        }
+
+        Ok(sizes.into_iter().zip(crcs).collect())
+    }
+
+    fn files_info(&mut self, streams: &StreamsInfo) -> Result<Vec<ArchiveEntry>> {
+        let n = self.entries()?;
+        let mut empty_stream = vec![false; n];
+        let mut names = Vec::with_capacity(n);
+        loop {
+            let property = self.number()?;
+            if property == id::END as u64 {
+                break;
+            }
+            let size = self.count()?;
+            let data = self.bytes(size)?;
+            match property as u8 {
+                id::EMPTY_STREAM => empty_stream = bits(data, n),
+                id::NAME => {
+                    let Some((0, data)) = data.split_first() else {
+                        bail!("external file names are not supported");
+                    };
+                    let chars: Vec<_> = data
+                        .chunks_exact(2)
+                        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                        .collect();
+                    names = chars
+                        .split(|&c| c == 0)
+                        .take(n)
+                        .map(String::from_utf16_lossy)
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+
+        // Files without a stream are directories or empty files
+        let mut streams = streams.streams.iter();
+        Ok(names
+            .into_iter()
+            .zip(empty_stream)
+            .filter(|(_, empty)| !empty)
+            .filter_map(|(name, _)| {
+                let &(size, crc32) = streams.next()?;
+                Some(ArchiveEntry {
+                    path: PathBuf::from(name),
+                    crc32: crc32.unwrap_or_default(),
+                    size,
+                })
+            })
+            .collect())
+    }
+}
+
+/// Reads `n` bits, most significant bit first.
+fn bits(data: &[u8], n: usize) -> Vec<bool> {
+    (0..n)
+        .map(|i| data.get(i / 8).is_some_and(|b| b & (0x80 >> (i % 8)) != 0))
+        .collect()
+}
+
+fn u16_le(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn u32_le(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn u64_le(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+/// Computes the CRC32 (IEEE) checksum of everything read from `reader`.
+pub fn crc32(mut reader: impl Read) -> Result<u32> {
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(&b""[..]).unwrap(), 0);
+        assert_eq!(crc32(&b"123456789"[..]).unwrap(), 0xcbf43926);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let path = std::env::temp_dir().join("allium-test-fingerprint.zip");
+        fs::write(&path, b"first").unwrap();
+        let file = File::options().write(true).open(&path).unwrap();
+        file.set_modified(UNIX_EPOCH + std::time::Duration::from_secs(1_000_000))
+            .unwrap();
+        let first = fingerprint(&path).unwrap();
+        assert!(first <= i64::MAX as u64);
+
+        // Replaced by a file of the same size
+        fs::write(&path, b"other").unwrap();
+        file.set_modified(UNIX_EPOCH + std::time::Duration::from_secs(2_000_000))
+            .unwrap();
+        assert_ne!(fingerprint(&path).unwrap(), first);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    /// Builds a 7z archive storing "a.gb" and "b.gbc" uncompressed, and an empty directory.
+    fn sevenz_archive(encode_header: bool) -> Vec<u8> {
+        let data = b"helloworld!";
+
+        let mut header = vec![
+            id::HEADER,
+            id::MAIN_STREAMS_INFO,
+            id::PACK_INFO,
+            0,
+            1,
+            id::SIZE,
+            11,
+            id::END,
+            id::UNPACK_INFO,
+            id::FOLDER,
+            1,
+            0,
+            1,
+            0x01,
+            0x00,
+            id::CODERS_UNPACK_SIZE,
+            11,
+            id::END,
+            id::SUBSTREAMS_INFO,
+            id::NUM_UNPACK_STREAM,
+            2,
+            id::SIZE,
+            5,
+            id::CRC,
+            1,
+        ];
+        header.extend(crc32(&b"hello"[..]).unwrap().to_le_bytes());
+        header.extend(crc32(&b"world!"[..]).unwrap().to_le_bytes());
+        header.extend([
+            id::END,
+            id::END,
+            id::FILES_INFO,
+            3,
+            id::EMPTY_STREAM,
+            1,
+            0b0010_0000,
+        ]);
+        let names: Vec<u8> = "a.gb\0b.gbc\0dir\0"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        header.extend([id::NAME, names.len() as u8 + 1, 0]);
+        header.extend(names);
+        header.extend([id::END, id::END]);
+
+        let mut packed = data.to_vec();
+        if encode_header {
+            let len = header.len() as u8;
+            packed.extend(header);
+            header = vec![
+                id::ENCODED_HEADER,
+                id::PACK_INFO,
+                11,
+                1,
+                id::SIZE,
+                len,
+                id::END,
+                id::UNPACK_INFO,
+                id::FOLDER,
+                1,
+                0,
+                1,
+                0x01,
+                0x00,
+                id::CODERS_UNPACK_SIZE,
+                len,
+                id::END,
+                id::END,
+            ];
+        }
+
+        let mut archive = SEVENZ_SIGNATURE.to_vec();
+        archive.extend([0, 4, 0, 0, 0, 0]);
+        archive.extend((packed.len() as u64).to_le_bytes());
+        archive.extend((header.len() as u64).to_le_bytes());
+        archive.extend([0, 0, 0, 0]);
+        archive.extend(packed);
+        archive.extend(header);
+        archive
+    }
+
+    #[test]
+    fn test_sevenz_entries() {
+        for encode_header in [false, true] {
+            let path = std::env::temp_dir().join(format!("allium-test-{}.7z", encode_header));
+            fs::write(&path, sevenz_archive(encode_header)).unwrap();
+            let entries = entries(&path);
+            fs::remove_file(&path).unwrap();
+
+            assert_eq!(
+                entries.unwrap(),
+                vec![
+                    ArchiveEntry {
+                        path: PathBuf::from("a.gb"),
+                        crc32: crc32(&b"hello"[..]).unwrap(),
+                        size: 5,
+                    },
+                    ArchiveEntry {
+                        path: PathBuf::from("b.gbc"),
+                        crc32: crc32(&b"world!"[..]).unwrap(),
+                        size: 6,
+                    },
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_extract_sevenz() {
+        let dir = std::env::temp_dir().join("allium-test-extract");
+        let path = dir.join("game.7z");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, sevenz_archive(true)).unwrap();
+        let result = extract(&path, &dir.join("out"));
+        let a = fs::read(dir.join("out/a.gb"));
+        let b = fs::read(dir.join("out/b.gbc"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        result.unwrap();
+        assert_eq!(a.unwrap(), b"hello");
+        assert_eq!(b.unwrap(), b"world!");
+    }
+
+    /// Builds a zip archive storing "a.gb" uncompressed and "b.gbc" deflated, optionally in the
+    /// ZIP64 format.
+    fn zip_archive(zip64: bool) -> Vec<u8> {
+        let mut deflated = flate2::write::DeflateEncoder::new(Vec::new(), Default::default());
+        deflated.write_all(b"world!").unwrap();
+        let deflated = deflated.finish().unwrap();
+        let files: [(&str, u16, &[u8], &[u8]); 2] = [
+            ("a.gb", 0, b"hello", b"hello"),
+            ("b.gbc", 8, b"world!", &deflated),
+        ];
+
+        let mut archive = Vec::new();
+        let mut cd = Vec::new();
+        for (name, method, data, packed) in files {
+            let crc32 = crc32(data).unwrap();
+            let offset = archive.len() as u32;
+
+            archive.extend(ZIP_LOCAL_HEADER_SIGNATURE.to_le_bytes());
+            archive.extend([20, 0, 0, 0]);
+            archive.extend(method.to_le_bytes());
+            archive.extend([0; 4]);
+            archive.extend(crc32.to_le_bytes());
+            archive.extend((packed.len() as u32).to_le_bytes());
+            archive.extend((data.len() as u32).to_le_bytes());
+            archive.extend((name.len() as u16).to_le_bytes());
+            archive.extend([0, 0]);
+            archive.extend(name.as_bytes());
+            archive.extend(packed);
+
+            let (sizes, offset, extra) = if zip64 {
+                let mut extra = ZIP64_EXTRA_FIELD.to_le_bytes().to_vec();
+                extra.extend(24u16.to_le_bytes());
+                extra.extend((data.len() as u64).to_le_bytes());
+                extra.extend((packed.len() as u64).to_le_bytes());
+                extra.extend((offset as u64).to_le_bytes());
+                ([u32::MAX; 2], u32::MAX, extra)
+            } else {
+                ([packed.len() as u32, data.len() as u32], offset, Vec::new())
+            };
+            cd.extend(ZIP_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+            cd.extend([45, 0, 45, 0, 0, 0]);
+            cd.extend(method.to_le_bytes());
+            cd.extend([0; 4]);
+            cd.extend(crc32.to_le_bytes());
+            cd.extend(sizes[0].to_le_bytes());
+            cd.extend(sizes[1].to_le_bytes());
+            cd.extend((name.len() as u16).to_le_bytes());
+            cd.extend((extra.len() as u16).to_le_bytes());
+            cd.extend([0; 10]);
+            cd.extend(offset.to_le_bytes());
+            cd.extend(name.as_bytes());
+            cd.extend(extra);
+        }
+
+        let cd_offset = archive.len() as u64;
+        let cd_size = cd.len() as u64;
+        archive.extend(cd);
+        if zip64 {
+            let record_offset = archive.len() as u64;
+            archive.extend(ZIP64_EOCD_SIGNATURE.to_le_bytes());
+            archive.extend(44u64.to_le_bytes());
+            archive.extend([45, 0, 45, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            archive.extend(2u64.to_le_bytes());
+            archive.extend(2u64.to_le_bytes());
+            archive.extend(cd_size.to_le_bytes());
+            archive.extend(cd_offset.to_le_bytes());
+
+            archive.extend(ZIP64_EOCD_LOCATOR_SIGNATURE.to_le_bytes());
+            archive.extend([0; 4]);
+            archive.extend(record_offset.to_le_bytes());
+            archive.extend(1u32.to_le_bytes());
+
+            archive.extend(ZIP_EOCD_SIGNATURE.to_le_bytes());
+            archive.extend([0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+            archive.extend([0xff; 8]);
+        } else {
+            archive.extend(ZIP_EOCD_SIGNATURE.to_le_bytes());
+            archive.extend([0, 0, 0, 0, 2, 0, 2, 0]);
+            archive.extend((cd_size as u32).to_le_bytes());
+            archive.extend((cd_offset as u32).to_le_bytes());
+        }
+        archive.extend([0, 0]);
+        archive
+    }
+
+    #[test]
+    fn test_zip() {
+        for zip64 in [false, true] {
+            let dir = std::env::temp_dir().join(format!("allium-test-zip-{}", zip64));
+            let path = dir.join("game.zip");
+            fs::create_dir_all(&dir).unwrap();
+            let mut archive = zip_archive(zip64);
+            fs::write(&path, &archive).unwrap();
+            let entries = entries(&path);
+            let result = extract(&path, &dir.join("out"));
+            let a = fs::read(dir.join("out/a.gb"));
+            let b = fs::read(dir.join("out/b.gbc"));
+
+            // A damaged file fails its CRC check rather than being extracted as is
+            let data = archive.windows(5).position(|w| w == b"hello").unwrap();
+            archive[data] = b'j';
+            fs::write(&path, &archive).unwrap();
+            let damaged = extract(&path, &dir.join("damaged"));
+            fs::remove_dir_all(&dir).unwrap();
+
+            assert_eq!(
+                entries.unwrap(),
+                vec![
+                    ArchiveEntry {
+                        path: PathBuf::from("a.gb"),
+                        crc32: crc32(&b"hello"[..]).unwrap(),
+                        size: 5,
+                    },
+                    ArchiveEntry {
+                        path: PathBuf::from("b.gbc"),
+                        crc32: crc32(&b"world!"[..]).unwrap(),
+                        size: 6,
+                    },
+                ]
+            );
+            result.unwrap();
+            assert_eq!(a.unwrap(), b"hello");
+            assert_eq!(b.unwrap(), b"world!");
+            assert!(damaged.is_err());
+        }
+    }
+
+    /// Sizes, offsets and counts are read from the archive, and must be checked before they are
+    /// seeked to or allocated for.
+    #[test]
+    fn test_huge_sizes() {
+        let dir = std::env::temp_dir().join("allium-test-huge");
+        let path = dir.join("game.zip");
+        fs::create_dir_all(&dir).unwrap();
+        let zip64 = zip_archive(true);
+        // The ZIP64 extra field of the first file holds its size, compressed size and offset
+        let extra = zip64
+            .windows(4)
+            .position(|w| w == [0x01, 0x00, 24, 0x00])
+            .unwrap()
+            + 4;
+
+        // An entry claiming to be far larger than its data is listed, but not extracted
+        let mut archive = zip64.clone();
+        archive[extra..extra + 8].copy_from_slice(&(u64::MAX / 2).to_le_bytes());
+        fs::write(&path, &archive).unwrap();
+        let listed = entries(&path);
+        let extracted = extract(&path, &dir.join("out"));
+        assert_eq!(listed.unwrap()[0].size, u64::MAX / 2);
+        assert!(extracted.is_err());
+
+        // As is one whose data would be past the end of the archive
+        let mut archive = zip64.clone();
+        archive[extra + 16..extra + 24].copy_from_slice(&u64::MAX.to_le_bytes());
+        fs::write(&path, &archive).unwrap();
+        assert!(extract(&path, &dir.join("out")).is_err());
+
+        // Archives claiming more files than they could hold aren't opened
+        let mut archive = zip64.clone();
+        let record = archive
+            .windows(4)
+            .position(|w| w == ZIP64_EOCD_SIGNATURE.to_le_bytes())
+            .unwrap();
+        let count = record + 32;
+        archive[count..count + 8].copy_from_slice(&(MAX_ENTRIES + 1).to_le_bytes());
+        fs::write(&path, &archive).unwrap();
+        assert!(entries(&path).is_err());
+
+        let path = dir.join("game.7z");
+        let mut archive = sevenz_archive(false);
+        archive[20..28].copy_from_slice(&u64::MAX.to_le_bytes());
+        fs::write(&path, &archive).unwrap();
+        assert!(entries(&path).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let huge = [0xff; 9];
+        for header in [
+            [&[id::FILES_INFO][..], &huge].concat(),
+            [&[id::FILES_INFO, 0xc1, 0xa1, 0x86][..], &[0; 200_000]].concat(),
+            [
+                &[id::MAIN_STREAMS_INFO, id::UNPACK_INFO, id::FOLDER][..],
+                &huge,
+            ]
+            .concat(),
+            [
+                &[
+                    id::MAIN_STREAMS_INFO,
+                    id::UNPACK_INFO,
+                    id::FOLDER,
+                    1,
+                    0,
+                    1,
+                    0x11,
+                ][..],
+                &[0x00],
+                &huge,
+                &[1],
+            ]
+            .concat(),
+        ] {
+            assert!(HeaderReader::new(&header).header().is_err());
+        }
+    }
+
+    /// Truncated archives and archives with any one byte overwritten must fail to open or
+    /// extract, rather than panic or allocate whatever sizes they claim.
+    #[test]
+    fn test_corrupt_archives() {
+        let dir = std::env::temp_dir().join("allium-test-corrupt");
+        fs::create_dir_all(&dir).unwrap();
+        let archives = [
+            ("zip", zip_archive(false)),
+            ("zip", zip_archive(true)),
+            ("7z", sevenz_archive(false)),
+            ("7z", sevenz_archive(true)),
+        ];
+        for (extension, archive) in archives {
+            let path = dir.join(format!("game.{}", extension));
+            let mut corrupted: Vec<Vec<u8>> = (0..archive.len())
+                .map(|len| archive[..len].to_vec())
+                .collect();
+            for i in 0..archive.len() {
+                for byte in [0x00, 0x7f, 0xff] {
+                    let mut archive = archive.clone();
+                    archive[i] = byte;
+                    corrupted.push(archive);
+                }
+            }
+            for archive in corrupted {
+                fs::write(&path, &archive).unwrap();
+                let _ = entries(&path);
+                let _ = extract(&path, &dir.join("out"));
+            }
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -3,14 +3,24 @@ use std::{collections::HashMap, path::Path};
 
 use anyhow::{anyhow, bail, Context, Result};
 use common::command::Command;
-use common::database::Database;
+use common::database::{ArchiveEntry, Database};
 use common::game_info::GameInfo;
 use serde::Deserialize;
 
-use common::constants::{ALLIUM_CONFIG_CONSOLES, ALLIUM_RETROARCH};
-use log::{debug, trace};
+use common::constants::{
+    ALLIUM_CONFIG_ARCADE, ALLIUM_CONFIG_ARCADE_DATS, ALLIUM_CONFIG_ARCADE_USER,
+    ALLIUM_CONFIG_CONSOLES, ALLIUM_RETROARCH, ALLIUM_SAVES_DIR, ALLIUM_STATES_DIR,
+};
+use common::locale::Locale;
+use log::{debug, error, trace, warn};
+use tokio::sync::mpsc::Sender;
 
+use crate::arcade::{ArcadeSet, ArcadeSets};
+use crate::archive;
 use crate::entry::game::Game;
+use crate::entry::multi_disc::DISC_EXTENSIONS;
+use crate::extract_cache::ExtractCache;
+use crate::patch;
 
 #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
 pub struct Console {
@@ -34,18 +44,38 @@ pub struct Console {
     /// e.g. "Doukutsu.exe" for NXEngine
     #[serde(default)]
     pub file_name: Vec<String>,
+    /// Name of the libretro database (without .rdb) to look up game metadata in.
+    /// e.g. "Nintendo - Game Boy Advance"
+    #[serde(default)]
+    pub rdb: Option<String>,
+    /// Whether the cores can load archives containing multiple files, e.g. arcade romsets.
+    /// Otherwise, the game file inside the archive is launched directly.
+    #[serde(default)]
+    pub multi_file_archives: bool,
+    /// Whether games are MAME or FBNeo romsets, which are named after their set in arcade.toml.
+    #[serde(default)]
+    pub arcade: bool,
 }
 
 #[derive(Debug, Deserialize)]
 struct ConsoleConfig {
+    /// Glob patterns of files and directories to leave out of every listing.
+    #[serde(default)]
+    ignore: Vec<String>,
     cores: HashMap<String, String>,
+    /// Archive extensions that each core can load. Cores that aren't listed can load any archive.
+    #[serde(default)]
+    archive_formats: HashMap<String, Vec<String>>,
     consoles: Vec<Console>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ConsoleMapper {
+    ignore: Vec<String>,
     cores: HashMap<String, String>,
+    archive_formats: HashMap<String, Vec<String>>,
     consoles: Vec<Console>,
+    arcade_sets: ArcadeSets,
 }
 
 impl Default for ConsoleMapper {
@@ -57,8 +87,11 @@ impl Default for ConsoleMapper {
 impl ConsoleMapper {
     pub fn new() -> ConsoleMapper {
         ConsoleMapper {
+            ignore: Vec::new(),
             cores: HashMap::new(),
+            archive_formats: HashMap::new(),
             consoles: Vec::new(),
+            arcade_sets: ArcadeSets::default(),
         }
     }
 
@@ -73,9 +106,31 @@ impl ConsoleMapper {
         let config: ConsoleConfig =
             toml::from_str(&config).context("Failed to parse consoles.toml.")?;
 
+        self.ignore = config.ignore;
         self.cores = config.cores;
+        self.archive_formats = config.archive_formats;
         self.consoles = config.consoles;
 
+        // DAT files are loaded after the bundled sets, so that they can complete them
+        let mut dats: Vec<PathBuf> = std::fs::read_dir(&*ALLIUM_CONFIG_ARCADE_DATS)
+            .map(|dir| {
+                dir.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "dat"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        dats.sort();
+        let paths: Vec<&Path> = std::iter::once(ALLIUM_CONFIG_ARCADE.as_path())
+            .chain(dats.iter().map(PathBuf::as_path))
+            .chain(std::iter::once(ALLIUM_CONFIG_ARCADE_USER.as_path()))
+            .collect();
+
+        // Games are still listed by file name without the arcade sets
+        match ArcadeSets::load(&paths) {
+            Ok(sets) => self.arcade_sets = sets,
+            Err(e) => warn!("Failed to load arcade sets: {}", e),
+        }
+
         Ok(())
     }
 
@@ -94,8 +149,59 @@ impl ConsoleMapper {
         None
     }
 
-    /// Returns a console that this path maps to, or none.
-    pub fn get_console(&self, path: &Path) -> Option<&Console> {
+    /// Returns a console that this path maps to, or none. Archives that can't be mapped by their
+    /// name or directory are mapped by the files inside them, and patches by the ROM they apply to.
+    pub fn get_console(&self, database: &Database, path: &Path) -> Option<&Console> {
+        if patch::is_patch(path) {
+            if let Some(rom) = patch::find_rom(path) {
+                return self.get_console(database, &rom);
+            }
+        }
+
+        if let Some(console) = self.get_console_by_name(path) {
+            return Some(console);
+        }
+
+        let mut parent = Some(path);
+        while let Some(path) = parent {
+            trace!("path: {:?}", path);
+            if let Some(filename) = path.file_name().and_then(std::ffi::OsStr::to_str) {
+                let console = self.consoles.iter().find(|core| {
+                    core.patterns.iter().any(|pattern| {
+                        filename == pattern || filename.contains(&format!("({})", pattern))
+                    })
+                });
+                if console.is_some() {
+                    return console;
+                }
+            }
+            parent = path.parent();
+        }
+
+        if archive::is_archive(path) && path.is_file() {
+            match archive::cached_entries(database, path) {
+                Ok(entries) => {
+                    return entries
+                        .iter()
+                        .find_map(|entry| self.get_console_by_name(&entry.path))
+                }
+                Err(e) => warn!("Failed to inspect archive {}: {}", path.display(), e),
+            }
+        }
+
+        None
+    }
+
+    /// Returns the romset of a game, if its console is an arcade one and the set is known.
+    pub fn arcade_set(&self, database: &Database, path: &Path) -> Option<&ArcadeSet> {
+        if !self.get_console(database, path)?.arcade {
+            return None;
+        }
+        self.arcade_sets.get(path.file_stem()?.to_str()?)
+    }
+
+    /// Returns a console that matches the file name or extensions of the path, or none.
+    fn get_console_by_name(&self, path: &Path) -> Option<&Console> {
         let path_lowercase = path.as_os_str().to_ascii_lowercase();
 
         if let Some(name) = path.file_name().and_then(std::ffi::OsStr::to_str) {
@@ -120,26 +226,92 @@ impl ConsoleMapper {
             }
         }
 
-        let mut parent = Some(path);
-        while let Some(path) = parent {
-            trace!("path: {:?}", path);
-            if let Some(filename) = path.file_name().and_then(std::ffi::OsStr::to_str) {
-                let console = self.consoles.iter().find(|core| {
-                    core.patterns.iter().any(|pattern| {
-                        filename == pattern || filename.contains(&format!("({})", pattern))
-                    })
-                });
-                if console.is_some() {
-                    return console;
-                }
-            }
-            parent = path.parent();
+        None
+    }
+
+    /// Returns the file to launch inside an archive containing multiple files, for consoles whose
+    /// cores can't load those. e.g. the .cue in a zip containing a .cue and its .bin tracks.
+    fn get_archive_entry(
+        &self,
+        database: &Database,
+        console: &Console,
+        path: &Path,
+    ) -> Result<Option<PathBuf>> {
+        if console.multi_file_archives || !archive::is_archive(path) {
+            return Ok(None);
         }
 
-        None
+        let entries = archive::cached_entries(database, path)?;
+        if entries.len() <= 1 {
+            return Ok(None);
+        }
+
+        let extension = |entry: &&ArchiveEntry| {
+            entry
+                .path
+                .extension()
+                .and_then(std::ffi::OsStr::to_str)
+                .map(str::to_ascii_lowercase)
+                .unwrap_or_default()
+        };
+        let entry = console
+            .extensions
+            .iter()
+            .map(String::as_str)
+            .chain(DISC_EXTENSIONS)
+            .find_map(|ext| entries.iter().find(|e| extension(e) == ext));
+        match entry {
+            Some(entry) => Ok(Some(entry.path.clone())),
+            None => bail!(
+                "{} contains multiple files, which {} can't load.",
+                path.display(),
+                console.name
+            ),
+        }
     }
 
-    pub fn launch_game(&self, database: &Database, game: &mut Game) -> Result<Option<Command>> {
+    /// Finds what extracting an archived game needs from the database, so that it can be extracted
+    /// off the UI thread.
+    fn extraction(
+        &self,
+        database: &Database,
+        console: &Console,
+        path: &Path,
+    ) -> Result<Extraction> {
+        let entries = archive::cached_entries(database, path)?;
+        let entry = match self.get_archive_entry(database, console, path)? {
+            Some(entry) => entry,
+            None => entries
+                .first()
+                .map(|e| e.path.clone())
+                .ok_or_else(|| anyhow!("{} is empty", path.display()))?,
+        };
+        Ok(Extraction {
+            archive: path.to_path_buf(),
+            entry,
+            size: entries
+                .iter()
+                .fold(0u64, |size, e| size.saturating_add(e.size)),
+        })
+    }
+
+    /// Returns true if the core can't load the archive, so it has to be extracted first.
+    fn needs_extraction(&self, console: &Console, core: &str, path: &Path) -> bool {
+        if console.multi_file_archives || !archive::is_archive(path) {
+            return false;
+        }
+        let Some(formats) = self.archive_formats.get(core) else {
+            return false;
+        };
+        let extension = path
+            .extension()
+            .and_then(std::ffi::OsStr::to_str)
+            .unwrap_or_default();
+        !formats.iter().any(|f| f.eq_ignore_ascii_case(extension))
+    }
+
+    /// Returns how to launch the game, or None if no console plays it.
+    pub fn launch_game(&self, database: &Database, game: &mut Game) -> Result<Option<Launch>> {
         if !game.path.exists() {
             if let Some(old) = Game::resync(&mut game.path)? {
                 database.update_game_path(&old, &game.path)?;
@@ -149,37 +321,108 @@ impl ConsoleMapper {
         let image = game.image().map(Path::to_path_buf);
         database.increment_play_count(&game.name, game.path.as_path(), image.as_deref())?;
 
-        let core = self.get_console(game.path.as_path());
-        Ok(if let Some(console) = core {
-            let game_info = if let Some(ref path) = console.path {
-                GameInfo::new(
-                    game.name.clone(),
-                    game.path.clone(),
-                    image,
-                    path.display().to_string(),
-                    vec![game.path.display().to_string()],
-                    false,
-                )
-            } else if let Some(retroarch_core) =
-                game.core.clone().or_else(|| console.cores.first().cloned())
-            {
+        // Patched variants are played by patching the ROM they were found with
+        let (rom, patch) = if patch::is_patch(&game.path) {
+            let rom = patch::find_rom(&game.path)
+                .ok_or_else(|| anyhow!("No ROM found for patch {}", game.path.display()))?;
+            (rom, Some(game.path.clone()))
+        } else {
+            (game.path.clone(), None)
+        };
+
+        let Some(console) = self.get_console(database, &rom) else {
+            return Ok(None);
+        };
+        let name = game.name.clone();
+        let path = game.path.clone();
+        let launch = if let Some(ref command) = console.path {
+            let content = match patch {
+                Some(patch) => {
+                    // Patches apply to the ROM itself, not to the archive it's in
+                    let extraction = if archive::is_archive(&rom) {
+                        Some(self.extraction(database, console, &rom)?)
+                    } else {
+                        None
+                    };
+                    Content::Prepare(Box::new(move || {
+                        let rom = match extraction {
+                            Some(extraction) => extraction.extract()?,
+                            None => rom,
+                        };
+                        ExtractCache::new().patch(&rom, &patch)
+                    }))
+                }
+                None => Content::Ready(rom.display().to_string()),
+            };
+            let command = command.display().to_string();
+            Launch::new(content, move |content| {
+                GameInfo::new(name, path, image, command, vec![content], false)
+            })?
+        } else if let Some(retroarch_core) =
+            game.core.clone().or_else(|| console.cores.first().cloned())
+        {
+            let content = if self.needs_extraction(console, &retroarch_core, &rom) {
+                let extraction = self.extraction(database, console, &rom)?;
+                Content::Prepare(Box::new(move || extraction.extract()))
+            } else {
+                // RetroArch loads files inside archives with "archive.zip#file.ext"
+                Content::Ready(match self.get_archive_entry(database, console, &rom)? {
+                    Some(entry) => format!("{}#{}", rom.display(), entry.display()),
+                    None => rom.display().to_string(),
+                })
+            };
+
+            let core_name = self.get_core_name(&retroarch_core);
+            let mut args = vec![retroarch_core];
+            if let Some(patch) = patch {
+                // RetroArch soft patches the game as it loads, e.g. "--ips Game.ips"
+                let extension = patch
+                    .extension()
+                    .and_then(std::ffi::OsStr::to_str)
+                    .unwrap_or_default()
+                    .to_ascii_lowercase();
+                args.push(format!("--{}", extension));
+                args.push(patch.display().to_string());
+
+                // Saves would otherwise be named after the ROM, and shared with it
+                let stem = patch
+                    .file_stem()
+                    .and_then(std::ffi::OsStr::to_str)
+                    .unwrap_or_default();
+                for (flag, dir, extension) in [
+                    ("-s", &*ALLIUM_SAVES_DIR, "srm"),
+                    ("-S", &*ALLIUM_STATES_DIR, "state"),
+                ] {
+                    let dir = dir.join(&core_name);
+                    std::fs::create_dir_all(&dir)?;
+                    args.push(flag.to_string());
+                    args.push(
+                        dir.join(format!("{}.{}", stem, extension))
+                            .display()
+                            .to_string(),
+                    );
+                }
+            }
+            Launch::new(content, move |content| {
+                args.push(content);
                 GameInfo::new(
-                    game.name.clone(),
-                    game.path.clone(),
+                    name,
+                    path,
                     image,
                     ALLIUM_RETROARCH.display().to_string(),
-                    vec![retroarch_core, game.path.display().to_string()],
+                    args,
                     true,
                 )
-            } else {
-                bail!("Console \"{}\" has no path or cores.", console.name);
-            };
-            debug!("Saving game info: {:?}", game_info);
-            game_info.save()?;
-            Some(Command::Exec(game_info.command()))
+            })?
         } else {
-            None
-        })
+            bail!("Console \"{}\" has no path or cores.", console.name);
+        };
+        Ok(Some(launch))
+    }
+
+    /// Glob patterns of files and directories to leave out of every listing.
+    pub fn ignore(&self) -> &[String] {
+        &self.ignore
     }
 
     pub fn get_core_name(&self, core: &str) -> String {
@@ -190,6 +433,80 @@ impl ConsoleMapper {
     }
 }
 
+/// An archived game to extract, with what extracting it needs from the database.
+struct Extraction {
+    archive: PathBuf,
+    /// Path of the file to launch inside the archive.
+    entry: PathBuf,
+    /// Uncompressed size of the archive's files.
+    size: u64,
+}
+
+impl Extraction {
+    /// Extracts the archive, returning the path of the file to launch.
+    fn extract(self) -> Result<PathBuf> {
+        let dir = ExtractCache::new().extract(&self.archive, self.size)?;
+        Ok(dir.join(self.entry))
+    }
+}
+
+/// The file that a game is launched with.
+enum Content {
+    Ready(String),
+    /// Extracts or patches the file, which can take a while.
+    Prepare(Box<dyn FnOnce() -> Result<PathBuf> + Send>),
+}
+
+/// How to launch a game: right away, or once its file has been extracted or patched.
+pub enum Launch {
+    Now(Command),
+    Prepare(Box<dyn FnOnce() -> Result<Command> + Send>),
+}
+
+impl Launch {
+    /// Combines the game's file with the game info that runs it.
+    fn new(
+        content: Content,
+        game_info: impl FnOnce(String) -> GameInfo + Send + 'static,
+    ) -> Result<Self> {
+        Ok(match content {
+            Content::Ready(content) => Launch::Now(exec(game_info(content))?),
+            Content::Prepare(prepare) => Launch::Prepare(Box::new(move || {
+                exec(game_info(prepare()?.display().to_string()))
+            })),
+        })
+    }
+
+    /// Returns the command to send now. Games that have to be prepared first are prepared on
+    /// another thread while a toast is shown, so that the UI keeps responding, and the command
+    /// that launches them is sent once they're ready.
+    pub fn start(self, commands: Sender<Command>, locale: &Locale) -> Command {
+        match self {
+            Launch::Now(command) => command,
+            Launch::Prepare(prepare) => {
+                let failed = locale.t("launch-failed");
+                std::thread::spawn(move || {
+                    let command = prepare().unwrap_or_else(|e| {
+                        error!("failed to prepare game: {}", e);
+                        Command::Toast(failed, None)
+                    });
+                    if let Err(e) = commands.blocking_send(command) {
+                        warn!("failed to launch game: {}", e);
+                    }
+                });
+                Command::Toast(locale.t("launch-preparing"), None)
+            }
+        }
+    }
+}
+
+/// Saves the game info and returns the command that runs the game.
+fn exec(game_info: GameInfo) -> Result<Command> {
+    debug!("Saving game info: {:?}", game_info);
+    game_info.save()?;
+    Ok(Command::Exec(game_info.command()))
+}
+
 #[cfg(test)]
 mod tests {
     use std::env;
@@ -206,22 +523,42 @@ mod tests {
             cores: vec![],
             path: None,
             file_name: vec![],
+            rdb: None,
+            multi_file_archives: false,
+            arcade: false,
         }];
+        let database = Database::in_memory().unwrap();
 
-        assert!(mapper.get_console(Path::new("Roms/POKE/rom.zip")).is_some());
-        assert!(mapper.get_console(Path::new("Roms/PKM/rom.zip")).is_some());
         assert!(mapper
-            .get_console(Path::new("Roms/Pokemon Mini (POKE)/rom.zip"))
+            .get_console(&database, Path::new("Roms/POKE/rom.zip"))
             .is_some());
         assert!(mapper
-            .get_console(Path::new("Roms/POKE MINI/rom.zip"))
+            .get_console(&database, Path::new("Roms/PKM/rom.zip"))
+            .is_some());
+        assert!(mapper
+            .get_console(&database, Path::new("Roms/Pokemon Mini (POKE)/rom.zip"))
+            .is_some());
+        assert!(mapper
+            .get_console(&database, Path::new("Roms/POKE MINI/rom.zip"))
+            .is_none());
+        assert!(mapper
+            .get_console(&database, Path::new("Roms/rom.gb"))
+            .is_some());
+        assert!(mapper
+            .get_console(&database, Path::new("Roms/rom.gbc"))
+            .is_some());
+        assert!(mapper
+            .get_console(&database, Path::new("Roms/rom.gbc.zip"))
+            .is_some());
+        assert!(mapper
+            .get_console(&database, Path::new("Roms/rom.zip.gbc"))
+            .is_some());
+        assert!(mapper
+            .get_console(&database, Path::new("Roms/gbc"))
+            .is_none());
+        assert!(mapper
+            .get_console(&database, Path::new("Roms/rom.gba"))
             .is_none());
-        assert!(mapper.get_console(Path::new("Roms/rom.gb")).is_some());
-        assert!(mapper.get_console(Path::new("Roms/rom.gbc")).is_some());
-        assert!(mapper.get_console(Path::new("Roms/rom.gbc.zip")).is_some());
-        assert!(mapper.get_console(Path::new("Roms/rom.zip.gbc")).is_some());
-        assert!(mapper.get_console(Path::new("Roms/gbc")).is_none());
-        assert!(mapper.get_console(Path::new("Roms/rom.gba")).is_none());
     }
 
     #[test]
@@ -230,9 +567,10 @@ mod tests {
 
         let mut mapper = ConsoleMapper::new();
         mapper.load_config().unwrap();
+        let database = Database::in_memory().unwrap();
 
         let eq = |rom: &str, console_name: &str, core: &str| -> bool {
-            let console = mapper.get_console(Path::new(rom));
+            let console = mapper.get_console(&database, Path::new(rom));
             if console.is_none() {
                 println!("No console found for {}", rom);
                 return false;
@@ -1,24 +1,40 @@
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     ffi::OsStr,
     fs::{self, File},
     path::{Path, PathBuf},
+    time::UNIX_EPOCH,
 };
 
 use anyhow::{anyhow, Result};
 use common::{
     constants::{ALLIUM_GAMES_DIR, ALLIUM_SD_ROOT},
-    database::{Database, NewGame},
+    database::{Database, GameMetadata, GameStats, NewGame},
     locale::Locale,
+    stylesheet::ArtType,
 };
 use log::{error, trace};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     consoles::ConsoleMapper,
-    entry::{game::Game, gamelist::GameList, lazy_image::LazyImage, short_name, Entry},
+    entry::{
+        game::Game,
+        gamelist::GameList,
+        ignore::IgnoreRules,
+        lazy_image::{art_search_dirs, find_art, LazyImage},
+        multi_disc, natural_cmp, short_name, Entry,
+    },
+    patch,
 };
 
+/// Entries in a gamelist, and the metadata and play stats of its games.
+type GameListEntries = (
+    Vec<Entry>,
+    Vec<(PathBuf, GameMetadata)>,
+    Vec<(PathBuf, GameStats)>,
+);
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Directory {
     pub name: String,
@@ -27,11 +43,17 @@ pub struct Directory {
     /// image is loaded lazily.
     /// None means image hasn't been looked for, Some(None) means no image was found, Some(Some(path)) means an image was found.
     pub image: LazyImage,
+    /// Icon of the directory's console, or a generic folder icon.
+    #[serde(default)]
+    pub icon: Option<PathBuf>,
+    /// Console that the games are limited to, when the games of all subdirectories are listed.
+    #[serde(default)]
+    pub console: Option<String>,
 }
 
 impl Ord for Directory {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.full_name.cmp(&other.full_name)
+        natural_cmp(&self.full_name, &other.full_name)
     }
 }
 
@@ -48,6 +70,8 @@ impl Default for Directory {
             full_name: "Games".to_string(),
             path: ALLIUM_GAMES_DIR.to_owned(),
             image: LazyImage::Unknown(ALLIUM_GAMES_DIR.to_owned()),
+            icon: None,
+            console: None,
         }
     }
 }
@@ -66,6 +90,8 @@ impl Directory {
             full_name,
             path,
             image,
+            icon: None,
+            console: None,
         }
     }
 
@@ -81,6 +107,8 @@ impl Directory {
             full_name,
             path,
             image,
+            icon: None,
+            console: None,
         }
     }
 
@@ -88,47 +116,67 @@ impl Directory {
         self.image.image()
     }
 
-    fn parse_game_list(&self, game_list: &Path) -> Result<Vec<Entry>> {
+    /// Parses a gamelist.xml, returning its entries and the metadata and play stats of its games.
+    fn parse_game_list(&self, game_list: &Path) -> Result<GameListEntries> {
         let file = File::open(game_list)?;
         let gamelist: GameList = serde_xml_rs::from_reader(file)?;
 
-        let games = gamelist.games.into_iter().filter_map(|game| {
-            let path = self.path.join(&game.path).canonicalize().ok()?;
-            if !path.exists() {
-                return None;
-            }
+        let mut metadata = Vec::with_capacity(gamelist.games.len());
+        let mut stats = Vec::new();
+        let resolve = |path: &Path| self.path.join(path).canonicalize().ok();
 
-            let extension = game
-                .path
-                .extension()
-                .and_then(OsStr::to_str)
-                .unwrap_or_default()
-                .to_owned();
-
-            let full_name = game.name.clone();
-
-            let image = game.image.or(game.thumbnail);
-            let image = match image {
-                Some(image) => {
-                    let path = self.path.join(image).canonicalize().ok()?;
-                    if path.exists() {
-                        LazyImage::Found(path)
-                    } else {
-                        LazyImage::Unknown(self.path.clone())
+        let games: Vec<_> = gamelist
+            .games
+            .into_iter()
+            .filter_map(|game| {
+                let path = resolve(&game.path)?;
+                if !path.exists() {
+                    return None;
+                }
+
+                let extension = game
+                    .path
+                    .extension()
+                    .and_then(OsStr::to_str)
+                    .unwrap_or_default()
+                    .to_owned();
+
+                let full_name = game.name.clone();
+
+                let image = game.image.as_deref().or(game.thumbnail.as_deref());
+                let image = match image {
+                    Some(image) => {
+                        let path = resolve(image)?;
+                        if path.exists() {
+                            LazyImage::Found(path)
+                        } else {
+                            LazyImage::Unknown(self.path.clone())
+                        }
                     }
+                    None => LazyImage::Unknown(path.clone()),
+                };
+
+                // Only record metadata for games that are listed
+                let mut game_metadata = game.metadata();
+                game_metadata.marquee = game_metadata.marquee.as_deref().and_then(resolve);
+                game_metadata.video = game_metadata.video.as_deref().and_then(resolve);
+                metadata.push((path.clone(), game_metadata));
+                let game_stats = game.stats();
+                if game_stats != GameStats::default() {
+                    stats.push((path.clone(), game_stats));
                 }
-                None => LazyImage::Unknown(path.clone()),
-            };
 
-            Some(Entry::Game(Game {
-                path,
-                name: game.name,
-                full_name,
-                image,
-                extension,
-                core: None,
-            }))
-        });
+                Some(Entry::Game(Game {
+                    path,
+                    name: game.name,
+                    full_name,
+                    image,
+                    extension,
+                    core: None,
+                    variants: Vec::new(),
+                }))
+            })
+            .collect();
 
         let folders = gamelist.folders.into_iter().filter_map(|folder| {
             let path = self.path.join(&folder.path);
@@ -141,7 +189,117 @@ impl Directory {
             Some(Entry::Directory(Directory::with_name(path, name)))
         });
 
-        Ok(folders.chain(games).collect())
+        Ok((folders.chain(games).collect(), metadata, stats))
+    }
+
+    /// Every indexed game in the directory and its subdirectories, in place of the directory's
+    /// own entries. Games are listed from the index rather than the file system, leaving out what
+    /// `entries` leaves out of each directory: ignored files and folders, the discs of multi-disc
+    /// games, and patches that don't apply to any game.
+    pub fn flattened_entries(
+        &self,
+        database: &Database,
+        console_mapper: &ConsoleMapper,
+    ) -> Result<Vec<Entry>> {
+        let games = database.select_games_under_directory(&self.path)?;
+        let paths: Vec<PathBuf> = games.iter().map(|game| game.path.clone()).collect();
+
+        let discs: HashSet<PathBuf> = multi_disc::group_discs(&paths)
+            .into_iter()
+            .flat_map(|set| set.files)
+            .collect();
+
+        let mut roms: HashMap<&Path, Vec<PathBuf>> = HashMap::new();
+        for path in paths.iter().filter(|p| !patch::is_patch(p)) {
+            if let Some(dir) = path.parent() {
+                roms.entry(dir).or_default().push(path.clone());
+            }
+        }
+
+        let mut rules: HashMap<PathBuf, IgnoreRules> = HashMap::new();
+        let mut is_ignored = |dir: &Path, path: &Path| {
+            rules
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| IgnoreRules::load(console_mapper.ignore(), dir))
+                .is_ignored(dir, path)
+        };
+
+        Ok(games
+            .into_iter()
+            .filter(|game| !discs.contains(&game.path))
+            .filter(|game| {
+                // Patches are listed with the ROMs they patch
+                let dir = if patch::is_patch(&game.path) {
+                    let Some(dir) = patch::rom_dir(&game.path) else {
+                        return false;
+                    };
+                    let roms = roms.get(dir).map(Vec::as_slice).unwrap_or_default();
+                    if patch::find_variants(roms, std::slice::from_ref(&game.path)).is_empty() {
+                        return false;
+                    }
+                    dir
+                } else {
+                    let Some(dir) = game.path.parent() else {
+                        return false;
+                    };
+                    dir
+                };
+                if is_ignored(dir, &game.path) {
+                    return false;
+                }
+                // Games in ignored folders are hidden too
+                let mut child = dir;
+                while let Some(parent) = child.parent().filter(|p| p.starts_with(&self.path)) {
+                    if is_ignored(parent, child) {
+                        return false;
+                    }
+                    child = parent;
+                }
+                true
+            })
+            .filter(|game| {
+                self.console.as_ref().is_none_or(|console| {
+                    console_mapper
+                        .get_console(database, &game.path)
+                        .is_some_and(|c| &c.name == console)
+                })
+            })
+            .map(|game| {
+                let core = game.core.clone();
+                Entry::Game(Game {
+                    core,
+                    ..Game::from_db(game)
+                })
+            })
+            .collect())
+    }
+
+    /// Consoles of the directory's subdirectories that have indexed games, for limiting a
+    /// flattened listing to one console.
+    pub fn consoles(&self, database: &Database, console_mapper: &ConsoleMapper) -> Vec<String> {
+        let Ok(dir) = fs::read_dir(&self.path) else {
+            return Vec::new();
+        };
+        let mut paths: Vec<PathBuf> = dir
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_dir())
+            .collect();
+        paths.sort();
+
+        let mut consoles: Vec<String> = Vec::new();
+        for path in paths {
+            if database
+                .count_games_under_directory(&path)
+                .is_ok_and(|count| count > 0)
+            {
+                if let Some(console) = console_mapper.get_console_by_dir(&path) {
+                    if !consoles.contains(&console.name) {
+                        consoles.push(console.name.clone());
+                    }
+                }
+            }
+        }
+        consoles
     }
 
     pub fn entries(
@@ -207,7 +365,7 @@ impl Directory {
                     .wait()?;
             }
             match self.parse_game_list(&gamelist) {
-                Ok(res) => {
+                Ok((res, metadata, stats)) => {
                     database.update_games(
                         &res.iter()
                             .filter_map(|e| match e {
@@ -221,6 +379,8 @@ impl Directory {
                             })
                             .collect::<Vec<_>>(),
                     )?;
+                    database.update_game_metadata(&metadata)?;
+                    database.update_game_stats(&stats)?;
                     entries.extend(res);
                 }
                 Err(e) => error!("Failed to parse gamelist.xml: {}", e),
@@ -249,7 +409,7 @@ impl Directory {
                         .wait()?;
                 }
                 match self.parse_game_list(&gamelist) {
-                    Ok(res) => {
+                    Ok((res, metadata, stats)) => {
                         database.update_games(
                             &res.iter()
                                 .filter_map(|e| match e {
@@ -263,6 +423,8 @@ impl Directory {
                                 })
                                 .collect::<Vec<_>>(),
                         )?;
+                        database.update_game_metadata(&metadata)?;
+                        database.update_game_stats(&stats)?;
                         entries.extend(res);
                     }
                     Err(e) => error!("Failed to parse gamelist.xml: {}", e),
@@ -270,6 +432,13 @@ impl Directory {
             }
         }
 
+        // Games or images added or removed since the last lookup change the modification time
+        let mtime = self.mtime();
+        if database.get_directory_mtime(&self.path)? != Some(mtime) {
+            database.clear_missing_images(&self.path)?;
+            database.set_directory_mtime(&self.path, mtime)?;
+        }
+
         entries.extend(
             database
                 .select_games_in_directory(&self.path)?
@@ -291,17 +460,140 @@ impl Directory {
         let mut uniques = HashSet::new();
         entries.retain(|e| uniques.insert(e.path().to_path_buf()));
 
+        self.group_discs(&mut entries);
+        self.add_patch_variants(&mut entries);
+        self.name_arcade_sets(&mut entries, database, console_mapper);
+
+        let ignore = IgnoreRules::load(console_mapper.ignore(), &self.path);
+        entries.retain(|e| !ignore.is_ignored(&self.path, e.path()));
+
         for entry in entries.iter_mut() {
-            if let Entry::Game(game) = entry {
-                if let Some(core) = database.get_core(&game.path)? {
-                    game.core = Some(core);
+            match entry {
+                Entry::Game(game) => {
+                    if let Some(core) = database.get_core(&game.path)? {
+                        game.core = Some(core);
+                    }
+                }
+                Entry::Directory(dir) => {
+                    if let Some(image) = database.get_directory_image(&dir.path)? {
+                        dir.image = LazyImage::from_db(&dir.path, image, true);
+                    }
                 }
+                Entry::App(_) => {}
             }
         }
 
         Ok(entries)
     }
 
+    /// Replaces the discs of multi-disc games with a single entry that launches a generated m3u
+    /// playlist, so that RetroArch can swap between them. The playlists are written by
+    /// `populate_db`, so that listing a directory doesn't write to it.
+    fn group_discs(&self, entries: &mut Vec<Entry>) {
+        let paths: Vec<_> = entries
+            .iter()
+            .filter_map(|e| match e {
+                Entry::Game(game) => Some(game.path.clone()),
+                Entry::App(_) | Entry::Directory(_) => None,
+            })
+            .collect();
+        let sets = multi_disc::group_discs(&paths);
+        if sets.is_empty() {
+            return;
+        }
+
+        let mut games = Vec::with_capacity(sets.len());
+        for set in &sets {
+            // Users may have already made a playlist for this game
+            if set.directory.join(format!("{}.m3u", set.title)).exists() {
+                continue;
+            }
+
+            // The playlist itself is written when indexing
+            let path = set.m3u_path();
+
+            let name = entries
+                .iter()
+                .find(|e| e.path() == set.discs[0])
+                .map(|e| multi_disc::strip_disc(e.name()))
+                .unwrap_or_else(|| short_name(&set.title));
+
+            let mut game = Game::new(path);
+            game.name = name;
+            game.full_name = set.title.clone();
+            // Look for box art named after the game, falling back to the first disc's
+            game.image = LazyImage::Unknown(set.directory.join(format!("{}.m3u", set.title)));
+            if game.image().is_none() {
+                game.image = LazyImage::Unknown(set.discs[0].clone());
+            }
+            games.push(Entry::Game(game));
+        }
+
+        let files: HashSet<_> = sets.iter().flat_map(|s| s.files.iter()).collect();
+        entries.retain(|e| !files.contains(&e.path().to_path_buf()));
+        entries.extend(games);
+    }
+
+    /// Names arcade games after the title of their romset, e.g. `sf2.zip` after "Street Fighter
+    /// II: The World Warrior". Games named by a gamelist keep their name.
+    fn name_arcade_sets(
+        &self,
+        entries: &mut [Entry],
+        database: &Database,
+        console_mapper: &ConsoleMapper,
+    ) {
+        for entry in entries.iter_mut() {
+            let Entry::Game(game) = entry else {
+                continue;
+            };
+            let Some(set) = console_mapper.arcade_set(database, &game.path) else {
+                continue;
+            };
+            let stem = game
+                .path
+                .file_stem()
+                .and_then(OsStr::to_str)
+                .unwrap_or_default();
+            let name = short_name(&set.title);
+            // Games that were named before are in the database by either name
+            if game.name == short_name(stem) || game.name == name {
+                game.name = name;
+                game.full_name.clone_from(&set.title);
+            }
+        }
+    }
+
+    /// Replaces patch files with an entry for each game they patch, named after the game and the
+    /// patch, e.g. "Mother 3 (T-En)". Each variant is launched and tracked by its patch's path.
+    fn add_patch_variants(&self, entries: &mut Vec<Entry>) {
+        entries.retain(|e| !patch::is_patch(e.path()));
+
+        let patches = patch::patches_in(&self.path);
+        if patches.is_empty() {
+            return;
+        }
+        let roms: Vec<_> = entries
+            .iter()
+            .filter_map(|e| match e {
+                Entry::Game(game) => Some(game.path.clone()),
+                Entry::App(_) | Entry::Directory(_) => None,
+            })
+            .collect();
+
+        let mut variants = Vec::new();
+        for variant in patch::find_variants(&roms, &patches) {
+            let Some(Entry::Game(rom)) = entries.iter().find(|e| e.path() == variant.rom) else {
+                continue;
+            };
+            let mut game = Game::new(variant.patch);
+            game.name = format!("{} ({})", rom.name, variant.label);
+            game.full_name = format!("{} ({})", rom.full_name, variant.label);
+            game.image = rom.image.clone();
+            variants.push(Entry::Game(game));
+        }
+        entries.extend(variants);
+    }
+
     /// Populate the database with the games in this directory, pushing any subdirectories onto the
     /// queue.
     pub fn populate_db(
@@ -311,12 +603,44 @@ impl Directory {
         console_mapper: &ConsoleMapper,
         locale: &Locale,
     ) -> Result<()> {
-        let entries = self.entries(database, console_mapper, locale)?;
+        let mut entries = self.entries(database, console_mapper, locale)?;
+
+        let paths: Vec<_> = fs::read_dir(&self.path)?
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
+        for set in multi_disc::group_discs(&paths) {
+            if !entries.iter().any(|entry| entry.path() == set.m3u_path()) {
+                continue;
+            }
+            if let Err(e) = set.write_m3u() {
+                error!("Failed to write m3u for {}: {}", set.title, e);
+            }
+        }
 
-        for entry in &entries {
+        // Images are looked for now, so that listings don't have to search for them
+        let mut game_images = Vec::new();
+        let mut directory_images = Vec::new();
+        for art_type in [ArtType::Title, ArtType::Snap] {
+            let art: Vec<_> = entries
+                .iter()
+                .filter(|entry| matches!(entry, Entry::Game(_) | Entry::Directory(_)))
+                .map(|entry| (entry.path().to_path_buf(), find_art(entry.path(), art_type)))
+                .collect();
+            database.update_art(art_type, &art)?;
+        }
+        for entry in entries.iter_mut() {
             match entry {
-                Entry::Directory(dir) => queue.push_back(dir.clone()),
-                Entry::Game(_) | Entry::App(_) => {}
+                Entry::Directory(dir) => {
+                    let image = dir.image().map(Path::to_path_buf);
+                    directory_images.push((dir.path.clone(), image));
+                    queue.push_back(dir.clone());
+                }
+                Entry::Game(game) => {
+                    let image = game.image().map(Path::to_path_buf);
+                    game_images.push((game.path.clone(), image));
+                }
+                Entry::App(_) => {}
             }
         }
 
@@ -333,9 +657,23 @@ impl Directory {
             })
             .collect();
         database.update_games(&games)?;
+        database.update_game_images(&game_images)?;
+        database.update_directory_images(&directory_images)?;
 
         Ok(())
     }
+
+    /// Latest modification time of the directory and every folder that its art is looked for in,
+    /// in seconds.
+    fn mtime(&self) -> i64 {
+        std::iter::once(self.path.clone())
+            .chain(art_search_dirs(&self.path))
+            .filter_map(|path| fs::metadata(&path).and_then(|m| m.modified()).ok())
+            .filter_map(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs() as i64)
+            .max()
+            .unwrap_or_default()
+    }
 }
 
 impl From<&Path> for Directory {
@@ -10,7 +10,7 @@ use common::database::Game as DbGame;
 use log::info;
 use serde::{Deserialize, Serialize};
 
-use crate::entry::{lazy_image::LazyImage, short_name};
+use crate::entry::{lazy_image::LazyImage, natural_cmp, short_name};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Game {
@@ -26,6 +26,9 @@ pub struct Game {
     pub extension: String,
     /// The core to use for this game. If None, the default core will be used.
     pub core: Option<String>,
+    /// Every variant of the game when they are listed as one entry, including this one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<Game>,
 }
 
 impl Game {
@@ -49,6 +52,7 @@ impl Game {
             image,
             extension,
             core: None,
+            variants: Vec::new(),
         }
     }
 
@@ -59,10 +63,7 @@ impl Game {
             .and_then(std::ffi::OsStr::to_str)
             .unwrap_or("")
             .to_string();
-        let image = match game.image {
-            Some(image) => LazyImage::Found(image),
-            None => LazyImage::Unknown(game.path.clone()),
-        };
+        let image = LazyImage::from_db(&game.path, game.image, game.image_searched);
         let extension = game
             .path
             .extension()
@@ -77,6 +78,7 @@ impl Game {
             image,
             extension,
             core: None,
+            variants: Vec::new(),
         }
     }
 
@@ -103,7 +105,7 @@ impl Game {
 
 impl Ord for Game {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.full_name.cmp(&other.full_name)
+        natural_cmp(&self.full_name, &other.full_name)
     }
 }
 
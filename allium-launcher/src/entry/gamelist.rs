@@ -1,6 +1,18 @@
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
 
+use anyhow::Result;
+use chrono::{Local, NaiveDateTime, TimeZone};
+use common::constants::{ALLIUM_GAMES_DIR, ALLIUM_TRASH_DIR};
+use common::database::{Database, Game as DbGame, GameMetadata, GameStats};
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
+use xmltree::{Element, EmitterConfig, XMLNode};
+
+use crate::entry::multi_disc;
+use crate::patch;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -20,8 +32,79 @@ pub struct Game {
     pub image: Option<PathBuf>,
     #[serde(default, deserialize_with = "optional_path_buf_deserializer")]
     pub thumbnail: Option<PathBuf>,
+    #[serde(default, deserialize_with = "optional_from_str_deserializer")]
+    pub desc: Option<String>,
+    /// Rating between 0 and 1.
+    #[serde(default, deserialize_with = "optional_from_str_deserializer")]
+    pub rating: Option<f32>,
+    /// e.g. "19910101T000000"
+    #[serde(default, deserialize_with = "optional_from_str_deserializer")]
+    pub releasedate: Option<String>,
+    #[serde(default, deserialize_with = "optional_from_str_deserializer")]
+    pub developer: Option<String>,
+    #[serde(default, deserialize_with = "optional_from_str_deserializer")]
+    pub publisher: Option<String>,
+    #[serde(default, deserialize_with = "optional_from_str_deserializer")]
+    pub genre: Option<String>,
+    /// e.g. "1", "1-4"
+    #[serde(default, deserialize_with = "optional_from_str_deserializer")]
+    pub players: Option<String>,
+    #[serde(default, deserialize_with = "optional_path_buf_deserializer")]
+    pub marquee: Option<PathBuf>,
+    #[serde(default, deserialize_with = "optional_path_buf_deserializer")]
+    pub video: Option<PathBuf>,
+    #[serde(default, deserialize_with = "optional_from_str_deserializer")]
+    pub favorite: Option<bool>,
+    #[serde(default, deserialize_with = "optional_from_str_deserializer")]
+    pub playcount: Option<i64>,
+    /// Play time in seconds.
+    #[serde(default, deserialize_with = "optional_from_str_deserializer")]
+    pub gametime: Option<i64>,
+    /// e.g. "20230101T120000", in local time.
+    #[serde(default, deserialize_with = "optional_from_str_deserializer")]
+    pub lastplayed: Option<String>,
+}
+
+impl Game {
+    /// Returns the game's metadata. Paths are left relative to the gamelist.
+    pub fn metadata(&self) -> GameMetadata {
+        GameMetadata {
+            genre: self.genre.clone(),
+            developer: self.developer.clone(),
+            publisher: self.publisher.clone(),
+            release_year: self
+                .releasedate
+                .as_ref()
+                .and_then(|date| date.get(..4))
+                .and_then(|year| year.parse().ok()),
+            players: self.players.clone(),
+            description: self.desc.clone(),
+            rating: self.rating,
+            release_date: self.releasedate.clone(),
+            marquee: self.marquee.clone(),
+            video: self.video.clone(),
+        }
+    }
+
+    /// Returns the game's play stats, as written by Allium or another frontend.
+    pub fn stats(&self) -> GameStats {
+        GameStats {
+            favorite: self.favorite,
+            play_count: self.playcount,
+            play_time: self.gametime,
+            last_played_at: self
+                .lastplayed
+                .as_deref()
+                .and_then(|t| NaiveDateTime::parse_from_str(t, DATE_FORMAT).ok())
+                .and_then(|t| Local.from_local_datetime(&t).single())
+                .map(|t| t.timestamp()),
+        }
+    }
 }
 
+/// Format of dates in gamelists.
+const DATE_FORMAT: &str = "%Y%m%dT%H%M%S";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Folder {
@@ -44,6 +127,271 @@ where
     }
 }
 
+/// Deserializes empty or unparseable values as None.
+fn optional_from_str_deserializer<'de, D, T>(d: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: FromStr,
+{
+    let s = String::deserialize(d)?;
+    match s.trim() {
+        "" => Ok(None),
+        s => Ok(s.parse::<T>().ok()),
+    }
+}
+
+/// The state of a game to be written to a gamelist.xml.
+#[derive(Debug, Clone)]
+struct ExportedGame {
+    game: DbGame,
+    metadata: GameMetadata,
+    last_played_at: Option<i64>,
+    /// Whether the user renamed the game in Allium, so its name replaces the gamelist's.
+    renamed: bool,
+}
+
+/// Writes the state of every game in the database to gamelist.xml files, so that it can be read
+/// by other frontends. Existing gamelists are updated in place, preserving tags that Allium doesn't
+/// know about. Played or favorited games that aren't in an existing gamelist are added to the
+/// gamelist of their console's folder, unless it has a miyoogamelist.xml. Gamelists that can't be
+/// parsed are left alone, along with the games they might list. Entries that Allium makes up,
+/// multi-disc playlists and patched variants, aren't games to other frontends and are skipped.
+pub fn export_gamelists(database: &Database) -> Result<()> {
+    // None if the directory's gamelist couldn't be parsed
+    let mut gamelists: HashMap<PathBuf, Option<Element>> = HashMap::new();
+    let mut changed = HashSet::new();
+
+    'games: for game in database.select_all_games()? {
+        if !game.path.exists()
+            || game.path.starts_with(ALLIUM_TRASH_DIR.as_path())
+            || multi_disc::is_generated_playlist(&game.path)
+            || patch::is_patch(&game.path)
+        {
+            continue;
+        }
+        let Some(parent) = game.path.parent() else {
+            continue;
+        };
+
+        let mut dir = None;
+        for ancestor in parent
+            .ancestors()
+            .take_while(|d| d.starts_with(ALLIUM_GAMES_DIR.as_path()))
+        {
+            if !gamelists.contains_key(ancestor) {
+                if !ancestor.join("gamelist.xml").exists() {
+                    continue;
+                }
+                let gamelist = load_gamelist(ancestor)
+                    .map_err(|e| {
+                        warn!("failed to parse {}/gamelist.xml: {}", ancestor.display(), e)
+                    })
+                    .ok();
+                gamelists.insert(ancestor.to_path_buf(), gamelist);
+            }
+            match &gamelists[ancestor] {
+                Some(gamelist) => {
+                    if find_game(gamelist, ancestor, &game.path).is_some() {
+                        dir = Some(ancestor.to_path_buf());
+                        break;
+                    }
+                }
+                None => continue 'games,
+            }
+        }
+
+        let dir = match dir {
+            Some(dir) => dir,
+            None => {
+                let has_stats = game.play_count > 0 || game.play_time.num_seconds() > 0;
+                let Some(dir) = console_dir(&game.path) else {
+                    continue;
+                };
+                if !(has_stats || game.favorite) || dir.join("miyoogamelist.xml").exists() {
+                    continue;
+                }
+                dir
+            }
+        };
+
+        let game = ExportedGame {
+            metadata: database
+                .select_game_metadata(&game.path)?
+                .unwrap_or_default(),
+            last_played_at: database.get_last_played_at(&game.path)?,
+            renamed: database.is_renamed(&game.path)?,
+            game,
+        };
+        let Some(gamelist) = gamelists
+            .entry(dir.clone())
+            .or_insert_with(|| Some(Element::new("gameList")))
+        else {
+            continue;
+        };
+        update_gamelist(gamelist, &dir, &game);
+        changed.insert(dir);
+    }
+
+    for (dir, gamelist) in gamelists {
+        let Some(gamelist) = gamelist.filter(|_| changed.contains(&dir)) else {
+            continue;
+        };
+        let path = dir.join("gamelist.xml");
+        let tmp = dir.join("gamelist.xml.tmp");
+        let res = File::create(&tmp)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| {
+                gamelist.write_with_config(file, EmitterConfig::new().perform_indent(true))?;
+                Ok(())
+            })
+            .and_then(|_| Ok(fs::rename(&tmp, &path)?))
+            // Don't reimport a gamelist we just wrote
+            .and_then(|_| Ok(fs::metadata(&path)?.len()))
+            .and_then(|size| database.set_gamelist_fingerprint(&dir, size));
+        match res {
+            Ok(()) => info!("exported {}", path.display()),
+            Err(e) => warn!("failed to export {}: {}", path.display(), e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads the gamelist.xml in `dir`.
+fn load_gamelist(dir: &Path) -> Result<Element> {
+    let file = File::open(dir.join("gamelist.xml"))?;
+    Ok(Element::parse(file)?)
+}
+
+/// The folder of a game's console: the folder in the games directory that the game is in.
+fn console_dir(path: &Path) -> Option<PathBuf> {
+    let relative = path.strip_prefix(ALLIUM_GAMES_DIR.as_path()).ok()?;
+    let mut components = relative.components();
+    let console = components.next()?;
+    // Games directly in the games directory have no console folder
+    components.next()?;
+    Some(ALLIUM_GAMES_DIR.join(console))
+}
+
+/// Removes `.` components, so that "dir/./game.rom" and "dir/game.rom" compare equal.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+/// Returns the index of the `<game>` element in the gamelist whose path points to `path`.
+fn find_game(gamelist: &Element, dir: &Path, path: &Path) -> Option<usize> {
+    let path = normalize(path);
+    gamelist.children.iter().position(|node| {
+        node.as_element()
+            .filter(|e| e.name == "game")
+            .and_then(|e| e.get_child("path"))
+            .and_then(Element::get_text)
+            .is_some_and(|p| normalize(&dir.join(p.trim())) == path)
+    })
+}
+
+/// Updates (or adds) a game in the gamelist, leaving tags without a value in the database alone.
+/// The gamelist's name is kept unless the user renamed the game in Allium.
+fn update_gamelist(gamelist: &mut Element, dir: &Path, exported: &ExportedGame) {
+    let relative = |path: &Path| match path.strip_prefix(dir) {
+        Ok(path) => format!("./{}", path.display()),
+        Err(_) => path.display().to_string(),
+    };
+
+    let (i, added) = match find_game(gamelist, dir, &exported.game.path) {
+        Some(i) => (i, false),
+        None => {
+            let mut element = Element::new("game");
+            set_child_text(&mut element, "path", relative(&exported.game.path));
+            gamelist.children.push(XMLNode::Element(element));
+            (gamelist.children.len() - 1, true)
+        }
+    };
+    let Some(element) = gamelist.children[i].as_mut_element() else {
+        return;
+    };
+
+    let DbGame {
+        name,
+        image,
+        play_count,
+        play_time,
+        favorite,
+        ..
+    } = &exported.game;
+    let metadata = &exported.metadata;
+
+    if added || exported.renamed || element.get_child("name").is_none() {
+        set_child_text(element, "name", name.clone());
+    }
+    if let Some(image) = image {
+        set_child_text(element, "image", relative(image));
+    }
+    if let Some(desc) = &metadata.description {
+        set_child_text(element, "desc", desc.clone());
+    }
+    if let Some(rating) = metadata.rating {
+        set_child_text(element, "rating", rating.to_string());
+    }
+    if let Some(release_date) = metadata.release_date.clone().or_else(|| {
+        metadata
+            .release_year
+            .map(|y| format!("{:04}0101T000000", y))
+    }) {
+        set_child_text(element, "releasedate", release_date);
+    }
+    if let Some(developer) = &metadata.developer {
+        set_child_text(element, "developer", developer.clone());
+    }
+    if let Some(publisher) = &metadata.publisher {
+        set_child_text(element, "publisher", publisher.clone());
+    }
+    if let Some(genre) = &metadata.genre {
+        set_child_text(element, "genre", genre.clone());
+    }
+    if let Some(players) = &metadata.players {
+        set_child_text(element, "players", players.clone());
+    }
+    if let Some(marquee) = &metadata.marquee {
+        set_child_text(element, "marquee", relative(marquee));
+    }
+    if let Some(video) = &metadata.video {
+        set_child_text(element, "video", relative(video));
+    }
+    if *favorite || element.get_child("favorite").is_some() {
+        set_child_text(element, "favorite", favorite.to_string());
+    }
+    if *play_count > 0 {
+        set_child_text(element, "playcount", play_count.to_string());
+    }
+    if play_time.num_seconds() > 0 {
+        set_child_text(element, "gametime", play_time.num_seconds().to_string());
+    }
+    if let Some(last_played_at) = exported
+        .last_played_at
+        .and_then(|t| Local.timestamp_opt(t, 0).single())
+    {
+        set_child_text(
+            element,
+            "lastplayed",
+            last_played_at.format(DATE_FORMAT).to_string(),
+        );
+    }
+}
+
+fn set_child_text(element: &mut Element, name: &str, text: String) {
+    match element.get_mut_child(name) {
+        Some(child) => child.children = vec![XMLNode::Text(text)],
+        None => {
+            let mut child = Element::new(name);
+            child.children.push(XMLNode::Text(text));
+            element.children.push(XMLNode::Element(child));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -96,6 +444,180 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_deserialize_metadata() {
+        let xml = r#"
+        <gameList>
+            <game>
+                <path>./Game One.gba</path>
+                <name>Game One</name>
+                <desc>A game about things.</desc>
+                <rating>0.8</rating>
+                <releasedate>19960227T000000</releasedate>
+                <developer>Developer</developer>
+                <publisher>Publisher</publisher>
+                <genre>Role playing games</genre>
+                <players>1-2</players>
+                <marquee>./media/marquees/Game One.png</marquee>
+                <video>./media/videos/Game One.mp4</video>
+            </game>
+            <game>
+                <path>./Game Two.gba</path>
+                <name>Game Two</name>
+                <desc />
+                <rating>not a number</rating>
+            </game>
+        </gameList>
+        "#;
+        let game_list: GameList = serde_xml_rs::from_str(xml).unwrap();
+        assert_eq!(game_list.games.len(), 2);
+
+        let metadata = game_list.games[0].metadata();
+        assert_eq!(
+            metadata.description.as_deref(),
+            Some("A game about things.")
+        );
+        assert_eq!(metadata.rating, Some(0.8));
+        assert_eq!(metadata.release_year, Some(1996));
+        assert_eq!(metadata.release_date.as_deref(), Some("19960227T000000"));
+        assert_eq!(metadata.developer.as_deref(), Some("Developer"));
+        assert_eq!(metadata.publisher.as_deref(), Some("Publisher"));
+        assert_eq!(metadata.genre.as_deref(), Some("Role playing games"));
+        assert_eq!(metadata.players.as_deref(), Some("1-2"));
+        assert_eq!(
+            metadata.marquee,
+            Some(PathBuf::from("./media/marquees/Game One.png"))
+        );
+        assert_eq!(
+            metadata.video,
+            Some(PathBuf::from("./media/videos/Game One.mp4"))
+        );
+
+        let metadata = game_list.games[1].metadata();
+        assert_eq!(metadata.description, None);
+        assert_eq!(metadata.rating, None);
+        assert_eq!(metadata.release_year, None);
+    }
+
+    #[test]
+    fn test_deserialize_stats() {
+        let xml = r#"
+        <gameList>
+            <game>
+                <path>./Game One.gba</path>
+                <name>Game One</name>
+                <favorite>true</favorite>
+                <playcount>3</playcount>
+                <gametime>120</gametime>
+                <lastplayed>20230101T120000</lastplayed>
+            </game>
+            <game>
+                <path>./Game Two.gba</path>
+                <name>Game Two</name>
+                <lastplayed>yesterday</lastplayed>
+            </game>
+        </gameList>
+        "#;
+        let game_list: GameList = serde_xml_rs::from_str(xml).unwrap();
+
+        let stats = game_list.games[0].stats();
+        assert_eq!(stats.favorite, Some(true));
+        assert_eq!(stats.play_count, Some(3));
+        assert_eq!(stats.play_time, Some(120));
+        assert_eq!(
+            stats.last_played_at,
+            Some(
+                Local
+                    .with_ymd_and_hms(2023, 1, 1, 12, 0, 0)
+                    .unwrap()
+                    .timestamp()
+            )
+        );
+
+        assert_eq!(game_list.games[1].stats(), GameStats::default());
+    }
+
+    #[test]
+    fn test_update_gamelist() {
+        let xml = r#"
+        <gameList>
+            <game id="1">
+                <path>./Game One.gba</path>
+                <name>Game One</name>
+                <kidgame>true</kidgame>
+            </game>
+        </gameList>
+        "#;
+        let mut gamelist = Element::parse(xml.as_bytes()).unwrap();
+        let dir = Path::new("Roms/GBA");
+
+        let game = |name: &str, play_count| ExportedGame {
+            renamed: false,
+            game: DbGame {
+                name: name.to_string(),
+                path: dir.join(format!("{}.gba", name)),
+                image: Some(dir.join(format!("Imgs/{}.png", name))),
+                play_count,
+                play_time: chrono::Duration::seconds(60),
+                last_played: 1,
+                core: None,
+                favorite: true,
+                image_searched: true,
+            },
+            metadata: GameMetadata {
+                genre: Some("Platform".to_string()),
+                release_year: Some(1991),
+                ..Default::default()
+            },
+            last_played_at: None,
+        };
+        let mut game_one = game("Game One", 3);
+        game_one.game.name = "Allium's Name".to_string();
+        update_gamelist(&mut gamelist, dir, &game_one);
+        update_gamelist(&mut gamelist, dir, &game("Game Two", 0));
+
+        let games: Vec<_> = gamelist
+            .children
+            .iter()
+            .filter_map(XMLNode::as_element)
+            .collect();
+        assert_eq!(games.len(), 2);
+
+        let text = |game: &Element, name: &str| {
+            game.get_child(name)
+                .and_then(Element::get_text)
+                .map(|t| t.to_string())
+        };
+        assert_eq!(games[0].attributes.get("id").map(String::as_str), Some("1"));
+        assert_eq!(text(games[0], "name").as_deref(), Some("Game One"));
+        assert_eq!(text(games[0], "kidgame").as_deref(), Some("true"));
+        assert_eq!(text(games[0], "playcount").as_deref(), Some("3"));
+        assert_eq!(text(games[0], "favorite").as_deref(), Some("true"));
+        assert_eq!(text(games[0], "genre").as_deref(), Some("Platform"));
+        assert_eq!(
+            text(games[0], "releasedate").as_deref(),
+            Some("19910101T000000")
+        );
+        assert_eq!(
+            text(games[0], "image").as_deref(),
+            Some("./Imgs/Game One.png")
+        );
+
+        assert_eq!(text(games[1], "path").as_deref(), Some("./Game Two.gba"));
+        assert_eq!(text(games[1], "name").as_deref(), Some("Game Two"));
+        assert_eq!(text(games[1], "playcount"), None);
+        assert_eq!(text(games[1], "gametime").as_deref(), Some("60"));
+
+        // Names the user gave in Allium replace the gamelist's
+        game_one.renamed = true;
+        update_gamelist(&mut gamelist, dir, &game_one);
+        let name = gamelist
+            .get_child("game")
+            .and_then(|game| game.get_child("name"))
+            .and_then(Element::get_text);
+        assert_eq!(name.as_deref(), Some("Allium's Name"));
+    }
+
     #[test]
     fn test_deserialize_folder() {
         let xml = r#"
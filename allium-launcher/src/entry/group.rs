@@ -0,0 +1,286 @@
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, Local, TimeZone};
+use common::database::Database;
+use common::locale::Locale;
+
+use crate::consoles::ConsoleMapper;
+use crate::entry::{strip_article, Entry};
+
+/// A run of entries that are next to each other in a sort order, which L/R jump between.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Group {
+    /// First letter of the name, or '#' for names that don't start with a letter.
+    Letter(char),
+    Today,
+    Yesterday,
+    ThisWeek,
+    Month(i32, u32),
+    NeverPlayed,
+    /// Played for at least this many hours. Zero means less than an hour.
+    Hours(u32),
+    Year(i32),
+    /// Rating out of 5.
+    Stars(u32),
+    Console(String),
+    Unknown,
+}
+
+impl Group {
+    pub fn letter(name: &str) -> Self {
+        match name.chars().next() {
+            Some(c) if c.is_alphabetic() => Group::Letter(c.to_uppercase().next().unwrap_or(c)),
+            _ => Group::Letter('#'),
+        }
+    }
+
+    /// Group of a unix timestamp by how long before `now` it was.
+    pub fn date(timestamp: i64, now: DateTime<Local>) -> Self {
+        let Some(time) = Local.timestamp_opt(timestamp, 0).single() else {
+            return Group::Unknown;
+        };
+        let days = (now.date_naive() - time.date_naive()).num_days();
+        match days {
+            ..=0 => Group::Today,
+            1 => Group::Yesterday,
+            2..=6 => Group::ThisWeek,
+            _ => Group::Month(time.year(), time.month()),
+        }
+    }
+
+    pub fn play_time(play_time: Duration) -> Self {
+        match play_time.num_hours() {
+            _ if play_time <= Duration::zero() => Group::NeverPlayed,
+            0 => Group::Hours(0),
+            1..=9 => Group::Hours(1),
+            10..=99 => Group::Hours(10),
+            _ => Group::Hours(100),
+        }
+    }
+
+    /// Group of a rating from 0 to 1.
+    pub fn rating(rating: f32) -> Self {
+        Group::Stars((rating.clamp(0.0, 1.0) * 5.0).round() as u32)
+    }
+
+    pub fn text(&self, locale: &Locale) -> String {
+        match self {
+            Group::Letter(c) => c.to_string(),
+            Group::Today => locale.t("group-today"),
+            Group::Yesterday => locale.t("group-yesterday"),
+            Group::ThisWeek => locale.t("group-this-week"),
+            Group::Month(year, month) => format!("{}-{:02}", year, month),
+            Group::NeverPlayed => locale.t("group-never-played"),
+            Group::Hours(0) => locale.t("group-under-an-hour"),
+            Group::Hours(hours) => locale.ta(
+                "group-hours",
+                &[("hours".to_string(), (*hours).into())]
+                    .into_iter()
+                    .collect(),
+            ),
+            Group::Year(year) => year.to_string(),
+            Group::Stars(stars) => format!("{}/5", stars),
+            Group::Console(name) => name.clone(),
+            Group::Unknown => locale.t("group-unknown"),
+        }
+    }
+}
+
+/// Groups entries by the first letter of the name they are sorted by, as in `Entry::cmp_by_name`.
+pub fn by_letter(entries: &[Entry], ignore_articles: bool) -> Vec<Group> {
+    entries
+        .iter()
+        .map(|e| {
+            let name = e.sort_name();
+            Group::letter(if ignore_articles {
+                strip_article(name)
+            } else {
+                name
+            })
+        })
+        .collect()
+}
+
+/// Groups games by `group`, and other entries by letter. `values` is given the paths of the
+/// games and returns a value for each, which `group` turns into its group.
+fn by_game<T>(
+    entries: &[Entry],
+    values: impl FnOnce(&[&Path]) -> Result<Vec<T>>,
+    group: impl Fn(T) -> Group,
+) -> Result<Vec<Group>> {
+    let paths: Vec<_> = entries
+        .iter()
+        .filter_map(|entry| match entry {
+            Entry::Game(game) => Some(game.path.as_path()),
+            _ => None,
+        })
+        .collect();
+    let mut values = values(&paths)?.into_iter();
+    Ok(entries
+        .iter()
+        .map(|entry| match entry {
+            Entry::Game(_) => values.next().map_or(Group::Unknown, &group),
+            _ => Group::letter(entry.sort_name()),
+        })
+        .collect())
+}
+
+/// Groups games by when they were last played.
+pub fn by_last_played(entries: &[Entry], database: &Database) -> Result<Vec<Group>> {
+    let now = Local::now();
+    by_game(
+        entries,
+        |paths| {
+            paths
+                .iter()
+                .map(|path| database.get_last_played_at(path))
+                .collect()
+        },
+        |last_played_at| last_played_at.map_or(Group::NeverPlayed, |t| Group::date(t, now)),
+    )
+}
+
+/// Groups games by how long they have been played.
+pub fn by_play_time(entries: &[Entry], database: &Database) -> Result<Vec<Group>> {
+    by_game(
+        entries,
+        |paths| database.select_games(paths),
+        |game| game.map_or(Group::NeverPlayed, |game| Group::play_time(game.play_time)),
+    )
+}
+
+/// Groups games by the year they were released.
+pub fn by_release_year(entries: &[Entry], database: &Database) -> Result<Vec<Group>> {
+    by_game(
+        entries,
+        |paths| database.select_games_metadata(paths),
+        |metadata| {
+            metadata
+                .and_then(|m| m.release_year)
+                .map_or(Group::Unknown, Group::Year)
+        },
+    )
+}
+
+/// Groups games by their rating.
+pub fn by_rating(entries: &[Entry], database: &Database) -> Result<Vec<Group>> {
+    by_game(
+        entries,
+        |paths| database.select_games_metadata(paths),
+        |metadata| {
+            metadata
+                .and_then(|m| m.rating)
+                .map_or(Group::Unknown, Group::rating)
+        },
+    )
+}
+
+/// Groups games by when they were added.
+pub fn by_added(entries: &[Entry], database: &Database) -> Result<Vec<Group>> {
+    let now = Local::now();
+    by_game(
+        entries,
+        |paths| database.select_added_at(paths),
+        |added_at| added_at.map_or(Group::Unknown, |t| Group::date(t, now)),
+    )
+}
+
+/// Groups games by their console.
+pub fn by_console(
+    entries: &[Entry],
+    database: &Database,
+    console_mapper: &ConsoleMapper,
+) -> Result<Vec<Group>> {
+    by_game(
+        entries,
+        |paths| {
+            Ok(paths
+                .iter()
+                .map(|path| console_mapper.get_console(database, path))
+                .collect())
+        },
+        |console| console.map_or(Group::Unknown, |c| Group::Console(c.name.clone())),
+    )
+}
+
+/// Index to jump to from `selected`: the start of the next group, or going backwards, the start
+/// of the current group, or of the previous one if already at its start.
+pub fn jump(groups: &[Group], selected: usize, forward: bool) -> usize {
+    if groups.is_empty() {
+        return 0;
+    }
+    let selected = selected.min(groups.len() - 1);
+
+    if forward {
+        return groups[selected..]
+            .iter()
+            .position(|g| *g != groups[selected])
+            .map_or(groups.len() - 1, |i| selected + i);
+    }
+
+    let start = |i: usize| {
+        groups[..i]
+            .iter()
+            .rposition(|g| *g != groups[i])
+            .map_or(0, |j| j + 1)
+    };
+    let current = start(selected);
+    if current < selected || current == 0 {
+        current
+    } else {
+        start(current - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::entry::game::Game;
+
+    #[test]
+    fn test_groups() {
+        assert_eq!(Group::letter("mega Man"), Group::Letter('M'));
+        assert_eq!(Group::letter("1942"), Group::Letter('#'));
+        assert_eq!(Group::letter(""), Group::Letter('#'));
+
+        let mut game = Game::new("/Roms/GB/Legend of Zelda, The.gb".into());
+        game.full_name = "The Legend of Zelda".to_string();
+        let entries = [Entry::Game(game)];
+        assert_eq!(by_letter(&entries, false), [Group::Letter('T')]);
+        assert_eq!(by_letter(&entries, true), [Group::Letter('L')]);
+
+        assert_eq!(Group::play_time(Duration::zero()), Group::NeverPlayed);
+        assert_eq!(Group::play_time(Duration::minutes(30)), Group::Hours(0));
+        assert_eq!(Group::play_time(Duration::hours(12)), Group::Hours(10));
+
+        let now = Local.with_ymd_and_hms(2024, 5, 20, 12, 0, 0).unwrap();
+        let at = |d: u32| {
+            Local
+                .with_ymd_and_hms(2024, 5, d, 9, 0, 0)
+                .unwrap()
+                .timestamp()
+        };
+        assert_eq!(Group::date(at(20), now), Group::Today);
+        assert_eq!(Group::date(at(19), now), Group::Yesterday);
+        assert_eq!(Group::date(at(15), now), Group::ThisWeek);
+        assert_eq!(Group::date(at(2), now), Group::Month(2024, 5));
+
+        assert_eq!(Group::rating(0.8), Group::Stars(4));
+        assert_eq!(Group::rating(1.5), Group::Stars(5));
+    }
+
+    #[test]
+    fn test_jump() {
+        let groups: Vec<_> = "AABBBC".chars().map(Group::Letter).collect();
+        assert_eq!(jump(&groups, 0, true), 2);
+        assert_eq!(jump(&groups, 3, true), 5);
+        assert_eq!(jump(&groups, 5, true), 5);
+        assert_eq!(jump(&groups, 4, false), 2);
+        assert_eq!(jump(&groups, 2, false), 0);
+        assert_eq!(jump(&groups, 5, false), 2);
+        assert_eq!(jump(&groups, 0, false), 0);
+        assert_eq!(jump(&[], 3, true), 0);
+    }
+}
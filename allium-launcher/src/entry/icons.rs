@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use common::constants::ALLIUM_ICONS_DIR;
+use common::stylesheet::Stylesheet;
+
+use crate::consoles::ConsoleMapper;
+
+/// Icon for folders that aren't a console, and consoles without an icon of their own.
+const FOLDER_ICON: &str = "folder";
+
+/// Finds console and folder icons, named after the console, e.g. "Game Boy Advance.png".
+pub struct Icons {
+    /// Folders to look in, in order of preference.
+    dirs: Vec<PathBuf>,
+    /// Icons that have already been looked for, by name.
+    found: HashMap<String, Option<PathBuf>>,
+}
+
+impl Icons {
+    pub fn new(styles: &Stylesheet) -> Self {
+        Self {
+            dirs: styles
+                .icons_dir
+                .iter()
+                .cloned()
+                .chain([ALLIUM_ICONS_DIR.clone()])
+                .collect(),
+            found: HashMap::new(),
+        }
+    }
+
+    /// Icon of the console a directory holds games for, or the generic folder icon.
+    pub fn directory(&mut self, console_mapper: &ConsoleMapper, path: &Path) -> Option<PathBuf> {
+        console_mapper
+            .get_console_by_dir(path)
+            .and_then(|console| self.find(&console.name))
+            .or_else(|| self.find(FOLDER_ICON))
+    }
+
+    fn find(&mut self, name: &str) -> Option<PathBuf> {
+        if let Some(icon) = self.found.get(name) {
+            return icon.clone();
+        }
+        let icon = self
+            .dirs
+            .iter()
+            .map(|dir| dir.join(format!("{}.png", name)))
+            .find(|path| path.is_file());
+        self.found.insert(name.to_string(), icon.clone());
+        icon
+    }
+}
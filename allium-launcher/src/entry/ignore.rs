@@ -0,0 +1,78 @@
+use std::fs;
+use std::path::Path;
+
+use glob::{MatchOptions, Pattern};
+use log::warn;
+
+/// Name of the file listing the glob patterns to ignore in a directory, one per line.
+pub const IGNORE_FILE: &str = ".alliumignore";
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Glob patterns of files and directories to leave out of a directory's listing.
+#[derive(Debug, Default)]
+pub struct IgnoreRules {
+    patterns: Vec<Pattern>,
+}
+
+impl IgnoreRules {
+    /// Parses patterns, skipping blank lines and comments starting with #.
+    pub fn new<'a>(patterns: impl IntoIterator<Item = &'a str>) -> Self {
+        let patterns = patterns
+            .into_iter()
+            .map(str::trim)
+            .filter(|p| !p.is_empty() && !p.starts_with('#'))
+            .filter_map(|p| match Pattern::new(p) {
+                Ok(pattern) => Some(pattern),
+                Err(e) => {
+                    warn!("invalid ignore pattern {:?}: {}", p, e);
+                    None
+                }
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    /// Loads the global patterns, and those in the directory's .alliumignore.
+    pub fn load(global: &[String], directory: &Path) -> Self {
+        let local = fs::read_to_string(directory.join(IGNORE_FILE)).unwrap_or_default();
+        Self::new(global.iter().map(String::as_str).chain(local.lines()))
+    }
+
+    /// Returns true if the path matches a pattern, either by its name or by its path relative to
+    /// the directory, e.g. "bios" or "Extras/*.pdf".
+    pub fn is_ignored(&self, directory: &Path, path: &Path) -> bool {
+        let name = path.file_name().map(Path::new);
+        let relative = path.strip_prefix(directory).ok();
+        self.patterns.iter().any(|pattern| {
+            name.is_some_and(|name| pattern.matches_path_with(name, MATCH_OPTIONS))
+                || relative.is_some_and(|rel| pattern.matches_path_with(rel, MATCH_OPTIONS))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_rules() {
+        let rules = IgnoreRules::new(
+            "# BIOS and saves\nbios\n*.sav\n\nExtras/*.pdf\n"
+                .lines()
+                .chain(["*.srm"]),
+        );
+        let dir = Path::new("Roms/PS");
+
+        assert!(rules.is_ignored(dir, &dir.join("BIOS")));
+        assert!(rules.is_ignored(dir, &dir.join("Game.sav")));
+        assert!(rules.is_ignored(dir, &dir.join("Game.SRM")));
+        assert!(rules.is_ignored(dir, &dir.join("Extras/Manual.pdf")));
+        assert!(!rules.is_ignored(dir, &dir.join("Manual.pdf")));
+        assert!(!rules.is_ignored(dir, &dir.join("Game.cue")));
+    }
+}
@@ -1,6 +1,7 @@
 use std::path::{Path, PathBuf};
 
 use common::constants::ALLIUM_GAMES_DIR;
+use common::stylesheet::ArtType;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -13,10 +14,13 @@ pub enum LazyImage {
 }
 
 impl LazyImage {
-    pub fn from_path(path: &Path, image: Option<PathBuf>) -> Self {
+    /// Image lookup stored in the database. `searched` tells a file without an image apart from
+    /// one that hasn't been looked for yet.
+    pub fn from_db(path: &Path, image: Option<PathBuf>, searched: bool) -> Self {
         match image {
             Some(image) => Self::Found(image),
-            _ => Self::Unknown(path.to_path_buf()),
+            None if searched => Self::NotFound,
+            None => Self::Unknown(path.to_path_buf()),
         }
     }
 
@@ -28,36 +32,7 @@ impl LazyImage {
             Self::NotFound => return None,
         };
 
-        // Search for Imgs folder upwards, recursively
-        let mut parent = path.clone();
-        let mut image = None;
-        let file_name = path.file_name().unwrap();
-        'image: while parent.pop() {
-            let mut image_path = parent.join("Imgs");
-            if image_path.is_dir() {
-                const IMAGE_EXTENSIONS: [&str; 4] = ["png", "jpg", "jpeg", "gif"];
-                image_path.push(file_name);
-                for ext in &IMAGE_EXTENSIONS {
-                    image_path.set_extension(ext);
-                    if image_path.is_file() {
-                        image = Some(image_path);
-                        break 'image;
-                    }
-                }
-                image_path.pop();
-                image_path.extend(path.strip_prefix(&parent).unwrap());
-                for ext in &IMAGE_EXTENSIONS {
-                    image_path.set_extension(ext);
-                    if image_path.is_file() {
-                        image = Some(image_path);
-                        break 'image;
-                    }
-                }
-            }
-            if parent.to_str() == ALLIUM_GAMES_DIR.to_str() {
-                break;
-            }
-        }
+        let image = find_art(path, ArtType::Boxart);
         *self = match image {
             Some(image) => Self::Found(image),
             None => Self::NotFound,
@@ -82,3 +57,153 @@ impl From<PathBuf> for LazyImage {
         Self::Found(path)
     }
 }
+
+/// Image extensions to look for, in order of preference.
+const IMAGE_EXTENSIONS: [&str; 4] = ["png", "jpg", "jpeg", "gif"];
+
+/// Folders that art is kept in. Art in `Imgs` follows the game's file name and folder
+/// structure, while libretro-thumbnails folders are flat and use the libretro file naming.
+fn art_dirs(art_type: ArtType) -> [(&'static str, bool); 2] {
+    match art_type {
+        ArtType::Boxart => [("Imgs", false), ("Named_Boxarts", true)],
+        ArtType::Title => [("Imgs/Titles", false), ("Named_Titles", true)],
+        ArtType::Snap => [("Imgs/Snaps", false), ("Named_Snaps", true)],
+    }
+}
+
+/// Folder that art of a type is kept in next to games, following their file names.
+pub fn art_dir(art_type: ArtType) -> &'static str {
+    art_dirs(art_type)[0].0
+}
+
+/// Whether there is an art folder of a type that `find_art` looks in for the files in `dir`.
+pub fn has_art_dir(dir: &Path, art_type: ArtType) -> bool {
+    for parent in dir.ancestors() {
+        if art_dirs(art_type)
+            .iter()
+            .any(|(art_dir, _)| parent.join(art_dir).is_dir())
+        {
+            return true;
+        }
+        if parent.to_str() == ALLIUM_GAMES_DIR.to_str() {
+            break;
+        }
+    }
+    false
+}
+
+/// Searches for art of a file in art folders upwards, up to the games directory.
+pub fn find_art(path: &Path, art_type: ArtType) -> Option<PathBuf> {
+    let file_name = path.file_name()?;
+    let libretro_name = path
+        .file_stem()
+        .map(|stem| libretro_name(&stem.to_string_lossy()));
+
+    let mut parent = path.to_path_buf();
+    while parent.pop() {
+        for (dir, libretro) in art_dirs(art_type) {
+            let mut image_path = parent.join(dir);
+            if !image_path.is_dir() {
+                continue;
+            }
+            if libretro {
+                let Some(ref name) = libretro_name else {
+                    continue;
+                };
+                image_path.push(format!("{}.png", name));
+                if image_path.is_file() {
+                    return Some(image_path);
+                }
+                continue;
+            }
+
+            image_path.push(file_name);
+            for ext in &IMAGE_EXTENSIONS {
+                image_path.set_extension(ext);
+                if image_path.is_file() {
+                    return Some(image_path);
+                }
+            }
+            image_path.pop();
+            image_path.extend(path.strip_prefix(&parent).unwrap());
+            for ext in &IMAGE_EXTENSIONS {
+                image_path.set_extension(ext);
+                if image_path.is_file() {
+                    return Some(image_path);
+                }
+            }
+        }
+        if parent.to_str() == ALLIUM_GAMES_DIR.to_str() {
+            break;
+        }
+    }
+    None
+}
+
+/// Folders that `find_art` looks in for art of the files in `dir`, of every art type. Adding or
+/// removing art changes the modification time of one of them.
+pub fn art_search_dirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for parent in dir.ancestors() {
+        for art_type in ArtType::ALL {
+            for (art_dir, libretro) in art_dirs(art_type) {
+                let art_dir = parent.join(art_dir);
+                if !libretro && parent != dir {
+                    // Art of files in subfolders follows their folder structure
+                    dirs.push(art_dir.join(dir.strip_prefix(parent).unwrap()));
+                }
+                dirs.push(art_dir);
+            }
+        }
+        if parent.to_str() == ALLIUM_GAMES_DIR.to_str() {
+            break;
+        }
+    }
+    dirs
+}
+
+/// File name used by libretro-thumbnails, which replaces characters that aren't allowed in
+/// file names on some systems with underscores.
+pub fn libretro_name(name: &str) -> String {
+    name.replace(
+        ['&', '*', '/', ':', '`', '<', '>', '?', '\\', '|', '"'],
+        "_",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_libretro_name() {
+        assert_eq!(
+            libretro_name("Legend of Zelda, The - A Link to the Past (USA)"),
+            "Legend of Zelda, The - A Link to the Past (USA)"
+        );
+        assert_eq!(
+            libretro_name("Tom & Jerry: Frantic Antics! (USA)"),
+            "Tom _ Jerry_ Frantic Antics! (USA)"
+        );
+        assert_eq!(libretro_name("What? <Beta>"), "What_ _Beta_");
+    }
+
+    #[test]
+    fn test_art_search_dirs() {
+        let dir = ALLIUM_GAMES_DIR.join("PS/RPG");
+        let dirs = art_search_dirs(&dir);
+        for expected in [
+            dir.join("Imgs"),
+            dir.join("Named_Snaps"),
+            ALLIUM_GAMES_DIR.join("PS/Imgs/RPG"),
+            ALLIUM_GAMES_DIR.join("PS/Imgs/Titles/RPG"),
+            ALLIUM_GAMES_DIR.join("PS/Named_Boxarts"),
+            ALLIUM_GAMES_DIR.join("Imgs/PS/RPG"),
+        ] {
+            assert!(dirs.contains(&expected), "{}", expected.display());
+        }
+        assert!(!dirs
+            .iter()
+            .any(|d| !d.starts_with(ALLIUM_GAMES_DIR.as_path())));
+    }
+}
@@ -1,17 +1,26 @@
 pub mod app;
 pub mod directory;
 pub mod game;
-mod gamelist;
+pub mod gamelist;
+pub mod group;
+pub mod icons;
+pub mod ignore;
 pub mod lazy_image;
+pub mod multi_disc;
+pub mod variant;
 
+use std::cmp::Ordering;
 use std::ffi::OsStr;
 use std::fmt::Debug;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use common::database::Database;
+use common::launcher::LauncherSettings;
 use common::locale::Locale;
+use common::stylesheet::ArtType;
 use lazy_static::lazy_static;
+use log::warn;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
@@ -19,6 +28,9 @@ use crate::consoles::ConsoleMapper;
 use crate::entry::app::App;
 use crate::entry::directory::Directory;
 use crate::entry::game::Game;
+use crate::entry::group::Group;
+use crate::entry::lazy_image::find_art;
+use crate::patch::PATCHES_DIR;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Entry {
@@ -44,9 +56,10 @@ impl Entry {
             .unwrap_or_default()
             .to_owned();
 
-        // Exclude Imgs and Guide directories
+        // Exclude Imgs, Guides and Patches directories
         if file_name == "Imgs"
             || file_name == "Guides"
+            || file_name == PATCHES_DIR
             || file_name == "gamelist.xml"
             || file_name == "miyoogamelist.xml"
         {
@@ -96,16 +109,126 @@ impl Entry {
         }
     }
 
+    /// Name used to order the entry alphabetically.
+    pub fn sort_name(&self) -> &str {
+        match self {
+            Entry::Game(game) => &game.full_name,
+            Entry::Directory(directory) => &directory.full_name,
+            Entry::App(app) => &app.name,
+        }
+    }
+
+    /// Compares entries by name, keeping directories first, then apps, then games.
+    pub fn cmp_by_name(&self, other: &Self, ignore_articles: bool) -> Ordering {
+        match (self, other) {
+            (Entry::Directory(_), Entry::Directory(_))
+            | (Entry::App(_), Entry::App(_))
+            | (Entry::Game(_), Entry::Game(_)) => {
+                let (a, b) = (self.sort_name(), other.sort_name());
+                if ignore_articles {
+                    natural_cmp(strip_article(a), strip_article(b))
+                } else {
+                    natural_cmp(a, b)
+                }
+            }
+            _ => self.cmp(other),
+        }
+    }
+
+    /// Image shown when the entry is selected. Directories without one show their icon.
     pub fn image(&mut self) -> Option<&Path> {
         match self {
             Entry::Game(game) => game.image(),
-            Entry::Directory(dir) => dir.image(),
+            Entry::Directory(dir) => dir.image.image().or(dir.icon.as_deref()),
             Entry::App(app) => app.image.as_deref(),
         }
     }
+
+    /// Icon drawn next to the entry's name.
+    pub fn icon(&self) -> Option<&Path> {
+        match self {
+            Entry::Directory(dir) => dir.icon.as_deref(),
+            Entry::Game(_) | Entry::App(_) => None,
+        }
+    }
+
+    /// Art of the given type, falling back to box art if there is none. Like box art, it's looked
+    /// up in the database, and only searched for if it hasn't been found while indexing.
+    pub fn art(&mut self, database: &Database, art_type: ArtType) -> Option<PathBuf> {
+        let art = match self {
+            _ if art_type == ArtType::Boxart => None,
+            Entry::Game(Game { path, .. }) | Entry::Directory(Directory { path, .. }) => {
+                match database.get_art(path, art_type) {
+                    Ok(Some(art)) => art,
+                    _ => {
+                        let art = find_art(path, art_type);
+                        if let Err(e) =
+                            database.update_art(art_type, &[(path.clone(), art.clone())])
+                        {
+                            warn!("failed to store art of {}: {}", path.display(), e);
+                        }
+                        art
+                    }
+                }
+            }
+            Entry::App(_) => None,
+        };
+        art.or_else(|| self.image().map(Path::to_path_buf))
+    }
 }
 
-fn short_name(name: &str) -> String {
+/// Compares names so that numbers are ordered by value, e.g. "Mega Man 2" comes before
+/// "Mega Man 10". Case is ignored unless the names are otherwise equal.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut x = a.chars().peekable();
+    let mut y = b.chars().peekable();
+    loop {
+        match (x.peek().copied(), y.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(c), Some(d)) if c.is_ascii_digit() && d.is_ascii_digit() => {
+                let take_number = |chars: &mut std::iter::Peekable<std::str::Chars<'_>>| {
+                    let mut number = String::new();
+                    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                        number.push(c);
+                    }
+                    number.trim_start_matches('0').to_string()
+                };
+                let (m, n) = (take_number(&mut x), take_number(&mut y));
+                let ordering = m.len().cmp(&n.len()).then_with(|| m.cmp(&n));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(c), Some(d)) => {
+                let ordering = c.to_lowercase().cmp(d.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                x.next();
+                y.next();
+            }
+        }
+    }
+}
+
+/// Removes a leading "The", "A" or "An" from a name, so that "The Legend of Zelda" is sorted
+/// under L.
+pub fn strip_article(name: &str) -> &str {
+    for article in ["the ", "a ", "an "] {
+        if name
+            .get(..article.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(article))
+        {
+            return name[article.len()..].trim_start();
+        }
+    }
+    name
+}
+
+/// Name of a game shown in listings, without leading numbers or trailing tags.
+pub fn short_name(name: &str) -> String {
     // Remove numbers
     lazy_static! {
         static ref NUMBERS_RE: Regex = Regex::new(r"^\d+[.\)]").unwrap();
@@ -129,10 +252,84 @@ pub trait Sort: Debug + Clone {
     fn button_hint(&self, locale: &Locale) -> String;
     fn next(&self) -> Self;
     fn with_directory(&self, directory: Directory) -> Self;
+    /// Directory being listed, for settings remembered per directory.
+    fn path(&self) -> Option<&Path> {
+        None
+    }
+    /// Whether the listing is of consoles, showing a summary of each.
+    fn is_console_view(&self, _settings: &LauncherSettings) -> bool {
+        false
+    }
+    /// Text shown above the list for each entry when it's selected, if the listing has any.
+    fn summaries(
+        &self,
+        _entries: &[Entry],
+        _database: &Database,
+        _locale: &Locale,
+        _settings: &LauncherSettings,
+    ) -> Result<Option<Vec<String>>> {
+        Ok(None)
+    }
+    /// Console that a flattened listing's games are limited to.
+    fn console(&self) -> Option<&str> {
+        None
+    }
+    /// The same order, with a flattened listing limited to a console's games, or to all games.
+    fn with_console(&self, _console: Option<String>) -> Self {
+        self.clone()
+    }
     fn entries(
         &self,
         database: &Database,
         console_mapper: &ConsoleMapper,
         locale: &Locale,
+        settings: &LauncherSettings,
     ) -> Result<Vec<Entry>>;
+    /// Groups of the entries, in the same order, for jumping between groups. Empty if the order
+    /// has no groups, e.g. when it's random.
+    fn groups(
+        &self,
+        entries: &[Entry],
+        _database: &Database,
+        _console_mapper: &ConsoleMapper,
+        settings: &LauncherSettings,
+    ) -> Result<Vec<Group>> {
+        Ok(group::by_letter(entries, settings.ignore_articles))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec![
+            "Mega Man 10",
+            "mega Man 2",
+            "Mega Man",
+            "Mega Man 2",
+            "Zelda",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec![
+                "Mega Man",
+                "Mega Man 2",
+                "mega Man 2",
+                "Mega Man 10",
+                "Zelda"
+            ]
+        );
+        assert_eq!(natural_cmp("Game 007", "Game 7"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_strip_article() {
+        assert_eq!(strip_article("The Legend of Zelda"), "Legend of Zelda");
+        assert_eq!(strip_article("an American Tail"), "American Tail");
+        assert_eq!(strip_article("Theme Park"), "Theme Park");
+        assert_eq!(strip_article("A"), "A");
+    }
 }
@@ -0,0 +1,246 @@
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    /// Matches a disc tag, e.g. "(Disc 1)", "(Disk 2 of 3)", "[CD1]".
+    static ref DISC_RE: Regex =
+        Regex::new(r"(?i)\s*[(\[](?:disc|disk|cd)\s*(\d+)(?:\s*of\s*\d+)?[)\]]").unwrap();
+    /// Matches a track tag, e.g. "(Track 1)".
+    static ref TRACK_RE: Regex = Regex::new(r"(?i)\s*\(track\s*\d+\)").unwrap();
+}
+
+/// Disc image extensions that can be listed in an m3u, in order of preference when a disc has
+/// more than one (e.g. a .cue and the .bin it points to).
+pub const DISC_EXTENSIONS: [&str; 11] = [
+    "cue", "gdi", "ccd", "mds", "toc", "chd", "pbp", "iso", "cdi", "img", "bin",
+];
+
+/// Directory and title that the discs of a set share.
+type SetKey = (PathBuf, String);
+
+/// The discs of a multi-disc game, found by naming convention.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscSet {
+    /// File name of the discs, without the disc tag. e.g. "Final Fantasy VII (USA)"
+    pub title: String,
+    /// Directory containing the discs.
+    pub directory: PathBuf,
+    /// The disc images, in disc order.
+    pub discs: Vec<PathBuf>,
+    /// Every file belonging to the set, including track files referenced by the discs.
+    pub files: Vec<PathBuf>,
+}
+
+impl DiscSet {
+    /// Path of the generated playlist. It is hidden so that it doesn't show up on its own.
+    pub fn m3u_path(&self) -> PathBuf {
+        self.directory.join(format!(".{}.m3u", self.title))
+    }
+
+    /// Contents of the m3u playlist, with disc paths relative to the playlist.
+    pub fn playlist(&self) -> String {
+        self.discs
+            .iter()
+            .filter_map(|disc| disc.file_name().and_then(OsStr::to_str))
+            .map(|disc| format!("{}\n", disc))
+            .collect()
+    }
+
+    /// Writes the m3u playlist if it has changed, returning its path.
+    pub fn write_m3u(&self) -> Result<PathBuf> {
+        let path = self.m3u_path();
+        let playlist = self.playlist();
+        if fs::read_to_string(&path).ok().as_deref() != Some(playlist.as_str()) {
+            fs::write(&path, playlist)?;
+        }
+        Ok(path)
+    }
+}
+
+/// Whether the path is of a playlist generated for a disc set, which is hidden.
+pub fn is_generated_playlist(path: &Path) -> bool {
+    path.file_name()
+        .and_then(OsStr::to_str)
+        .is_some_and(|name| name.starts_with('.') && name.ends_with(".m3u"))
+}
+
+/// Finds the disc set that a generated m3u playlist was written for, from the files next to it.
+pub fn find_set(m3u: &Path) -> Option<DiscSet> {
+    if !is_generated_playlist(m3u) {
+        return None;
+    }
+    let paths: Vec<_> = fs::read_dir(m3u.parent()?)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    group_discs(&paths)
+        .into_iter()
+        .find(|set| set.m3u_path() == m3u)
+}
+
+/// Splits the disc tag out of a file stem, returning the title and the disc number.
+/// e.g. "Final Fantasy VII (USA) (Disc 2)" returns ("Final Fantasy VII (USA)", 2)
+pub fn parse_disc(stem: &str) -> Option<(String, u32)> {
+    let captures = DISC_RE.captures(stem)?;
+    let number = captures[1].parse().ok()?;
+    let tag = captures.get(0)?;
+    let title = format!("{}{}", &stem[..tag.start()], &stem[tag.end()..]);
+    Some((title.trim().to_string(), number))
+}
+
+/// Removes the disc tag from a name, e.g. "Final Fantasy VII (Disc 1)" becomes "Final Fantasy VII".
+pub fn strip_disc(name: &str) -> String {
+    DISC_RE.replace(name, "").trim().to_string()
+}
+
+/// Finds the multi-disc sets among the given paths. Sets need at least two different discs.
+pub fn group_discs(paths: &[PathBuf]) -> Vec<DiscSet> {
+    let mut sets: BTreeMap<SetKey, BTreeMap<u32, (usize, &Path)>> = BTreeMap::new();
+
+    for path in paths {
+        let Some((title, number)) = path
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .and_then(parse_disc)
+        else {
+            continue;
+        };
+        let extension = path
+            .extension()
+            .and_then(OsStr::to_str)
+            .unwrap_or_default()
+            .to_lowercase();
+        let Some(priority) = DISC_EXTENSIONS.iter().position(|e| *e == extension) else {
+            continue;
+        };
+        let directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+        let disc = sets
+            .entry((directory, title))
+            .or_default()
+            .entry(number)
+            .or_insert((priority, path));
+        if priority < disc.0 {
+            *disc = (priority, path);
+        }
+    }
+    sets.retain(|_, discs| discs.len() > 1);
+
+    let mut files: BTreeMap<&SetKey, HashSet<&Path>> = BTreeMap::new();
+    for path in paths {
+        let Some((title, _)) = path
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .and_then(parse_disc)
+        else {
+            continue;
+        };
+        let title = TRACK_RE.replace_all(&title, "").trim().to_string();
+        let directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        if let Some((key, _)) = sets.get_key_value(&(directory, title)) {
+            files.entry(key).or_default().insert(path);
+        }
+    }
+
+    sets.iter()
+        .map(|(key, discs)| {
+            let (directory, title) = key;
+            let mut files: Vec<_> = files
+                .get(key)
+                .into_iter()
+                .flatten()
+                .map(|p| p.to_path_buf())
+                .collect();
+            files.sort();
+            DiscSet {
+                title: title.clone(),
+                directory: directory.clone(),
+                discs: discs.values().map(|(_, p)| p.to_path_buf()).collect(),
+                files,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_disc() {
+        assert_eq!(
+            parse_disc("Final Fantasy VII (USA) (Disc 2)"),
+            Some(("Final Fantasy VII (USA)".to_string(), 2))
+        );
+        assert_eq!(
+            parse_disc("Metal Gear Solid (Disk 1 of 2) (USA)"),
+            Some(("Metal Gear Solid (USA)".to_string(), 1))
+        );
+        assert_eq!(parse_disc("Riven [CD3]"), Some(("Riven".to_string(), 3)));
+        assert_eq!(parse_disc("Discworld (USA)"), None);
+        assert_eq!(
+            strip_disc("Final Fantasy VII (Disc 1)"),
+            "Final Fantasy VII"
+        );
+    }
+
+    #[test]
+    fn test_group_discs() {
+        let dir = Path::new("Roms/PS");
+        let paths: Vec<_> = [
+            "Game (USA) (Disc 1).cue",
+            "Game (USA) (Disc 1) (Track 1).bin",
+            "Game (USA) (Disc 1) (Track 2).bin",
+            "Game (USA) (Disc 2).chd",
+            "Game (USA) (Disc 2).m3u",
+            "Other (USA) (Disc 1).chd",
+            "Single (USA).chd",
+            "Raw (Disc 1).bin",
+            "Raw (Disc 2).bin",
+        ]
+        .iter()
+        .map(|p| dir.join(p))
+        .collect();
+
+        let sets = group_discs(&paths);
+        assert_eq!(sets.len(), 2);
+
+        let set = &sets[0];
+        assert_eq!(set.title, "Game (USA)");
+        assert_eq!(set.directory, dir);
+        assert_eq!(
+            set.discs,
+            vec![
+                dir.join("Game (USA) (Disc 1).cue"),
+                dir.join("Game (USA) (Disc 2).chd"),
+            ]
+        );
+        assert_eq!(
+            set.files,
+            vec![
+                dir.join("Game (USA) (Disc 1) (Track 1).bin"),
+                dir.join("Game (USA) (Disc 1) (Track 2).bin"),
+                dir.join("Game (USA) (Disc 1).cue"),
+                dir.join("Game (USA) (Disc 2).chd"),
+                dir.join("Game (USA) (Disc 2).m3u"),
+            ]
+        );
+        assert_eq!(
+            set.playlist(),
+            "Game (USA) (Disc 1).cue\nGame (USA) (Disc 2).chd\n"
+        );
+        assert_eq!(set.m3u_path(), dir.join(".Game (USA).m3u"));
+
+        assert_eq!(
+            sets[1].discs,
+            vec![dir.join("Raw (Disc 1).bin"), dir.join("Raw (Disc 2).bin")]
+        );
+    }
+}
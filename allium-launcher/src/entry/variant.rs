@@ -0,0 +1,155 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+use common::database::Database;
+
+use crate::entry::Entry;
+
+/// Tags in the parentheses of a name, e.g. "USA, Europe" and "Rev 1" for
+/// "Game (USA, Europe) (Rev 1)".
+fn tags(name: &str) -> impl Iterator<Item = &str> {
+    name.split('(')
+        .skip(1)
+        .filter_map(|s| s.split_once(')').map(|(tag, _)| tag.trim()))
+}
+
+/// Text telling variants of a game apart: the tags of its full name, e.g. "USA, Rev 1".
+pub fn label(full_name: &str) -> String {
+    tags(full_name).collect::<Vec<_>>().join(", ")
+}
+
+/// Revision of a variant, e.g. 1 for "Rev 1" or "Rev A". Zero if it isn't a revision.
+fn revision(full_name: &str) -> u32 {
+    tags(full_name)
+        .filter_map(|tag| tag.strip_prefix("Rev "))
+        .filter_map(|rev| match rev.parse() {
+            Ok(rev) => Some(rev),
+            Err(_) => rev
+                .chars()
+                .next()
+                .filter(char::is_ascii_uppercase)
+                .map(|c| c as u32 - 'A' as u32 + 1),
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Rank of a variant; lower is preferred. Variants for the most preferred region come first, then
+/// later revisions.
+fn rank(full_name: &str, regions: &[String]) -> (usize, Reverse<u32>) {
+    let region = tags(full_name)
+        .flat_map(|tag| tag.split(','))
+        .filter_map(|region| regions.iter().position(|r| r == region.trim()))
+        .min()
+        .unwrap_or(regions.len());
+    (region, Reverse(revision(full_name)))
+}
+
+/// Index of the preferred variant among their full names. The first one wins a tie.
+pub fn preferred(full_names: &[&str], regions: &[String]) -> usize {
+    full_names
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, name)| rank(name, regions))
+        .map_or(0, |(i, _)| i)
+}
+
+/// Lists games of the same name in the same directory as one entry, where the first of them was.
+/// The entry is the variant played most recently, or if none have been played, the one preferred
+/// by `regions`. Every variant is kept in its `variants`.
+pub fn collapse(
+    entries: Vec<Entry>,
+    database: &Database,
+    regions: &[String],
+) -> Result<Vec<Entry>> {
+    let mut collapsed: Vec<Entry> = Vec::with_capacity(entries.len());
+    let mut positions: HashMap<(Option<&Path>, String), usize> = HashMap::new();
+    let mut variants: Vec<Vec<_>> = Vec::new();
+
+    for entry in &entries {
+        let Entry::Game(game) = entry else {
+            collapsed.push(entry.clone());
+            continue;
+        };
+        let key = (game.path.parent(), game.name.clone());
+        match positions.get(&key) {
+            Some(&i) => variants[i].push(game.clone()),
+            None => {
+                positions.insert(key, variants.len());
+                variants.push(vec![game.clone()]);
+                collapsed.push(entry.clone());
+            }
+        }
+    }
+
+    let mut variants = variants.into_iter();
+    for entry in collapsed.iter_mut() {
+        if !matches!(entry, Entry::Game(_)) {
+            continue;
+        }
+        let Some(variants) = variants.next() else {
+            break;
+        };
+        if variants.len() < 2 {
+            continue;
+        }
+
+        let mut last_played = Vec::with_capacity(variants.len());
+        for game in &variants {
+            last_played.push(database.get_last_played_at(&game.path)?);
+        }
+        let chosen = match last_played.iter().enumerate().max_by_key(|(_, t)| **t) {
+            Some((i, Some(_))) => i,
+            _ => preferred(
+                &variants
+                    .iter()
+                    .map(|g| g.full_name.as_str())
+                    .collect::<Vec<_>>(),
+                regions,
+            ),
+        };
+
+        let mut game = variants[chosen].clone();
+        game.variants = variants;
+        *entry = Entry::Game(game);
+    }
+
+    Ok(collapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label() {
+        assert_eq!(label("Game (USA, Europe) (Rev 1)"), "USA, Europe, Rev 1");
+        assert_eq!(label("Game"), "");
+    }
+
+    #[test]
+    fn test_preferred() {
+        let regions: Vec<String> = ["USA", "Europe", "Japan"]
+            .iter()
+            .map(|r| r.to_string())
+            .collect();
+        assert_eq!(
+            preferred(&["Game (Japan)", "Game (Europe)", "Game (USA)"], &regions),
+            2
+        );
+        assert_eq!(
+            preferred(
+                &["Game (USA)", "Game (USA) (Rev 1)", "Game (Japan)"],
+                &regions
+            ),
+            1
+        );
+        assert_eq!(
+            preferred(&["Game (Japan)", "Game (USA, Europe) (Rev A)"], &regions),
+            1
+        );
+        assert_eq!(preferred(&["Game (Brazil)", "Game (Korea)"], &regions), 0);
+    }
+}
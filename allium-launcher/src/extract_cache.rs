@@ -0,0 +1,281 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{bail, Result};
+use common::constants::{
+    ALLIUM_EXTRACT_CACHE_DIR, EXTRACT_CACHE_MAX_SIZE, EXTRACT_CACHE_MIN_FREE_SPACE,
+};
+use log::{debug, info, warn};
+use sysinfo::{DiskExt, SystemExt};
+
+use crate::{archive, patch};
+
+/// Written once an archive has been fully extracted. It holds the archive's fingerprint, and its
+/// modification time records when the extracted game was last launched.
+const MARKER: &str = ".allium-extracted";
+
+/// Games extracted from archives for cores that can't load them, and ROMs with patches applied
+/// for emulators that can't soft patch, kept on the SD card so that relaunching them is fast.
+/// The least recently launched games are evicted when the cache grows past its size limit or the
+/// SD card runs low on space.
+pub struct ExtractCache {
+    dir: PathBuf,
+    max_size: u64,
+    min_free_space: u64,
+}
+
+/// An extracted archive in the cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CacheEntry {
+    path: PathBuf,
+    size: u64,
+    last_used: SystemTime,
+}
+
+impl Default for ExtractCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExtractCache {
+    pub fn new() -> Self {
+        Self {
+            dir: ALLIUM_EXTRACT_CACHE_DIR.clone(),
+            max_size: EXTRACT_CACHE_MAX_SIZE,
+            min_free_space: EXTRACT_CACHE_MIN_FREE_SPACE,
+        }
+    }
+
+    /// Returns the directory the archive is extracted to, extracting it first if it isn't
+    /// already cached. `size` is the uncompressed size of the archive's files.
+    pub fn extract(&self, path: &Path, size: u64) -> Result<PathBuf> {
+        let fingerprint = archive::fingerprint(path)?.to_string();
+        self.cached(path, &fingerprint, |dest| {
+            self.make_room(size, dest)?;
+            info!("extracting {} to {}", path.display(), dest.display());
+            archive::extract(path, dest)
+        })
+    }
+
+    /// Returns a copy of the ROM with the patch applied, patching it first if it isn't already
+    /// cached. The copy keeps the ROM's file name, so that cores recognize it.
+    pub fn patch(&self, rom: &Path, patch: &Path) -> Result<PathBuf> {
+        let Some(file_name) = rom.file_name() else {
+            bail!("{} is not a file", rom.display());
+        };
+        let fingerprint = format!(
+            "{}:{}",
+            archive::fingerprint(rom)?,
+            archive::fingerprint(patch)?
+        );
+        let dir = self.cached(patch, &fingerprint, |dest| {
+            info!("patching {} with {}", rom.display(), patch.display());
+            let patched = patch::apply(&fs::read(rom)?, patch)?;
+            self.make_room(patched.len() as u64, dest)?;
+            fs::write(dest.join(file_name), patched)?;
+            Ok(())
+        })?;
+        Ok(dir.join(file_name))
+    }
+
+    /// Returns the cache directory for `path`. If it is missing or was made from a different
+    /// version of the file, `fill` is called to populate it again.
+    fn cached(
+        &self,
+        path: &Path,
+        fingerprint: &str,
+        fill: impl FnOnce(&Path) -> Result<()>,
+    ) -> Result<PathBuf> {
+        let dest = self.dir.join(Self::key(path));
+        let marker = dest.join(MARKER);
+
+        if fs::read_to_string(&marker).is_ok_and(|f| f == fingerprint) {
+            debug!("using cached {}", dest.display());
+            // Rewriting the marker marks the entry as recently used
+            fs::write(&marker, fingerprint)?;
+            return Ok(dest);
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        if dest.exists() {
+            fs::remove_dir_all(&dest)?;
+        }
+        fs::create_dir_all(&dest)?;
+        if let Err(e) = fill(&dest) {
+            fs::remove_dir_all(&dest).ok();
+            return Err(e);
+        }
+        fs::write(&marker, fingerprint)?;
+
+        Ok(dest)
+    }
+
+    /// Name of the cache directory for a file. The hash of the full path keeps files with the
+    /// same name in different directories apart.
+    fn key(path: &Path) -> String {
+        let hash = archive::crc32(path.as_os_str().as_encoded_bytes()).unwrap_or_default();
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy())
+            .unwrap_or_default();
+        format!("{:08x}-{}", hash, stem)
+    }
+
+    /// Evicts cached games, other than the one being made in `dest`, until `needed` more bytes fit.
+    fn make_room(&self, needed: u64, dest: &Path) -> Result<()> {
+        let mut entries = self.entries()?;
+        entries.retain(|e| e.path != dest);
+        let free = available_space(&self.dir);
+        for path in evictions(entries, needed, free, self.max_size, self.min_free_space)? {
+            info!("evicting {}", path.display());
+            if let Err(e) = fs::remove_dir_all(&path) {
+                warn!("failed to evict {}: {}", path.display(), e);
+            }
+        }
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if !path.is_dir() {
+                continue;
+            }
+            // Unfinished extractions have no marker, and are evicted first
+            let last_used = fs::metadata(path.join(MARKER))
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.push(CacheEntry {
+                size: dir_size(&path),
+                path,
+                last_used,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+/// Picks the least recently used entries to evict so that `needed` bytes fit in the cache
+/// without going over `max_size` or leaving less than `min_free_space` free on the disk.
+/// `needed` comes from the archive's headers, so a game larger than `max_size` is refused.
+fn evictions(
+    mut entries: Vec<CacheEntry>,
+    needed: u64,
+    mut free: Option<u64>,
+    max_size: u64,
+    min_free_space: u64,
+) -> Result<Vec<PathBuf>> {
+    if needed > max_size {
+        bail!("the game is too large to extract: {} bytes", needed);
+    }
+
+    // Least recently used last, so that it's popped first
+    entries.sort_by_key(|e| Reverse(e.last_used));
+    let mut total = entries
+        .iter()
+        .fold(0u64, |total, e| total.saturating_add(e.size));
+
+    let mut evicted = Vec::new();
+    loop {
+        let low_space = free.is_some_and(|free| free < needed.saturating_add(min_free_space));
+        if !low_space && total.saturating_add(needed) <= max_size {
+            break;
+        }
+        let Some(entry) = entries.pop() else {
+            if low_space {
+                bail!("not enough free space to extract the game");
+            }
+            break;
+        };
+        total = total.saturating_sub(entry.size);
+        free = free.map(|free| free.saturating_add(entry.size));
+        evicted.push(entry.path);
+    }
+    Ok(evicted)
+}
+
+/// Total size of the files in a directory.
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Space available on the disk containing the path, if it can be found.
+fn available_space(path: &Path) -> Option<u64> {
+    let path = path.canonicalize().ok()?;
+    let mut sys = sysinfo::System::new();
+    sys.refresh_disks_list();
+    sys.disks()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn entry(name: &str, size: u64, last_used: u64) -> CacheEntry {
+        CacheEntry {
+            path: PathBuf::from(name),
+            size,
+            last_used: SystemTime::UNIX_EPOCH + Duration::from_secs(last_used),
+        }
+    }
+
+    #[test]
+    fn test_evictions() {
+        let entries = vec![entry("b", 30, 2), entry("a", 30, 1), entry("c", 30, 3)];
+
+        // Fits without evicting anything
+        assert!(evictions(entries.clone(), 10, Some(1000), 100, 100)
+            .unwrap()
+            .is_empty());
+
+        // Over the size limit, so the least recently used are evicted
+        assert_eq!(
+            evictions(entries.clone(), 40, Some(1000), 100, 100).unwrap(),
+            vec![PathBuf::from("a")]
+        );
+        assert_eq!(
+            evictions(entries.clone(), 100, None, 100, 100).unwrap(),
+            vec![PathBuf::from("a"), PathBuf::from("b"), PathBuf::from("c")]
+        );
+
+        // Games larger than the cache are refused, however large their headers claim they are
+        assert!(evictions(entries.clone(), 200, None, 100, 100).is_err());
+        assert!(evictions(entries.clone(), u64::MAX, None, 100, 100).is_err());
+        assert!(evictions(entries.clone(), 50, Some(1000), 100, u64::MAX).is_err());
+        assert!(evictions(
+            vec![entry("huge", u64::MAX, 1), entry("a", 30, 2)],
+            10,
+            Some(u64::MAX - 5),
+            100,
+            100
+        )
+        .is_ok());
+
+        // Low on disk space
+        assert_eq!(
+            evictions(entries.clone(), 10, Some(60), 100, 100).unwrap(),
+            vec![PathBuf::from("a"), PathBuf::from("b")]
+        );
+        assert!(evictions(entries, 10, Some(10), 100, 100).is_err());
+    }
+}
@@ -0,0 +1,592 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Result};
+use common::constants::{
+    ALLIUM_GAMES_DIR, ALLIUM_SAVES_DIR, ALLIUM_SD_ROOT, ALLIUM_STATES_DIR, ALLIUM_TRASH_DIR,
+};
+use common::database::Database;
+use common::game_info::find_guide;
+use common::stylesheet::ArtType;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::consoles::ConsoleMapper;
+use crate::entry::lazy_image::{art_dir, find_art};
+use crate::entry::{multi_disc, short_name};
+use crate::patch::PATCHES_DIR;
+
+/// How long trashed games are kept before they are deleted for good.
+pub const TRASH_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Name of the file in each trash item that records what was trashed.
+const TRASH_ITEM_FILE: &str = "item.json";
+
+/// Files that belong to a game and should follow it when it moves from `old` to `new`: its art,
+/// guide, saves and save states. Returns where each file is and where it should go.
+///
+/// Art and guides are moved next to the new path, while saves and states are renamed in place.
+/// Art shared through libretro-thumbnails folders is left alone.
+pub fn companions(
+    console_mapper: &ConsoleMapper,
+    database: &Database,
+    old: &Path,
+    new: &Path,
+) -> Vec<(PathBuf, PathBuf)> {
+    let (Some(old_stem), Some(new_stem), Some(new_dir)) = (
+        old.file_stem().and_then(|s| s.to_str()),
+        new.file_stem().and_then(|s| s.to_str()),
+        new.parent(),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    for art_type in ArtType::ALL {
+        let Some(art) = find_art(old, art_type) else {
+            continue;
+        };
+        let shared = art.components().any(|c| {
+            c.as_os_str()
+                .to_str()
+                .is_some_and(|c| c.starts_with("Named_"))
+        });
+        if shared {
+            continue;
+        }
+        let mut target = new_dir.join(art_dir(art_type)).join(new_stem);
+        if let Some(ext) = art.extension() {
+            target.set_extension(ext);
+        }
+        files.push((art, target));
+    }
+
+    if let Some(guide) = find_guide(old) {
+        files.push((
+            guide,
+            new_dir.join("Guides").join(format!("{}.txt", new_stem)),
+        ));
+    }
+
+    for dir in save_dirs(console_mapper, database, old) {
+        let Ok(read_dir) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            let file_name = entry.file_name();
+            let Some(suffix) = file_name.to_str().and_then(|n| save_suffix(n, old_stem)) else {
+                continue;
+            };
+            let path = entry.path();
+            if path.is_file() {
+                files.push((path, dir.join(format!("{}.{}", new_stem, suffix))));
+            }
+        }
+    }
+
+    files
+}
+
+/// Folders that RetroArch keeps a game's saves and states in: the folders in the saves and states
+/// folders named after each core that the game can be played with. Files directly in the saves
+/// and states folders may belong to another console's game of the same name, so they're left.
+fn save_dirs(console_mapper: &ConsoleMapper, database: &Database, game: &Path) -> Vec<PathBuf> {
+    let mut cores = console_mapper
+        .get_console(database, game)
+        .map(|console| console.cores.clone())
+        .unwrap_or_default();
+    if let Ok(Some(core)) = database.get_core(game) {
+        cores.push(core);
+    }
+
+    let mut dirs = Vec::new();
+    for base in [ALLIUM_SAVES_DIR.as_path(), ALLIUM_STATES_DIR.as_path()] {
+        for core in &cores {
+            let dir = base.join(console_mapper.get_core_name(core));
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    dirs
+}
+
+/// If `file_name` is a save, state or state thumbnail of the game with the file stem `stem`,
+/// returns what follows the stem, e.g. "srm" or "state1.png".
+fn save_suffix<'a>(file_name: &'a str, stem: &str) -> Option<&'a str> {
+    let suffix = file_name.strip_prefix(stem)?.strip_prefix('.')?;
+    let kind = match suffix {
+        "png" => return Some(suffix),
+        _ => suffix.strip_suffix(".png").unwrap_or(suffix),
+    };
+    let is_save = match kind {
+        "srm" | "rtc" | "state" | "state.auto" => true,
+        _ => kind
+            .strip_prefix("state")
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit())),
+    };
+    is_save.then_some(suffix)
+}
+
+/// Moves a file, creating the folder it goes into.
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(from, to)?;
+    Ok(())
+}
+
+/// Files that make up a game besides the game itself: for the generated playlist of a multi-disc
+/// game, the files of its discs.
+fn disc_files(game: &Path) -> Vec<PathBuf> {
+    multi_disc::find_set(game).map_or_else(Vec::new, |set| set.files)
+}
+
+/// Moves a game from `old` to `new` along with its companion files, keeping its play history.
+pub fn move_game(
+    console_mapper: &ConsoleMapper,
+    database: &Database,
+    old: &Path,
+    new: &Path,
+) -> Result<()> {
+    if new.exists() {
+        bail!("{} already exists", new.display());
+    }
+    // The discs of a multi-disc game keep their names, so they can only move between folders
+    let discs = disc_files(old);
+    if !discs.is_empty() && old.file_name() != new.file_name() {
+        bail!("can't rename multi-disc game {}", old.display());
+    }
+    let Some(new_dir) = new.parent() else {
+        bail!("{} has no parent", new.display());
+    };
+    let mut files = companions(console_mapper, database, old, new);
+    for disc in discs {
+        if let Some(file_name) = disc.file_name() {
+            let target = new_dir.join(file_name);
+            if target.exists() {
+                bail!("{} already exists", target.display());
+            }
+            files.push((disc, target));
+        }
+    }
+    move_file(old, new)?;
+    for (from, to) in files {
+        if let Err(e) = move_file(&from, &to) {
+            warn!(
+                "failed to move {} to {}: {}",
+                from.display(),
+                to.display(),
+                e
+            );
+        }
+    }
+    database.update_game_path(old, new)?;
+    database.update_game_images(&[(new.to_path_buf(), find_art(new, ArtType::Boxart))])?;
+    Ok(())
+}
+
+/// Moves a game into another folder, keeping its file name. Returns its new path.
+pub fn move_game_to(
+    console_mapper: &ConsoleMapper,
+    database: &Database,
+    path: &Path,
+    dir: &Path,
+) -> Result<PathBuf> {
+    let Some(file_name) = path.file_name() else {
+        bail!("{} has no file name", path.display());
+    };
+    let new = dir.join(file_name);
+    move_game(console_mapper, database, path, &new)?;
+    Ok(new)
+}
+
+/// Renames a game to `name`, keeping its extension. Returns its new path.
+pub fn rename_game(
+    console_mapper: &ConsoleMapper,
+    database: &Database,
+    path: &Path,
+    name: &str,
+) -> Result<PathBuf> {
+    let name = name.trim();
+    if name.is_empty() || name.contains('/') || name.starts_with('.') {
+        bail!("invalid file name: {:?}", name);
+    }
+    // The name may contain dots of its own, e.g. "Game v1.1", so the extension is appended
+    let new = match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => path.with_file_name(format!("{}.{}", name, ext)),
+        None => path.with_file_name(name),
+    };
+    if new != path {
+        move_game(console_mapper, database, path, &new)?;
+    }
+    database.set_name(&new, &short_name(name))?;
+    Ok(new)
+}
+
+/// Folders that games can be moved to: the console folders in the games directory and the folders
+/// in them, with their path relative to the games directory.
+pub fn move_targets() -> Vec<(PathBuf, String)> {
+    let mut targets = Vec::new();
+    let mut dirs = subdirectories(&ALLIUM_GAMES_DIR);
+    dirs.sort();
+    for dir in dirs {
+        let mut children = subdirectories(&dir);
+        children.sort();
+        targets.push(dir);
+        targets.extend(children);
+    }
+    targets
+        .into_iter()
+        .map(|dir| {
+            let label = dir
+                .strip_prefix(ALLIUM_GAMES_DIR.as_path())
+                .unwrap_or(&dir)
+                .display()
+                .to_string();
+            (dir, label)
+        })
+        .collect()
+}
+
+/// Folders in `dir` that could hold games, leaving out hidden, art, guide and patch folders.
+fn subdirectories(dir: &Path) -> Vec<PathBuf> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    read_dir
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter(|path| {
+            path.file_name().and_then(|n| n.to_str()).is_some_and(|n| {
+                !n.starts_with('.')
+                    && n != "Imgs"
+                    && n != "Guides"
+                    && n != PATCHES_DIR
+                    && !n.starts_with("Named_")
+            })
+        })
+        .collect()
+}
+
+/// What a trash item holds: the games deleted together.
+#[derive(Debug, Serialize, Deserialize)]
+struct TrashItem {
+    games: Vec<TrashedGame>,
+}
+
+/// The path a game was at, and the paths of the files trashed with it.
+#[derive(Debug, Serialize, Deserialize)]
+struct TrashedGame {
+    game: PathBuf,
+    files: Vec<PathBuf>,
+}
+
+/// Deleted games, kept until they are purged so that deleting can be undone.
+///
+/// Games deleted together are kept in one folder named by when they were trashed, with their
+/// files laid out as they were relative to the SD card, so that undoing restores all of them.
+/// Games keep their rows in the database, pointing into the trash, so that restoring them keeps
+/// their play history.
+pub struct Trash {
+    dir: PathBuf,
+    root: PathBuf,
+}
+
+impl Trash {
+    pub fn new() -> Self {
+        Self::at(ALLIUM_TRASH_DIR.to_path_buf(), ALLIUM_SD_ROOT.to_path_buf())
+    }
+
+    /// Trash in `dir`, for files under `root`.
+    pub fn at(dir: PathBuf, root: PathBuf) -> Self {
+        Self { dir, root }
+    }
+
+    /// Where a file is kept in a trash item.
+    fn trashed(&self, item: &Path, path: &Path) -> PathBuf {
+        let relative = path
+            .strip_prefix(&self.root)
+            .or_else(|_| path.strip_prefix("/"))
+            .unwrap_or(path);
+        item.join("files").join(relative)
+    }
+
+    /// Trash items, oldest first.
+    fn items(&self) -> Vec<PathBuf> {
+        let Ok(read_dir) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut items: Vec<_> = read_dir
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.join(TRASH_ITEM_FILE).is_file())
+            .collect();
+        items.sort_by_key(|item| trashed_at(item));
+        items
+    }
+
+    fn read_item(item: &Path) -> Result<TrashItem> {
+        Ok(serde_json::from_str(&fs::read_to_string(
+            item.join(TRASH_ITEM_FILE),
+        )?)?)
+    }
+
+    pub fn has_items(&self) -> bool {
+        !self.items().is_empty()
+    }
+
+    /// Moves games and their companion files into the trash, as one item. Games that can't be
+    /// moved are left in place, and the first error is returned after the rest are trashed.
+    pub fn trash(
+        &self,
+        console_mapper: &ConsoleMapper,
+        database: &Database,
+        games: &[PathBuf],
+    ) -> Result<()> {
+        let mut time = now();
+        while self.dir.join(time.to_string()).exists() {
+            time += 1;
+        }
+        let item = self.dir.join(time.to_string());
+
+        let mut trashed = Vec::with_capacity(games.len());
+        let mut error = None;
+        for game in games {
+            match self.trash_game(console_mapper, database, &item, game) {
+                Ok(game) => trashed.push(game),
+                Err(e) => {
+                    warn!("failed to trash {}: {}", game.display(), e);
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        if trashed.is_empty() {
+            let _ = fs::remove_dir_all(&item);
+        } else {
+            fs::write(
+                item.join(TRASH_ITEM_FILE),
+                serde_json::to_string(&TrashItem { games: trashed })?,
+            )?;
+        }
+        match error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Moves a game and its companion files into a trash item.
+    fn trash_game(
+        &self,
+        console_mapper: &ConsoleMapper,
+        database: &Database,
+        item: &Path,
+        game: &Path,
+    ) -> Result<TrashedGame> {
+        let files: Vec<_> = disc_files(game)
+            .into_iter()
+            .chain(
+                companions(console_mapper, database, game, game)
+                    .into_iter()
+                    .map(|(file, _)| file),
+            )
+            .collect();
+
+        let trashed_game = self.trashed(item, game);
+        move_file(game, &trashed_game)?;
+        let mut trashed_files = Vec::with_capacity(files.len());
+        for file in files {
+            match move_file(&file, &self.trashed(item, &file)) {
+                Ok(()) => trashed_files.push(file),
+                Err(e) => warn!("failed to trash {}: {}", file.display(), e),
+            }
+        }
+        database.update_game_path(game, &trashed_game)?;
+        Ok(TrashedGame {
+            game: game.to_path_buf(),
+            files: trashed_files,
+        })
+    }
+
+    /// Puts the most recently deleted games and their files back where they were. Returns the
+    /// paths of the games, which is empty if the trash is empty.
+    pub fn restore(&self, database: &Database) -> Result<Vec<PathBuf>> {
+        let Some(item) = self.items().pop() else {
+            return Ok(Vec::new());
+        };
+        let TrashItem { games } = Self::read_item(&item)?;
+        if let Some(TrashedGame { game, .. }) = games.iter().find(|g| g.game.exists()) {
+            bail!("{} already exists", game.display());
+        }
+
+        let mut restored = Vec::with_capacity(games.len());
+        for TrashedGame { game, files } in games {
+            let trashed_game = self.trashed(&item, &game);
+            move_file(&trashed_game, &game)?;
+            for file in files {
+                if let Err(e) = move_file(&self.trashed(&item, &file), &file) {
+                    warn!("failed to restore {}: {}", file.display(), e);
+                }
+            }
+            database.update_game_path(&trashed_game, &game)?;
+            restored.push(game);
+        }
+        fs::remove_dir_all(&item)?;
+        Ok(restored)
+    }
+
+    /// Deletes games that were trashed longer than `age` ago for good, along with their play
+    /// history.
+    pub fn purge(&self, database: &Database, age: Duration) -> Result<()> {
+        let now = now();
+        for item in self.items() {
+            if now.saturating_sub(trashed_at(&item)) < age.as_millis() {
+                continue;
+            }
+            info!("purging {} from the trash", item.display());
+            if let Ok(TrashItem { games }) = Self::read_item(&item) {
+                for TrashedGame { game, .. } in games {
+                    database.delete_game(&self.trashed(&item, &game))?;
+                }
+            }
+            fs::remove_dir_all(&item)?;
+        }
+        Ok(())
+    }
+}
+
+/// Current time in milliseconds, which trash items are named by.
+fn now() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis())
+}
+
+/// When a trash item was trashed, in milliseconds.
+fn trashed_at(item: &Path) -> u128 {
+    item.file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use common::database::NewGame;
+
+    use super::*;
+
+    #[test]
+    fn test_trash() -> Result<()> {
+        let root = std::env::temp_dir().join(format!("allium-test-trash-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let game = root.join("Roms/GB/Game (USA).gb");
+        let image = root.join("Roms/GB/Imgs/Game (USA).png");
+        fs::create_dir_all(image.parent().unwrap())?;
+        fs::write(&game, "rom")?;
+        fs::write(&image, "png")?;
+
+        let database = Database::in_memory()?;
+        database.update_games(&[NewGame {
+            name: "Game".to_string(),
+            path: game.clone(),
+            image: None,
+            core: None,
+        }])?;
+        database.increment_play_count("Game", &game, None)?;
+
+        let console_mapper = ConsoleMapper::new();
+        let renamed = rename_game(&console_mapper, &database, &game, "Better Game (USA)")?;
+        assert_eq!(renamed, root.join("Roms/GB/Better Game (USA).gb"));
+        assert!(!game.exists());
+        assert!(root.join("Roms/GB/Imgs/Better Game (USA).png").is_file());
+        let row = database.select_game(&renamed)?.unwrap();
+        assert_eq!(row.name, "Better Game");
+        assert_eq!(row.play_count, 1);
+        assert_eq!(
+            row.image,
+            Some(root.join("Roms/GB/Imgs/Better Game (USA).png"))
+        );
+        assert!(rename_game(&console_mapper, &database, &renamed, "../Escape").is_err());
+
+        let trash = Trash::at(root.join(".trash"), root.clone());
+        assert!(!trash.has_items());
+        trash.trash(&console_mapper, &database, std::slice::from_ref(&renamed))?;
+        assert!(!renamed.exists());
+        assert!(!root.join("Roms/GB/Imgs/Better Game (USA).png").exists());
+        assert!(trash.has_items());
+        assert!(database.select_game(&renamed)?.is_none());
+
+        assert_eq!(trash.restore(&database)?, vec![renamed.clone()]);
+        assert!(renamed.is_file());
+        assert!(root.join("Roms/GB/Imgs/Better Game (USA).png").is_file());
+        assert_eq!(database.select_game(&renamed)?.unwrap().play_count, 1);
+        assert!(!trash.has_items());
+
+        trash.trash(&console_mapper, &database, std::slice::from_ref(&renamed))?;
+        trash.purge(&database, Duration::from_secs(60))?;
+        assert!(trash.has_items());
+        trash.purge(&database, Duration::ZERO)?;
+        assert!(!trash.has_items());
+        assert_eq!(database.select_all_games()?.len(), 0);
+
+        // Dots in the new name are kept
+        fs::write(&game, "rom")?;
+        let renamed = rename_game(&console_mapper, &database, &game, "Game v1.1")?;
+        assert_eq!(renamed, root.join("Roms/GB/Game v1.1.gb"));
+
+        // The discs of a multi-disc game are trashed and restored with its playlist
+        let discs = [
+            root.join("Roms/PS/Game (Disc 1).chd"),
+            root.join("Roms/PS/Game (Disc 2).chd"),
+        ];
+        fs::create_dir_all(root.join("Roms/PS"))?;
+        for disc in &discs {
+            fs::write(disc, "chd")?;
+        }
+        let m3u = root.join("Roms/PS/.Game.m3u");
+        fs::write(&m3u, "Game (Disc 1).chd\nGame (Disc 2).chd\n")?;
+        assert!(rename_game(&console_mapper, &database, &m3u, "Other").is_err());
+        trash.trash(&console_mapper, &database, std::slice::from_ref(&m3u))?;
+        assert!(!m3u.exists());
+        assert!(discs.iter().all(|disc| !disc.exists()));
+        trash.restore(&database)?;
+        assert!(m3u.is_file());
+        assert!(discs.iter().all(|disc| disc.is_file()));
+
+        // Games deleted together are restored together
+        let games = [root.join("Roms/GB/One.gb"), root.join("Roms/GB/Two.gb")];
+        for game in &games {
+            fs::write(game, "rom")?;
+        }
+        trash.trash(&console_mapper, &database, &games)?;
+        assert!(games.iter().all(|game| !game.exists()));
+        assert_eq!(trash.restore(&database)?, games.to_vec());
+        assert!(games.iter().all(|game| game.is_file()));
+        assert!(trash.restore(&database)?.is_empty());
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_suffix() {
+        let stem = "Super Mario Bros";
+        assert_eq!(save_suffix("Super Mario Bros.srm", stem), Some("srm"));
+        assert_eq!(save_suffix("Super Mario Bros.state", stem), Some("state"));
+        assert_eq!(
+            save_suffix("Super Mario Bros.state12", stem),
+            Some("state12")
+        );
+        assert_eq!(
+            save_suffix("Super Mario Bros.state.auto.png", stem),
+            Some("state.auto.png")
+        );
+        assert_eq!(save_suffix("Super Mario Bros. 3.srm", stem), None);
+        assert_eq!(save_suffix("Super Mario Bros.state1.bak", stem), None);
+        assert_eq!(save_suffix("Super Mario Bros.nes", stem), None);
+    }
+}
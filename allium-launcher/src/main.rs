@@ -3,22 +3,36 @@
 #![feature(trait_upcasting)]
 
 mod allium_launcher;
+mod arcade;
+mod archive;
 mod consoles;
 mod entry;
+mod extract_cache;
+mod files;
+mod patch;
+mod rdb;
+mod start;
 mod view;
 
 use anyhow::Result;
+use log::warn;
 
 use allium_launcher::AlliumLauncher;
 use common::platform::{DefaultPlatform, Platform};
 use simple_logger::SimpleLogger;
+use start::StartOptions;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     SimpleLogger::new().init().unwrap();
 
+    // A bad option shouldn't stop the launcher from starting
+    let options = StartOptions::from_env().unwrap_or_else(|e| {
+        warn!("ignoring start options: {}", e);
+        StartOptions::default()
+    });
     let platform = DefaultPlatform::new()?;
-    let mut app = AlliumLauncher::new(platform)?;
+    let mut app = AlliumLauncher::new(platform, options)?;
     app.run_event_loop().await?;
     Ok(())
 }
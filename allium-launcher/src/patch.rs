@@ -0,0 +1,477 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+
+/// Soft patch formats. RetroArch applies them with the matching `--ips`, `--bps` or `--ups` flag.
+pub const PATCH_EXTENSIONS: [&str; 3] = ["ips", "bps", "ups"];
+
+/// Folder next to the games that patches can also be kept in. Patches in a subfolder named after
+/// a game apply to that game, e.g. "Patches/Mother 3 (Japan)/English.ips".
+pub const PATCHES_DIR: &str = "Patches";
+
+/// A game made by applying a patch to a ROM, e.g. a translation or a ROM hack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub patch: PathBuf,
+    pub rom: PathBuf,
+    /// What the patch is called, e.g. "T-En" for "Mother 3 (Japan) [T-En].ips".
+    pub label: String,
+}
+
+/// Returns true if the path is a soft patch.
+pub fn is_patch(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|ext| PATCH_EXTENSIONS.iter().any(|p| ext.eq_ignore_ascii_case(p)))
+}
+
+/// Lists the patches in a directory and its Patches folder.
+pub fn patches_in(dir: &Path) -> Vec<PathBuf> {
+    let list = |dir: &Path| -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .map(|entries| entries.flatten().map(|e| e.path()).collect())
+            .unwrap_or_default()
+    };
+
+    let mut patches = Vec::new();
+    for path in list(dir) {
+        if is_patch(&path) {
+            patches.push(path);
+        }
+    }
+    for path in list(&dir.join(PATCHES_DIR)) {
+        if path.is_dir() {
+            patches.extend(list(&path).into_iter().filter(|p| is_patch(p)));
+        } else if is_patch(&path) {
+            patches.push(path);
+        }
+    }
+    patches.sort();
+    patches
+}
+
+/// Matches patches to the ROMs they apply to. A patch in a subfolder of the Patches folder
+/// applies to the ROM named after the subfolder. Any other patch applies to the ROM whose name
+/// is the longest prefix of its own, e.g. "Mother 3 (Japan) [T-En].ips" patches
+/// "Mother 3 (Japan).gba". Patches named exactly like a ROM are skipped, as RetroArch already
+/// applies those to the ROM itself.
+pub fn find_variants(roms: &[PathBuf], patches: &[PathBuf]) -> Vec<Variant> {
+    let stem = |path: &Path| {
+        path.file_stem()
+            .and_then(OsStr::to_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    patches
+        .iter()
+        .filter_map(|patch| {
+            let patch_stem = stem(patch);
+            let folder = patch.parent()?;
+            let in_game_folder = folder
+                .parent()
+                .and_then(Path::file_name)
+                .is_some_and(|name| name == PATCHES_DIR);
+
+            let (rom, label) = if in_game_folder {
+                let folder = folder.file_name()?;
+                let rom = roms.iter().find(|rom| rom.file_stem() == Some(folder))?;
+                (rom, patch_stem)
+            } else {
+                let rom = roms
+                    .iter()
+                    .filter(|rom| {
+                        let rom_stem = stem(rom);
+                        patch_stem.len() > rom_stem.len()
+                            && patch_stem.starts_with(&rom_stem)
+                            && !patch_stem[rom_stem.len()..].starts_with(char::is_alphanumeric)
+                    })
+                    .max_by_key(|rom| stem(rom).len())?;
+                let label = patch_stem[stem(rom).len()..]
+                    .trim_matches(|c: char| c.is_whitespace() || "()[]-_".contains(c))
+                    .to_string();
+                (rom, label)
+            };
+            if label.is_empty() {
+                return None;
+            }
+
+            Some(Variant {
+                patch: patch.clone(),
+                rom: rom.clone(),
+                label,
+            })
+        })
+        .collect()
+}
+
+/// Directory of the ROMs that a patch may apply to: the directory of the patch, or the directory
+/// containing its Patches folder.
+pub fn rom_dir(patch: &Path) -> Option<&Path> {
+    let is_patches_dir = |dir: &Path| dir.file_name().is_some_and(|name| name == PATCHES_DIR);
+    let mut dir = patch.parent()?;
+    if !is_patches_dir(dir) && dir.parent().is_some_and(is_patches_dir) {
+        dir = dir.parent()?;
+    }
+    if is_patches_dir(dir) {
+        dir = dir.parent()?;
+    }
+    Some(dir)
+}
+
+/// Finds the ROM that a patch applies to, looking in the directory of the patch, or the
+/// directory containing its Patches folder.
+pub fn find_rom(patch: &Path) -> Option<PathBuf> {
+    let dir = rom_dir(patch)?;
+    let roms: Vec<_> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && !is_patch(p))
+        .collect();
+    find_variants(&roms, &[patch.to_path_buf()])
+        .into_iter()
+        .next()
+        .map(|v| v.rom)
+}
+
+/// How many times bigger than the ROM a patched ROM may be, so that a corrupt patch can't ask for
+/// more memory than the device has. Expansion hacks and translations rarely do more than double.
+const MAX_GROWTH: usize = 8;
+
+/// Size that patched ROMs may always grow to, however small the ROM.
+const MIN_MAX_SIZE: usize = 16 * 1024 * 1024;
+
+/// Largest ROM that patching `rom` may produce.
+fn max_size(rom: &[u8]) -> usize {
+    rom.len().saturating_mul(MAX_GROWTH).max(MIN_MAX_SIZE)
+}
+
+/// Applies a patch to the contents of a ROM.
+pub fn apply(rom: &[u8], patch_path: &Path) -> Result<Vec<u8>> {
+    let patch = fs::read(patch_path)?;
+    let extension = patch_path
+        .extension()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "ips" => apply_ips(rom, &patch),
+        "ups" => apply_ups(rom, &patch),
+        "bps" => apply_bps(rom, &patch),
+        _ => bail!("{} is not a patch", patch_path.display()),
+    }
+}
+
+/// Reads the parts of a patch.
+struct PatchReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PatchReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .pos
+            .checked_add(n)
+            .and_then(|end| self.data.get(self.pos..end))
+            .ok_or_else(|| anyhow!("unexpected end of patch"))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// Reads a big-endian number of `n` bytes.
+    fn be(&mut self, n: usize) -> Result<usize> {
+        Ok(self
+            .bytes(n)?
+            .iter()
+            .fold(0, |value, &b| value << 8 | b as usize))
+    }
+
+    /// Reads a variable-length number, as used by UPS and BPS.
+    fn number(&mut self) -> Result<usize> {
+        let overflow = || anyhow!("number in patch is too large");
+        let mut value: usize = 0;
+        let mut shift: usize = 1;
+        loop {
+            let byte = self.byte()?;
+            value = ((byte & 0x7f) as usize)
+                .checked_mul(shift)
+                .and_then(|n| value.checked_add(n))
+                .ok_or_else(overflow)?;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+            shift = shift.checked_mul(0x80).ok_or_else(overflow)?;
+            value = value.checked_add(shift).ok_or_else(overflow)?;
+        }
+    }
+}
+
+fn apply_ips(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>> {
+    let mut reader = PatchReader::new(patch);
+    if reader.bytes(5)? != b"PATCH" {
+        bail!("invalid IPS patch");
+    }
+
+    let mut output = rom.to_vec();
+    loop {
+        let offset = reader.bytes(3)?;
+        if offset == b"EOF" {
+            break;
+        }
+        let offset = offset.iter().fold(0, |value, &b| value << 8 | b as usize);
+        let size = reader.be(2)?;
+        let (size, data) = if size == 0 {
+            // Run-length encoded record
+            let size = reader.be(2)?;
+            (size, vec![reader.byte()?; size])
+        } else {
+            (size, reader.bytes(size)?.to_vec())
+        };
+        if output.len() < offset + size {
+            output.resize(offset + size, 0);
+        }
+        output[offset..offset + size].copy_from_slice(&data);
+    }
+
+    // Some patches truncate the ROM
+    if let Ok(size) = reader.be(3) {
+        output.truncate(size);
+    }
+
+    Ok(output)
+}
+
+/// UPS and BPS patches end with the checksums of the source, target and patch.
+const CHECKSUMS_SIZE: usize = 12;
+
+fn apply_ups(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>> {
+    let mut reader = PatchReader::new(patch);
+    if reader.bytes(4)? != b"UPS1" {
+        bail!("invalid UPS patch");
+    }
+    let end = patch.len().saturating_sub(CHECKSUMS_SIZE);
+
+    let source_size = reader.number()?;
+    let target_size = reader.number()?;
+    // Patches can be applied in either direction
+    let size = if rom.len() == target_size {
+        source_size
+    } else {
+        target_size
+    };
+    if size > max_size(rom) {
+        bail!("UPS patch is for a {} byte ROM", size);
+    }
+
+    let mut output = rom.to_vec();
+    output.resize(size, 0);
+    let mut pos: usize = 0;
+    while reader.pos < end {
+        pos = pos
+            .checked_add(reader.number()?)
+            .ok_or_else(|| anyhow!("corrupt UPS patch"))?;
+        loop {
+            let byte = reader.byte()?;
+            if let Some(out) = output.get_mut(pos) {
+                *out ^= byte;
+            }
+            pos = pos.saturating_add(1);
+            if byte == 0 {
+                break;
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+fn apply_bps(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>> {
+    let mut reader = PatchReader::new(patch);
+    if reader.bytes(4)? != b"BPS1" {
+        bail!("invalid BPS patch");
+    }
+    let end = patch.len().saturating_sub(CHECKSUMS_SIZE);
+
+    reader.number()?;
+    let target_size = reader.number()?;
+    if target_size > max_size(rom) {
+        bail!("BPS patch makes a {} byte ROM", target_size);
+    }
+    let metadata_size = reader.number()?;
+    reader.bytes(metadata_size)?;
+
+    let corrupt = || anyhow!("corrupt BPS patch");
+    let relative = |reader: &mut PatchReader<'_>, offset: usize| -> Result<usize> {
+        let data = reader.number()?;
+        let delta = data >> 1;
+        if data & 1 != 0 {
+            offset.checked_sub(delta).ok_or_else(corrupt)
+        } else {
+            offset.checked_add(delta).ok_or_else(corrupt)
+        }
+    };
+    let range = |start: usize, length: usize| Some(start..start.checked_add(length)?);
+
+    let mut output = Vec::with_capacity(target_size);
+    let mut source_offset = 0;
+    let mut target_offset = 0;
+    while reader.pos < end {
+        let data = reader.number()?;
+        let length = (data >> 2) + 1;
+        // Every action writes to the output, which can't grow past the target size
+        if output.len() + length > target_size {
+            return Err(corrupt());
+        }
+        match data & 3 {
+            // Source read
+            0 => {
+                let source = range(output.len(), length).and_then(|r| rom.get(r));
+                output.extend_from_slice(source.ok_or_else(corrupt)?);
+            }
+            // Target read
+            1 => output.extend_from_slice(reader.bytes(length)?),
+            // Source copy
+            2 => {
+                source_offset = relative(&mut reader, source_offset)?;
+                let source = range(source_offset, length).and_then(|r| rom.get(r));
+                output.extend_from_slice(source.ok_or_else(corrupt)?);
+                source_offset += length;
+            }
+            // Target copy, which can overlap with the bytes being written
+            _ => {
+                target_offset = relative(&mut reader, target_offset)?;
+                for _ in 0..length {
+                    let byte = *output.get(target_offset).ok_or_else(corrupt)?;
+                    output.push(byte);
+                    target_offset += 1;
+                }
+            }
+        }
+    }
+
+    if output.len() != target_size {
+        bail!(
+            "BPS patch produced {} bytes, expected {}",
+            output.len(),
+            target_size
+        );
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_variants() {
+        let dir = Path::new("Roms/GBA");
+        let roms = vec![
+            dir.join("Mother 3 (Japan).gba"),
+            dir.join("Mario.gba"),
+            dir.join("Mario Kart.gba"),
+        ];
+        let patches = vec![
+            dir.join("Mother 3 (Japan) [T-En].ips"),
+            dir.join("Mother 3 (Japan).ips"),
+            dir.join("Patches/Mario Kart (Hack).bps"),
+            dir.join("Patches/Mario/Randomizer.ups"),
+            dir.join("Marios.ips"),
+        ];
+
+        assert_eq!(
+            find_variants(&roms, &patches),
+            vec![
+                Variant {
+                    patch: dir.join("Mother 3 (Japan) [T-En].ips"),
+                    rom: dir.join("Mother 3 (Japan).gba"),
+                    label: "T-En".to_string(),
+                },
+                Variant {
+                    patch: dir.join("Patches/Mario Kart (Hack).bps"),
+                    rom: dir.join("Mario Kart.gba"),
+                    label: "Hack".to_string(),
+                },
+                Variant {
+                    patch: dir.join("Patches/Mario/Randomizer.ups"),
+                    rom: dir.join("Mario.gba"),
+                    label: "Randomizer".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_ips() {
+        let mut patch = b"PATCH".to_vec();
+        patch.extend([0, 0, 1, 0, 2, b'X', b'Y']);
+        patch.extend([0, 0, 6, 0, 0, 0, 3, b'Z']);
+        patch.extend(b"EOF");
+
+        assert_eq!(apply_ips(b"abcdef", &patch).unwrap(), b"aXYdefZZZ");
+    }
+
+    #[test]
+    fn test_apply_ups() {
+        let mut patch = b"UPS1".to_vec();
+        // Source and target are 3 bytes, XOR the second byte with 0x03
+        patch.extend([0x83, 0x83, 0x81, 0x03, 0x00]);
+        patch.extend([0; CHECKSUMS_SIZE]);
+
+        assert_eq!(apply_ups(b"abc", &patch).unwrap(), b"aac");
+    }
+
+    #[test]
+    fn test_apply_bps() {
+        let mut patch = b"BPS1".to_vec();
+        // Source is 3 bytes, target is 6 bytes, no metadata
+        patch.extend([0x83, 0x86, 0x80]);
+        // Source read 1 byte
+        patch.push(0x80);
+        // Target read 2 bytes
+        patch.extend([0x85, b'X', b'Y']);
+        // Target copy 3 bytes from offset 1
+        patch.extend([0x8b, 0x82]);
+        patch.extend([0; CHECKSUMS_SIZE]);
+
+        assert_eq!(apply_bps(b"abc", &patch).unwrap(), b"aXYXYX");
+    }
+
+    #[test]
+    fn test_corrupt_patches() {
+        // Sizes far beyond the ROM are refused rather than allocated
+        let mut patch = b"UPS1".to_vec();
+        patch.extend([0x83, 0x7f, 0x7f, 0x7f, 0x7f, 0x80, 0x00]);
+        patch.extend([0; CHECKSUMS_SIZE]);
+        assert!(apply_ups(b"abc", &patch).is_err());
+
+        let mut patch = b"BPS1".to_vec();
+        patch.extend([0x83, 0x7f, 0x7f, 0x7f, 0x7f, 0x80, 0x80]);
+        patch.extend([0; CHECKSUMS_SIZE]);
+        assert!(apply_bps(b"abc", &patch).is_err());
+
+        // Numbers that overflow, and actions that go past the target size
+        let mut patch = b"BPS1".to_vec();
+        patch.extend([0x83, 0x86, 0x80]);
+        patch.extend([0x7f; 16]);
+        patch.extend([0; CHECKSUMS_SIZE]);
+        assert!(apply_bps(b"abc", &patch).is_err());
+
+        let mut patch = b"BPS1".to_vec();
+        patch.extend([0x83, 0x81, 0x80, 0xa4]);
+        patch.extend([0; CHECKSUMS_SIZE]);
+        assert!(apply_bps(b"abc", &patch).is_err());
+    }
+}
@@ -0,0 +1,479 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use common::constants::{ALLIUM_RDB_DIR, RDB_MAX_CRC_SIZE};
+use common::database::{Database, GameMetadata};
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use regex::Regex;
+
+use crate::archive;
+use crate::consoles::ConsoleMapper;
+
+/// A game record from a libretro database (.rdb) file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RdbEntry {
+    pub description: Option<String>,
+    pub genre: Option<String>,
+    pub developer: Option<String>,
+    pub publisher: Option<String>,
+    pub release_year: Option<i32>,
+    pub users: Option<i32>,
+    pub rom_name: Option<String>,
+    pub crc: Option<u32>,
+    pub serial: Option<String>,
+}
+
+impl RdbEntry {
+    pub fn metadata(&self) -> GameMetadata {
+        GameMetadata {
+            genre: self.genre.clone(),
+            developer: self.developer.clone(),
+            publisher: self.publisher.clone(),
+            release_year: self.release_year,
+            players: self.users.map(|u| u.to_string()),
+            description: self.description.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// An in-memory libretro database, indexed by CRC, serial and ROM name.
+#[derive(Debug, Default)]
+pub struct Rdb {
+    entries: Vec<RdbEntry>,
+    by_crc: HashMap<u32, usize>,
+    by_serial: HashMap<String, usize>,
+    by_rom_name: HashMap<String, usize>,
+}
+
+const RDB_MAGIC: &[u8; 8] = b"RARCHDB\0";
+
+impl Rdb {
+    /// Loads a libretro database. The file begins with a magic number and metadata offset,
+    /// followed by one MessagePack map per game, terminated by nil.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut header = [0; 16];
+        reader.read_exact(&mut header)?;
+        if &header[..8] != RDB_MAGIC {
+            bail!("{} is not a libretro database", path.display());
+        }
+
+        let mut rdb = Rdb::default();
+        loop {
+            let value = match read_value(&mut reader) {
+                Ok(value) => value,
+                Err(e) if is_eof(&e) => break,
+                Err(e) => return Err(e),
+            };
+            let Value::Map(map) = value else {
+                break;
+            };
+            rdb.insert(parse_entry(map));
+        }
+
+        debug!(
+            "loaded {} entries from {}",
+            rdb.entries.len(),
+            path.display()
+        );
+        Ok(rdb)
+    }
+
+    fn insert(&mut self, entry: RdbEntry) {
+        let i = self.entries.len();
+        if let Some(crc) = entry.crc {
+            self.by_crc.entry(crc).or_insert(i);
+        }
+        if let Some(serial) = entry.serial.as_deref().map(normalize_serial) {
+            self.by_serial.entry(serial).or_insert(i);
+        }
+        if let Some(rom_name) = entry.rom_name.as_deref().map(normalize_rom_name) {
+            self.by_rom_name.entry(rom_name).or_insert(i);
+        }
+        self.entries.push(entry);
+    }
+
+    pub fn find_by_crc(&self, crc: u32) -> Option<&RdbEntry> {
+        self.by_crc.get(&crc).map(|&i| &self.entries[i])
+    }
+
+    pub fn find_by_serial(&self, serial: &str) -> Option<&RdbEntry> {
+        self.by_serial
+            .get(&normalize_serial(serial))
+            .map(|&i| &self.entries[i])
+    }
+
+    pub fn find_by_rom_name(&self, rom_name: &str) -> Option<&RdbEntry> {
+        self.by_rom_name
+            .get(&normalize_rom_name(rom_name))
+            .map(|&i| &self.entries[i])
+    }
+
+    /// Finds the entry matching the game at `path`, trying (in order) its CRC, a serial in its
+    /// file name, and its file name.
+    pub fn find(&self, database: &Database, path: &Path) -> Option<&RdbEntry> {
+        if let Some(entry) = game_crc(database, path).and_then(|crc| self.find_by_crc(crc)) {
+            return Some(entry);
+        }
+
+        let file_name = path.file_name().and_then(OsStr::to_str)?;
+        if let Some(entry) = serial_in_name(file_name).and_then(|s| self.find_by_serial(&s)) {
+            return Some(entry);
+        }
+
+        self.find_by_rom_name(file_name)
+    }
+}
+
+/// Looks up metadata in the libretro databases for every game that doesn't have any yet.
+pub fn populate_metadata(database: &Database, console_mapper: &ConsoleMapper) -> Result<()> {
+    if !ALLIUM_RDB_DIR.is_dir() {
+        debug!(
+            "no libretro databases found at {}",
+            ALLIUM_RDB_DIR.display()
+        );
+        return Ok(());
+    }
+
+    let mut games: HashMap<&str, Vec<PathBuf>> = HashMap::new();
+    for game in database.select_games_without_metadata()? {
+        if let Some(rdb) = console_mapper
+            .get_console(database, &game.path)
+            .and_then(|c| c.rdb.as_deref())
+        {
+            games.entry(rdb).or_default().push(game.path);
+        }
+    }
+
+    for (name, paths) in games {
+        let path = ALLIUM_RDB_DIR.join(format!("{}.rdb", name));
+        if !path.exists() {
+            continue;
+        }
+
+        let rdb = match Rdb::load(&path) {
+            Ok(rdb) => rdb,
+            Err(e) => {
+                warn!("failed to load libretro database {}: {}", path.display(), e);
+                continue;
+            }
+        };
+
+        let metadata: Vec<_> = paths
+            .into_iter()
+            .filter_map(|path| {
+                let metadata = rdb.find(database, &path)?.metadata();
+                Some((path, metadata))
+            })
+            .collect();
+        info!("found metadata for {} games in {}", metadata.len(), name);
+        database.update_game_metadata(&metadata)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the CRC32 of a game. For archives, this is the CRC of the largest file inside. CRCs
+/// are cached, so that games without a match aren't read again on every reindex.
+fn game_crc(database: &Database, path: &Path) -> Option<u32> {
+    if archive::is_archive(path) {
+        return archive::cached_entries(database, path)
+            .ok()?
+            .into_iter()
+            .max_by_key(|e| e.size)
+            .map(|e| e.crc32);
+    }
+
+    let metadata = path.metadata().ok()?;
+    if !metadata.is_file() || metadata.len() > RDB_MAX_CRC_SIZE {
+        return None;
+    }
+    let fingerprint = archive::fingerprint(path).ok()?;
+    if let Ok(Some(crc)) = database.select_crc(path, fingerprint) {
+        return Some(crc);
+    }
+    let crc = archive::crc32(BufReader::new(File::open(path).ok()?)).ok()?;
+    if let Err(e) = database.update_crc(path, fingerprint, crc) {
+        warn!("failed to cache CRC of {}: {}", path.display(), e);
+    }
+    Some(crc)
+}
+
+/// Finds a disc serial (e.g. "SLUS-00594") in a file name.
+fn serial_in_name(name: &str) -> Option<String> {
+    lazy_static! {
+        static ref SERIAL_RE: Regex = Regex::new(r"\b([A-Z]{4})[-_ ]?(\d{3})\.?(\d{2})\b").unwrap();
+    }
+    let captures = SERIAL_RE.captures(name)?;
+    Some(format!("{}-{}{}", &captures[1], &captures[2], &captures[3]))
+}
+
+fn normalize_serial(serial: &str) -> String {
+    serial
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn normalize_rom_name(rom_name: &str) -> String {
+    Path::new(rom_name)
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .unwrap_or(rom_name)
+        .to_lowercase()
+}
+
+fn parse_entry(map: Vec<(Value, Value)>) -> RdbEntry {
+    let mut entry = RdbEntry::default();
+    for (key, value) in map {
+        let Value::Str(key) = key else {
+            continue;
+        };
+        match key.as_str() {
+            "description" => entry.description = value.into_string(),
+            "genre" => entry.genre = value.into_string(),
+            "developer" => entry.developer = value.into_string(),
+            "publisher" => entry.publisher = value.into_string(),
+            "releaseyear" => entry.release_year = value.as_i64().map(|y| y as i32),
+            "users" => entry.users = value.as_i64().map(|u| u as i32),
+            "rom_name" => entry.rom_name = value.into_string(),
+            "serial" => entry.serial = value.into_string(),
+            "crc" => {
+                entry.crc = match value {
+                    Value::Bin(bytes) if bytes.len() == 4 => {
+                        Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                    }
+                    Value::Str(s) => u32::from_str_radix(&s, 16).ok(),
+                    _ => None,
+                }
+            }
+            _ => {}
+        }
+    }
+    entry
+}
+
+/// The subset of MessagePack values used by libretro databases.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Uint(u64),
+    Float(f64),
+    Str(String),
+    Bin(Vec<u8>),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+}
+
+impl Value {
+    fn into_string(self) -> Option<String> {
+        match self {
+            Value::Str(s) => Some(s),
+            Value::Bin(b) => Some(String::from_utf8_lossy(&b).into_owned()),
+            _ => None,
+        }
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::Int(i) => Some(i),
+            Value::Uint(u) => i64::try_from(u).ok(),
+            _ => None,
+        }
+    }
+}
+
+fn is_eof(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == ErrorKind::UnexpectedEof)
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut buf = [0; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Lengths are read from the file, so a corrupt one may be far larger than the file. The buffer
+/// only grows as data arrives, instead of being allocated up front.
+fn read_vec(reader: &mut impl Read, len: usize) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader.take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() < len {
+        return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+    }
+    Ok(buf)
+}
+
+fn read_value(reader: &mut impl Read) -> Result<Value> {
+    let [marker] = read_bytes::<1>(reader)?;
+    Ok(match marker {
+        0x00..=0x7f => Value::Uint(marker as u64),
+        0x80..=0x8f => read_map(reader, (marker & 0x0f) as usize)?,
+        0x90..=0x9f => read_array(reader, (marker & 0x0f) as usize)?,
+        0xa0..=0xbf => read_str(reader, (marker & 0x1f) as usize)?,
+        0xc0 => Value::Nil,
+        0xc2 => Value::Bool(false),
+        0xc3 => Value::Bool(true),
+        0xc4 => {
+            let len = u8::from_be_bytes(read_bytes(reader)?) as usize;
+            Value::Bin(read_vec(reader, len)?)
+        }
+        0xc5 => {
+            let len = u16::from_be_bytes(read_bytes(reader)?) as usize;
+            Value::Bin(read_vec(reader, len)?)
+        }
+        0xc6 => {
+            let len = u32::from_be_bytes(read_bytes(reader)?) as usize;
+            Value::Bin(read_vec(reader, len)?)
+        }
+        0xca => Value::Float(f32::from_be_bytes(read_bytes(reader)?) as f64),
+        0xcb => Value::Float(f64::from_be_bytes(read_bytes(reader)?)),
+        0xcc => Value::Uint(u8::from_be_bytes(read_bytes(reader)?) as u64),
+        0xcd => Value::Uint(u16::from_be_bytes(read_bytes(reader)?) as u64),
+        0xce => Value::Uint(u32::from_be_bytes(read_bytes(reader)?) as u64),
+        0xcf => Value::Uint(u64::from_be_bytes(read_bytes(reader)?)),
+        0xd0 => Value::Int(i8::from_be_bytes(read_bytes(reader)?) as i64),
+        0xd1 => Value::Int(i16::from_be_bytes(read_bytes(reader)?) as i64),
+        0xd2 => Value::Int(i32::from_be_bytes(read_bytes(reader)?) as i64),
+        0xd3 => Value::Int(i64::from_be_bytes(read_bytes(reader)?)),
+        0xd9 => {
+            let len = u8::from_be_bytes(read_bytes(reader)?) as usize;
+            read_str(reader, len)?
+        }
+        0xda => {
+            let len = u16::from_be_bytes(read_bytes(reader)?) as usize;
+            read_str(reader, len)?
+        }
+        0xdb => {
+            let len = u32::from_be_bytes(read_bytes(reader)?) as usize;
+            read_str(reader, len)?
+        }
+        0xdc => {
+            let len = u16::from_be_bytes(read_bytes(reader)?) as usize;
+            read_array(reader, len)?
+        }
+        0xdd => {
+            let len = u32::from_be_bytes(read_bytes(reader)?) as usize;
+            read_array(reader, len)?
+        }
+        0xde => {
+            let len = u16::from_be_bytes(read_bytes(reader)?) as usize;
+            read_map(reader, len)?
+        }
+        0xdf => {
+            let len = u32::from_be_bytes(read_bytes(reader)?) as usize;
+            read_map(reader, len)?
+        }
+        0xe0..=0xff => Value::Int(marker as i8 as i64),
+        _ => bail!("unsupported MessagePack marker: {:#x}", marker),
+    })
+}
+
+fn read_str(reader: &mut impl Read, len: usize) -> Result<Value> {
+    let bytes = read_vec(reader, len)?;
+    Ok(Value::Str(String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+/// Most values that are preallocated for in arrays and maps. Every value takes at least a byte,
+/// so larger lengths are only allocated for as values are read.
+const MAX_PREALLOCATED_VALUES: usize = 1024;
+
+fn read_array(reader: &mut impl Read, len: usize) -> Result<Value> {
+    let mut array = Vec::with_capacity(len.min(MAX_PREALLOCATED_VALUES));
+    for _ in 0..len {
+        array.push(read_value(reader)?);
+    }
+    Ok(Value::Array(array))
+}
+
+fn read_map(reader: &mut impl Read, len: usize) -> Result<Value> {
+    let mut map = Vec::with_capacity(len.min(MAX_PREALLOCATED_VALUES));
+    for _ in 0..len {
+        let key = read_value(reader)?;
+        let value = read_value(reader)?;
+        map.push((key, value));
+    }
+    Ok(Value::Map(map))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn str(s: &str) -> Vec<u8> {
+        let mut bytes = vec![0xa0 | s.len() as u8];
+        bytes.extend(s.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        let mut bytes = vec![0x86];
+        bytes.extend(str("name"));
+        bytes.extend(str("Game One (USA)"));
+        bytes.extend(str("genre"));
+        bytes.extend(str("Platform"));
+        bytes.extend(str("releaseyear"));
+        bytes.extend([0xcd, 0x07, 0xc7]);
+        bytes.extend(str("users"));
+        bytes.push(0x02);
+        bytes.extend(str("crc"));
+        bytes.extend([0xc4, 0x04, 0xcb, 0xf4, 0x39, 0x26]);
+        bytes.extend(str("serial"));
+        bytes.extend([0xc4, 0x0a]);
+        bytes.extend(b"SLUS-00594");
+
+        let Value::Map(map) = read_value(&mut &bytes[..]).unwrap() else {
+            panic!("expected map");
+        };
+        let entry = parse_entry(map);
+        assert_eq!(entry.genre.as_deref(), Some("Platform"));
+        assert_eq!(entry.release_year, Some(1991));
+        assert_eq!(entry.users, Some(2));
+        assert_eq!(entry.crc, Some(0xcbf43926));
+        assert_eq!(entry.serial.as_deref(), Some("SLUS-00594"));
+
+        let mut rdb = Rdb::default();
+        rdb.insert(entry);
+        assert!(rdb.find_by_crc(0xcbf43926).is_some());
+        assert!(rdb.find_by_serial("slus00594").is_some());
+    }
+
+    #[test]
+    fn test_read_truncated_value() {
+        for bytes in [
+            &[0xc6, 0xff, 0xff, 0xff, 0xff, 0x01][..],
+            &[0xdb, 0xff, 0xff, 0xff, 0xff, b'a'],
+            &[0xdd, 0xff, 0xff, 0xff, 0xff, 0x01],
+            &[0xdf, 0xff, 0xff, 0xff, 0xff, 0x01, 0x02],
+            &[0xa5, b'a', b'b'],
+        ] {
+            let e = read_value(&mut &bytes[..]).unwrap_err();
+            assert!(is_eof(&e), "{:x?}: {}", bytes, e);
+        }
+    }
+
+    #[test]
+    fn test_serial_in_name() {
+        assert_eq!(
+            serial_in_name("Game One (USA) [SLUS-00594].chd").as_deref(),
+            Some("SLUS-00594")
+        );
+        assert_eq!(
+            serial_in_name("SCES_012.37.Game Two.bin").as_deref(),
+            Some("SCES-01237")
+        );
+        assert_eq!(serial_in_name("Game Three (USA).chd"), None);
+    }
+}
@@ -0,0 +1,129 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use common::constants::ALLIUM_GAMES_DIR;
+
+/// Names of the tabs, in order.
+const TABS: [&str; 4] = ["recents", "games", "apps", "settings"];
+
+const USAGE: &str = "Usage: allium-launcher [--tab <recents|games|apps|settings>] [--dir <path>] [--game <path>] [--search <query>]";
+
+/// Where the launcher starts, instead of where it was left. Given as command line options, or as
+/// environment variables named `ALLIUM_LAUNCHER_` and the option in upper case, e.g.
+/// `ALLIUM_LAUNCHER_GAME`. Options on the command line win over environment variables.
+///
+/// This lets other programs and hook scripts return the user somewhere meaningful, e.g. to the
+/// folder of the game that just exited. Paths are relative to the games directory unless they're
+/// absolute.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StartOptions {
+    /// Index of the tab to show.
+    pub tab: Option<usize>,
+    /// Directory to open in the games tab.
+    pub directory: Option<PathBuf>,
+    /// Game to select in the games tab, in its directory.
+    pub game: Option<PathBuf>,
+    /// Search to run.
+    pub search: Option<String>,
+}
+
+impl StartOptions {
+    /// Reads the options of this process.
+    pub fn from_env() -> Result<Self> {
+        Self::parse(env::args().skip(1), |name| env::var(name).ok())
+    }
+
+    /// Parses options from command line arguments, falling back to environment variables read by
+    /// `var`.
+    pub fn parse(
+        args: impl IntoIterator<Item = String>,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self> {
+        let mut tab = var("ALLIUM_LAUNCHER_TAB");
+        let mut directory = var("ALLIUM_LAUNCHER_DIR");
+        let mut game = var("ALLIUM_LAUNCHER_GAME");
+        let mut search = var("ALLIUM_LAUNCHER_SEARCH");
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let option = match arg.as_str() {
+                "--tab" => &mut tab,
+                "--dir" => &mut directory,
+                "--game" => &mut game,
+                "--search" => &mut search,
+                _ => bail!("unknown option: {}\n{}", arg, USAGE),
+            };
+            match args.next() {
+                Some(value) => *option = Some(value),
+                None => bail!("missing value for {}\n{}", arg, USAGE),
+            }
+        }
+
+        let tab = match tab.filter(|t| !t.is_empty()) {
+            Some(tab) => match TABS.iter().position(|t| t.eq_ignore_ascii_case(&tab)) {
+                Some(i) => Some(i),
+                None => bail!("unknown tab: {}\n{}", tab, USAGE),
+            },
+            None => None,
+        };
+
+        Ok(Self {
+            tab,
+            directory: directory.filter(|d| !d.is_empty()).map(resolve),
+            game: game.filter(|g| !g.is_empty()).map(resolve),
+            search: search.filter(|s| !s.is_empty()),
+        })
+    }
+}
+
+/// Path of a game or directory, which is relative to the games directory unless it's absolute.
+fn resolve(path: String) -> PathBuf {
+    let path = Path::new(&path);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        ALLIUM_GAMES_DIR.join(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            StartOptions::parse(Vec::new(), |_| None).unwrap(),
+            StartOptions::default()
+        );
+
+        let options = StartOptions::parse(
+            args(&["--tab", "Apps", "--game", "/mnt/SDCARD/Roms/GB/Tetris.gb"]),
+            |_| None,
+        )
+        .unwrap();
+        assert_eq!(options.tab, Some(2));
+        assert_eq!(
+            options.game,
+            Some(PathBuf::from("/mnt/SDCARD/Roms/GB/Tetris.gb"))
+        );
+
+        let options = StartOptions::parse(args(&["--dir", "GBA"]), |name| match name {
+            "ALLIUM_LAUNCHER_DIR" => Some("GB".to_string()),
+            "ALLIUM_LAUNCHER_SEARCH" => Some("mario".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(options.directory, Some(ALLIUM_GAMES_DIR.join("GBA")));
+        assert_eq!(options.search.as_deref(), Some("mario"));
+
+        assert!(StartOptions::parse(args(&["--tab", "games", "--dir"]), |_| None).is_err());
+        assert!(StartOptions::parse(args(&["--tab", "nope"]), |_| None).is_err());
+        assert!(StartOptions::parse(args(&["--verbose"]), |_| None).is_err());
+    }
+}
@@ -17,6 +17,7 @@ use log::{trace, warn};
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::Sender;
 
+use crate::start::StartOptions;
 use crate::view::apps::AppsState;
 use crate::view::games::GamesState;
 use crate::view::recents::RecentsState;
@@ -100,15 +101,7 @@ where
     }
 
     pub fn load_or_new(rect: Rect, res: Resources, battery: B) -> Result<Self> {
-        let tab_rect = {
-            let styles = res.get::<Stylesheet>();
-            Rect::new(
-                rect.x,
-                rect.y + styles.ui_font.size as i32 + 8,
-                rect.w,
-                rect.h - styles.ui_font.size - 8,
-            )
-        };
+        let tab_rect = tab_rect(rect, &res.get::<Stylesheet>());
 
         if ALLIUM_LAUNCHER_STATE.exists() {
             let file = File::open(ALLIUM_LAUNCHER_STATE.as_path())?;
@@ -213,6 +206,35 @@ where
         self.views.0.search(query)?;
         Ok(())
     }
+
+    /// Goes to where the launcher was asked to start, instead of where it was left.
+    pub fn start_at(&mut self, options: &StartOptions) -> Result<()> {
+        // A game is selected in its directory, while a directory is opened
+        if let Some(path) = options.game.as_ref().or(options.directory.as_ref()) {
+            if self.views.1.reveal(path, options.game.is_none())? {
+                self.tab_change(1);
+            } else {
+                warn!("{} isn't in the games tab", path.display());
+            }
+        }
+        if let Some(tab) = options.tab {
+            self.tab_change(tab);
+        }
+        if let Some(query) = options.search.clone() {
+            self.search(query)?;
+        }
+        Ok(())
+    }
+}
+
+/// Area below the tab bar that each tab is drawn in.
+pub fn tab_rect(rect: Rect, styles: &Stylesheet) -> Rect {
+    Rect::new(
+        rect.x,
+        rect.y + styles.ui_font.size as i32 + 8,
+        rect.w,
+        rect.h - styles.ui_font.size - 8,
+    )
 }
 
 #[async_trait(?Send)]
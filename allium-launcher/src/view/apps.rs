@@ -7,6 +7,7 @@ use common::command::Command;
 use common::constants::ALLIUM_APPS_DIR;
 use common::database::Database;
 use common::geom::{Point, Rect};
+use common::launcher::LauncherSettings;
 use common::locale::Locale;
 use common::platform::{DefaultPlatform, KeyEvent, Platform};
 use common::resources::Resources;
@@ -35,10 +36,7 @@ impl Apps {
 
     pub fn load_or_new(rect: Rect, res: Resources, state: Option<AppsState>) -> Result<Self> {
         let list = if let Some(state) = state {
-            let selected = state.selected;
-            let mut list = EntryList::load(rect, res.clone(), state)?;
-            list.select(selected);
-            list
+            EntryList::load(rect, res.clone(), state)?
         } else {
             EntryList::new(
                 rect,
@@ -142,9 +140,10 @@ impl Sort for AppsSort {
         database: &Database,
         console_mapper: &ConsoleMapper,
         locale: &Locale,
+        settings: &LauncherSettings,
     ) -> Result<Vec<Entry>> {
         let mut entries = self.directory().entries(database, console_mapper, locale)?;
-        entries.sort_unstable();
+        entries.sort_by(|a, b| a.cmp_by_name(b, settings.ignore_articles));
         Ok(entries)
     }
 }
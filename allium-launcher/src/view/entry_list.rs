@@ -1,31 +1,67 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use async_trait::async_trait;
-use common::command::Command;
-use common::constants::{IMAGE_WIDTH, SELECTION_MARGIN};
+use chrono::Duration;
+use common::command::{Command, Value};
+use common::constants::{
+    ALLIUM_GAMES_DIR, GRID_COLUMNS, IMAGE_PREFETCH_COUNT, IMAGE_WIDTH, JUMP_INDICATOR_DURATION,
+    LISTING_JUMP_SIZE, SELECTION_MARGIN,
+};
 use common::database::Database;
 use common::display::Display;
 use common::geom::{Alignment, Point, Rect};
+use common::launcher::{LauncherSettings, Layout};
 use common::locale::Locale;
 use common::platform::{DefaultPlatform, Key, KeyEvent, Platform};
 use common::resources::Resources;
-use common::stylesheet::{Stylesheet, StylesheetColor};
-use common::view::{ButtonHint, ButtonIcon, Image, ImageMode, Row, ScrollList, View};
+use common::stylesheet::{ArtType, Stylesheet, StylesheetColor};
+use common::view::{
+    ButtonHint, ButtonIcon, Grid, Image, ImageMode, Keyboard, Label, Row, ScrollList, View,
+};
 use embedded_graphics::prelude::{Dimensions, OriginDimensions, Size};
 use embedded_graphics::primitives::{CornerRadii, Primitive, PrimitiveStyle, RoundedRectangle};
 use embedded_graphics::Drawable;
+use log::{error, warn};
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::Sender;
 
 use crate::consoles::ConsoleMapper;
+use crate::entry::directory::Directory;
+use crate::entry::game::Game;
+use crate::entry::gamelist::export_gamelists;
+use crate::entry::group::{self, Group};
+use crate::entry::icons::Icons;
+use crate::entry::lazy_image::has_art_dir;
+use crate::entry::variant;
 use crate::entry::{Entry, Sort};
+use crate::files::{self, Trash};
+use crate::view::game_details::GameDetails;
+
+/// Put before the names of marked entries in selection mode.
+const MARKED_PREFIX: &str = "• ";
+
+/// How many entries on each side of the selected one are saved, to select instead if it's gone.
+const SAVED_NEIGHBOURS: usize = 2;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EntryListState<S> {
     pub sort: S,
+    /// Index of the selected entry, used if neither it nor its neighbours can be found by path.
     pub selected: usize,
+    /// Path of the selected entry, which is selected again when restoring even if entries were
+    /// added or removed since.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// Paths of the entries around the selected one, nearest first, to select instead if it's gone.
+    #[serde(default)]
+    pub neighbours: Vec<PathBuf>,
+    /// State of the directory opened from the selected entry.
     pub child: Option<Box<EntryListState<S>>>,
+    /// Sorts chosen for directories. Only kept in the outermost state.
+    #[serde(default = "HashMap::new")]
+    pub sorts: HashMap<PathBuf, S>,
 }
 
 #[derive(Debug)]
@@ -34,6 +70,29 @@ pub struct CoreSelection {
     cores: Vec<String>,
 }
 
+/// Consoles that a flattened listing can be limited to. The first choice is all consoles.
+#[derive(Debug)]
+pub struct ConsoleSelection {
+    console: usize,
+    consoles: Vec<Option<String>>,
+}
+
+/// Variants of the selected game that can be launched instead, with a label for each.
+#[derive(Debug)]
+pub struct VariantSelection {
+    variant: usize,
+    variants: Vec<Game>,
+    labels: Vec<String>,
+}
+
+/// Folders that the selected game can be moved to, with their path relative to the games
+/// directory. The folder it's in is chosen first.
+#[derive(Debug)]
+pub struct MoveSelection {
+    target: usize,
+    targets: Vec<(PathBuf, String)>,
+}
+
 #[derive(Debug)]
 pub struct EntryList<S>
 where
@@ -43,11 +102,41 @@ where
     res: Resources,
     entries: Vec<Entry>,
     sort: S,
+    layout: Layout,
     list: ScrollList,
+    /// Shown instead of the list in the grid layout. Its selection is kept in sync with the list.
+    grid: Grid,
     image: Image,
+    /// Entry whose image was last shown, to tell which way the list is scrolling.
+    image_selected: usize,
+    /// Art found for entries, for art types other than box art.
+    art: HashMap<(PathBuf, ArtType), Option<PathBuf>>,
+    /// Whether the list shows art and there are title or snapshot art folders for its entries,
+    /// so that Start switches the art type.
+    cycle_art: bool,
+    /// Sorts chosen for directories, used again when they are opened. Shared with the child list
+    /// while it's open.
+    sorts: HashMap<PathBuf, S>,
+    /// Groups of the entries for jumping with L/R, found on the first jump.
+    groups: Option<Vec<Group>>,
+    /// Header showing the selected entry's group in flattened listings, or its summary in the
+    /// console view.
+    section: Option<Label<String>>,
+    summaries: Option<Vec<String>>,
     menu: Option<ScrollList>,
+    menu_entries: Vec<MenuEntry>,
     core: Option<CoreSelection>,
+    console: Option<ConsoleSelection>,
+    variant: Option<VariantSelection>,
+    target: Option<MoveSelection>,
+    /// Menu entry waiting for A to be pressed again, before moving or deleting several games.
+    confirm: Option<usize>,
+    /// Shown to type a new name for the selected game.
+    keyboard: Option<Keyboard>,
+    /// Paths of the entries marked in selection mode, or None outside of it.
+    marked: Option<HashSet<PathBuf>>,
     button_hints: Row<ButtonHint<String>>,
+    details: Option<GameDetails>,
     pub child: Option<Box<EntryList<S>>>,
 }
 
@@ -60,27 +149,47 @@ where
 
         let styles = res.get::<Stylesheet>();
 
+        // Flattened listings and the console view have a header above the list
+        let section = {
+            let settings = res.get::<LauncherSettings>();
+            settings.is_flattened(sort.path()) || sort.is_console_view(&settings)
+        }
+        .then(|| {
+            let mut label = Label::new(
+                Point::new(x + 12, y + 8),
+                String::new(),
+                Alignment::Left,
+                Some(w - IMAGE_WIDTH - 12 - 12 - 24),
+            );
+            label.color(StylesheetColor::Highlight);
+            label
+        });
+        let header = if section.is_some() {
+            styles.ui_font.size + SELECTION_MARGIN
+        } else {
+            0
+        };
+
         let list = ScrollList::new(
             Rect::new(
                 x + 12,
-                y + 8,
+                y + 8 + header as i32,
                 w - IMAGE_WIDTH - 12 - 12 - 24,
-                h - 8 - ButtonIcon::diameter(&styles) - 8,
+                h - 8 - header - ButtonIcon::diameter(&styles) - 8,
             ),
             Vec::new(),
             Alignment::Left,
             res.get::<Stylesheet>().ui_font.size + SELECTION_MARGIN,
         );
 
-        let mut image = Image::empty(
-            Rect::new(
-                x + w as i32 - IMAGE_WIDTH as i32 - 24,
-                y + 8,
-                IMAGE_WIDTH,
-                h - 8 - ButtonIcon::diameter(&styles) - 8,
-            ),
-            ImageMode::Contain,
+        let grid = Grid::new(
+            grid_rect(rect, &styles),
+            GRID_COLUMNS,
+            styles.ui_font.size + SELECTION_MARGIN,
         );
+        let layout = res.get::<LauncherSettings>().layout(sort.path());
+
+        let mut image = Image::empty(image_rect(rect, &styles), ImageMode::Contain);
         image.set_border_radius(12);
 
         let mut button_hints = Row::new(
@@ -88,7 +197,7 @@ where
                 x + w as i32 - 12,
                 y + h as i32 - ButtonIcon::diameter(&styles) as i32 - 8,
             ),
-            Vec::with_capacity(2),
+            Vec::with_capacity(3),
             Alignment::Right,
             12,
         );
@@ -118,11 +227,28 @@ where
             res,
             entries: vec![],
             sort,
+            layout,
             list,
+            grid,
             image,
+            image_selected: 0,
+            art: HashMap::new(),
+            cycle_art: false,
+            sorts: HashMap::new(),
+            groups: None,
+            section,
+            summaries: None,
             menu: None,
+            menu_entries: Vec::new(),
             core: None,
+            console: None,
+            variant: None,
+            target: None,
+            confirm: None,
+            keyboard: None,
+            marked: None,
             button_hints,
+            details: None,
             child: None,
         };
 
@@ -132,42 +258,113 @@ where
     }
 
     pub fn save(&self) -> EntryListState<S> {
+        let selected = self.list.selected();
         EntryListState {
             sort: self.sort.clone(),
-            selected: self.list.selected(),
-            child: self.child.as_ref().map(|c| Box::new(c.save())),
+            selected,
+            path: self.entries.get(selected).map(|e| e.path().to_path_buf()),
+            neighbours: neighbours(&self.entries, selected),
+            child: self.child.as_ref().map(|c| {
+                Box::new(EntryListState {
+                    sorts: HashMap::new(),
+                    ..c.save()
+                })
+            }),
+            sorts: self.latest_sorts().clone(),
         }
     }
 
     pub fn load(rect: Rect, res: Resources, state: EntryListState<S>) -> Result<Self> {
-        let mut this = Self::new(rect, res.clone(), state.sort)?;
-        this.select(state.selected);
-        if let Some(child) = state.child {
-            this.child = Some(Box::new(Self::load(rect, res, *child)?));
+        let mut this = Self::new(rect, res.clone(), state.sort.clone())?;
+        this.select(find_selected(&this.entries, &state));
+        this.sorts = state.sorts;
+        // Directories that are gone are left closed, with their nearest neighbour selected
+        if let Some(child) = state
+            .child
+            .filter(|child| child.sort.path().is_none_or(Path::is_dir))
+        {
+            match Self::load(rect, res, *child) {
+                Ok(mut child) => {
+                    child.sorts = this.sorts.clone();
+                    this.child = Some(Box::new(child));
+                }
+                Err(e) => warn!("failed to restore directory: {}", e),
+            }
         }
         Ok(this)
     }
 
+    /// Sorts chosen for directories, including by the open child lists.
+    fn latest_sorts(&self) -> &HashMap<PathBuf, S> {
+        self.child
+            .as_ref()
+            .map_or(&self.sorts, |child| child.latest_sorts())
+    }
+
     pub fn select(&mut self, index: usize) {
         self.list.select(index);
+        self.grid.select(index);
+    }
+
+    /// Opens a directory as a child list, with the sort last chosen for it.
+    fn open_directory(&mut self, dir: Directory) -> Result<()> {
+        let sort = match self.sorts.get(&dir.path) {
+            Some(sort) => sort.with_directory(dir),
+            None => self.sort.with_directory(dir),
+        };
+        let mut child = EntryList::new(self.rect, self.res.clone(), sort)?;
+        child.sorts = self.sorts.clone();
+        self.child = Some(Box::new(child));
+        Ok(())
+    }
+
+    /// Selects the entry at `path`, opening the directories on the way to it. If it's a directory
+    /// and `open` is set, it's opened too. Returns whether it was found.
+    pub fn reveal(&mut self, path: &Path, open: bool) -> Result<bool> {
+        if let Some(child) = self.child.take() {
+            self.sorts = child.sorts;
+        }
+
+        // Games may be in a directory further down
+        let (index, exact) = match position(&self.entries, path) {
+            Some(index) => (index, true),
+            None => match self
+                .entries
+                .iter()
+                .position(|e| matches!(e, Entry::Directory(_)) && path.starts_with(e.path()))
+            {
+                Some(index) => (index, false),
+                None => return Ok(false),
+            },
+        };
+        self.select(index);
+        if exact && !open {
+            return Ok(true);
+        }
+
+        let Some(Entry::Directory(dir)) = self.entries.get(index) else {
+            return Ok(exact);
+        };
+        self.open_directory(dir.clone())?;
+        match self.child.as_mut() {
+            Some(child) if !exact => child.reveal(path, open),
+            _ => Ok(true),
+        }
     }
 
     async fn select_entry(&mut self, commands: Sender<Command>) -> Result<()> {
         if let Some(entry) = self.entries.get_mut(self.list.selected()) {
             match entry {
                 Entry::Directory(dir) => {
-                    let child = EntryList::new(
-                        self.rect,
-                        self.res.clone(),
-                        self.sort.with_directory(dir.clone()),
-                    )?;
-                    self.child = Some(Box::new(child));
+                    let dir = dir.clone();
+                    self.open_directory(dir)?;
                 }
                 Entry::Game(game) => {
                     let command = self
                         .res
                         .get::<ConsoleMapper>()
-                        .launch_game(&self.res.get(), game)?;
+                        .launch_game(&self.res.get(), game)?
+                        .map(|launch| launch.start(commands.clone(), &self.res.get()));
                     if let Some(cmd) = command {
                         commands.send(cmd).await?;
                     }
@@ -182,10 +379,13 @@ where
 
     pub fn sort(&mut self, sort: S) -> Result<()> {
         self.sort = sort;
+        if let Some(path) = self.sort.path() {
+            self.sorts.insert(path.to_path_buf(), self.sort.clone());
+        }
         self.load_entries()?;
         if S::HAS_BUTTON_HINTS {
             self.button_hints
-                .get_mut(1)
+                .get_mut(1 + self.cycle_art as usize)
                 .unwrap()
                 .set_text(self.sort.button_hint(&self.res.get::<Locale>()));
         }
@@ -193,34 +393,239 @@ where
     }
 
     fn load_entries(&mut self) -> Result<()> {
-        self.entries = self
-            .sort
-            .entries(&self.res.get(), &self.res.get(), &self.res.get())?;
-        self.list.set_items(
-            self.entries.iter().map(|e| e.name().to_string()).collect(),
-            false,
+        self.entries = self.sort.entries(
+            &self.res.get(),
+            &self.res.get(),
+            &self.res.get(),
+            &self.res.get(),
+        )?;
+        if !self.res.get::<LauncherSettings>().show_hidden {
+            let hidden = self.res.get::<Database>().select_hidden()?;
+            // Games in hidden folders are hidden too when listing the games of all subfolders
+            self.entries
+                .retain(|e| !hidden.iter().any(|h| e.path().starts_with(h)));
+        }
+        {
+            let mut icons = Icons::new(&self.res.get());
+            let console_mapper = self.res.get::<ConsoleMapper>();
+            for entry in self.entries.iter_mut() {
+                if let Entry::Directory(dir) = entry {
+                    dir.icon = icons.directory(&console_mapper, &dir.path);
+                }
+            }
+        }
+        self.list.set_icons(
+            self.entries
+                .iter()
+                .map(|e| e.icon().map(Path::to_path_buf))
+                .collect(),
         );
+        self.groups = None;
+        if self.section.is_some() {
+            self.find_groups()?;
+            self.summaries = self.sort.summaries(
+                &self.entries,
+                &self.res.get(),
+                &self.res.get(),
+                &self.res.get(),
+            )?;
+        }
+        let names = self.names();
+        self.grid.set_items(names.clone(), false);
+        self.list.set_items(names, false);
+        self.update_art_hint();
+        if self.marked.is_some() {
+            self.update_marked();
+        }
         Ok(())
     }
 
+    /// Shows the art button hint only if Start can switch to other art for the entries.
+    fn update_art_hint(&mut self) {
+        let cycle_art =
+            self.layout == Layout::List && self.res.get::<Stylesheet>().enable_box_art && {
+                let dirs: HashSet<&Path> = self
+                    .entries
+                    .iter()
+                    .filter_map(|e| e.path().parent())
+                    .collect();
+                dirs.iter()
+                    .any(|dir| has_art_dir(dir, ArtType::Title) || has_art_dir(dir, ArtType::Snap))
+            };
+        if cycle_art == self.cycle_art {
+            return;
+        }
+        self.cycle_art = cycle_art;
+        if cycle_art {
+            let text = self.res.get::<Locale>().t("button-art");
+            self.button_hints.insert(
+                1,
+                ButtonHint::new(Point::zero(), Key::Start, text, Alignment::Right),
+            );
+        } else {
+            self.button_hints.remove(1);
+        }
+    }
+
+    /// Names shown for the entries, with marked entries prefixed in selection mode.
+    fn names(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .map(|e| match &self.marked {
+                Some(marked) if marked.contains(e.path()) => {
+                    format!("{}{}", MARKED_PREFIX, e.name())
+                }
+                _ => e.name().to_string(),
+            })
+            .collect()
+    }
+
+    /// Enters or leaves selection mode, where A marks entries and the menu acts on all of the
+    /// marked entries at once.
+    fn set_selection_mode(&mut self, enabled: bool) {
+        if enabled == self.marked.is_some() {
+            return;
+        }
+        let text = self.res.get::<Locale>().t(if enabled {
+            "button-mark"
+        } else {
+            "button-select"
+        });
+        self.button_hints.get_mut(0).unwrap().set_text(text);
+        if enabled {
+            self.marked = Some(HashSet::new());
+        } else {
+            self.marked = None;
+            self.button_hints.pop();
+        }
+        self.update_marked();
+    }
+
+    /// Marks the selected entry, or unmarks it if it's already marked.
+    fn toggle_marked(&mut self) {
+        if let (Some(marked), Some(entry)) =
+            (self.marked.as_mut(), self.entries.get(self.list.selected()))
+        {
+            let path = entry.path().to_path_buf();
+            if !marked.remove(&path) {
+                marked.insert(path);
+            }
+        }
+        self.update_marked();
+    }
+
+    /// Shows which entries are marked, and how many are in the button hints.
+    fn update_marked(&mut self) {
+        let selected = self.list.selected();
+        let names = self.names();
+        self.grid.set_items(names.clone(), true);
+        self.list.set_items(names, true);
+        self.select(selected);
+
+        if self.marked.is_some() {
+            let text = self.res.get::<Locale>().ta(
+                "selection-count",
+                &[("count".to_string(), self.targets().len().into())]
+                    .into_iter()
+                    .collect(),
+            );
+            if self.button_hints.len() > 1 + self.cycle_art as usize + S::HAS_BUTTON_HINTS as usize
+            {
+                self.button_hints.pop();
+            }
+            // Replaced rather than changed, so that the row is laid out again
+            self.button_hints.push(ButtonHint::new(
+                Point::zero(),
+                Key::Select,
+                text,
+                Alignment::Right,
+            ));
+        }
+    }
+
+    /// Entries that menu actions apply to: the marked entries in selection mode, otherwise the
+    /// selected entry.
+    fn targets(&self) -> Vec<&Entry> {
+        match &self.marked {
+            Some(marked) => self
+                .entries
+                .iter()
+                .filter(|e| marked.contains(e.path()))
+                .collect(),
+            None => self.entries.get(self.list.selected()).into_iter().collect(),
+        }
+    }
+
+    /// Games among the entries that menu actions apply to.
+    fn target_games(&self) -> Vec<Game> {
+        self.targets()
+            .into_iter()
+            .filter_map(|e| match e {
+                Entry::Game(game) => Some(game.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
     fn open_menu(&mut self) -> Result<()> {
-        let Rect { x, y, w, h } = self.rect;
-        let styles = self.res.get::<Stylesheet>();
+        if self.marked.is_some() {
+            return self.open_selection_menu();
+        }
+        // Folders can be empty, e.g. once everything in them is hidden
+        if self.list.selected() >= self.entries.len() {
+            return Ok(());
+        }
         let locale = self.res.get::<Locale>();
 
         let mut entries = vec![
             MenuEntry::Launch(None),
+            MenuEntry::Hide(false),
             MenuEntry::RemoveFromRecents,
             MenuEntry::RepopulateDatabase,
+            MenuEntry::ExportGamelists,
+            MenuEntry::Layout(self.layout.toggle()),
         ];
+        if let Some(path) = self.sort.path() {
+            let flattened = self.res.get::<LauncherSettings>().is_flattened(Some(path));
+            entries.push(MenuEntry::Flatten(!flattened));
+            if flattened {
+                let mut consoles = vec![None];
+                consoles.extend(
+                    Directory::new(path.to_path_buf())
+                        .consoles(&self.res.get(), &self.res.get())
+                        .into_iter()
+                        .map(Some),
+                );
+                let console = self.sort.console().map(str::to_string);
+                let i = consoles
+                    .iter()
+                    .position(|c| *c == console)
+                    .unwrap_or_default();
+                entries.push(MenuEntry::Console(console));
+                self.console = Some(ConsoleSelection {
+                    console: i,
+                    consoles,
+                });
+            }
+        }
 
-        let entry = self.entries.get(self.list.selected()).unwrap();
+        let entry = &self.entries[self.list.selected()];
+        if self
+            .res
+            .get::<Database>()
+            .select_hidden()?
+            .contains(entry.path())
+        {
+            entries[1] = MenuEntry::Hide(true);
+        }
         match entry {
             Entry::Game(game) => {
+                entries.insert(1, MenuEntry::Details);
+
                 let cores = self
                     .res
                     .get::<ConsoleMapper>()
-                    .get_console(&game.path)
+                    .get_console(&self.res.get(), &game.path)
                     .map(|c| c.cores.clone())
                     .unwrap_or_default();
 
@@ -237,11 +642,161 @@ where
                 } else {
                     self.core = None;
                 }
+
+                if game.variants.len() > 1 {
+                    let paths: Vec<&Path> =
+                        game.variants.iter().map(|g| g.path.as_path()).collect();
+                    let played = self.res.get::<Database>().select_games(&paths)?;
+                    let labels: Vec<String> = game
+                        .variants
+                        .iter()
+                        .zip(played)
+                        .map(|(variant, played)| {
+                            let mut label = variant::label(&variant.full_name);
+                            if label.is_empty() {
+                                label.clone_from(&variant.full_name);
+                            }
+                            match played.filter(|g| g.play_time > Duration::zero()) {
+                                Some(played) => locale.ta(
+                                    "menu-variant-played",
+                                    &[
+                                        ("variant".to_string(), label.into()),
+                                        ("hours".to_string(), played.play_time.num_hours().into()),
+                                        (
+                                            "minutes".to_string(),
+                                            (played.play_time.num_minutes() % 60).into(),
+                                        ),
+                                    ]
+                                    .into_iter()
+                                    .collect(),
+                                ),
+                                None => locale.ta(
+                                    "menu-variant",
+                                    &[("variant".to_string(), label.into())]
+                                        .into_iter()
+                                        .collect(),
+                                ),
+                            }
+                        })
+                        .collect();
+                    let i = game
+                        .variants
+                        .iter()
+                        .position(|v| v.path == game.path)
+                        .unwrap_or_default();
+                    entries.push(MenuEntry::Variant(labels[i].clone()));
+                    self.variant = Some(VariantSelection {
+                        variant: i,
+                        variants: game.variants.clone(),
+                        labels,
+                    });
+                } else {
+                    self.variant = None;
+                }
+
+                // Only games in the games directory are moved around, not ones in the trash
+                if let Some(dir) = game
+                    .path
+                    .parent()
+                    .filter(|p| p.starts_with(ALLIUM_GAMES_DIR.as_path()))
+                {
+                    let mut targets = files::move_targets();
+                    let i = match targets.iter().position(|(path, _)| path == dir) {
+                        Some(i) => i,
+                        None => {
+                            let label = dir
+                                .strip_prefix(ALLIUM_GAMES_DIR.as_path())
+                                .unwrap_or(dir)
+                                .display()
+                                .to_string();
+                            targets.insert(0, (dir.to_path_buf(), label));
+                            0
+                        }
+                    };
+                    entries.push(MenuEntry::Rename);
+                    entries.push(MenuEntry::Move(targets[i].1.clone()));
+                    entries.push(MenuEntry::Delete);
+                    self.target = Some(MoveSelection { target: i, targets });
+                } else {
+                    self.target = None;
+                }
             }
-            Entry::App(_) | Entry::Directory(_) => {}
+            Entry::Directory(_) => {
+                let settings = self.res.get::<LauncherSettings>();
+                if self.sort.is_console_view(&settings) {
+                    entries.push(MenuEntry::Pin(!settings.is_pinned(entry.path())));
+                    entries.push(MenuEntry::MoveUp);
+                    entries.push(MenuEntry::MoveDown);
+                }
+            }
+            Entry::App(_) => {}
+        }
+        if Trash::new().has_items() {
+            entries.push(MenuEntry::UndoDelete);
+        }
+        entries.push(MenuEntry::SelectMultiple);
+
+        drop(locale);
+        self.show_menu(entries);
+        Ok(())
+    }
+
+    /// Opens the menu of actions for the entries marked in selection mode.
+    fn open_selection_menu(&mut self) -> Result<()> {
+        let games = self.target_games();
+        let mut entries = vec![
+            MenuEntry::SelectAll,
+            MenuEntry::SelectNone,
+            MenuEntry::Favorite,
+        ];
+
+        // Cores are those of the first marked game's console, and are set for the games that can
+        // use them
+        self.core = games
+            .first()
+            .and_then(|game| {
+                self.res
+                    .get::<ConsoleMapper>()
+                    .get_console(&self.res.get(), &game.path)
+                    .map(|c| c.cores.clone())
+            })
+            .filter(|cores| !cores.is_empty())
+            .map(|cores| CoreSelection { core: 0, cores });
+        if let Some(core) = self.core.as_ref() {
+            let name = self
+                .res
+                .get::<ConsoleMapper>()
+                .get_core_name(&core.cores[0]);
+            entries.push(MenuEntry::Core(name));
         }
 
-        let height = entries.len() as u32 * (styles.ui_font.size + SELECTION_MARGIN);
+        entries.push(MenuEntry::Hide(false));
+        entries.push(MenuEntry::ResetStats);
+
+        let targets = files::move_targets();
+        if !targets.is_empty() {
+            let i = games
+                .first()
+                .and_then(|game| game.path.parent())
+                .and_then(|dir| targets.iter().position(|(path, _)| path == dir))
+                .unwrap_or_default();
+            entries.push(MenuEntry::Move(targets[i].1.clone()));
+            self.target = Some(MoveSelection { target: i, targets });
+        }
+        entries.push(MenuEntry::Delete);
+
+        self.show_menu(entries);
+        Ok(())
+    }
+
+    fn show_menu(&mut self, entries: Vec<MenuEntry>) {
+        let Rect { x, y, w, h } = self.rect;
+        let styles = self.res.get::<Stylesheet>();
+        let locale = self.res.get::<Locale>();
+
+        // Long menus scroll
+        let row_height = styles.ui_font.size + SELECTION_MARGIN;
+        let height = (entries.len() as u32 * row_height).min((h - 48) / row_height * row_height);
 
         let mut menu = ScrollList::new(
             Rect::new(
@@ -256,9 +811,194 @@ where
         );
         menu.set_background_color(Some(StylesheetColor::BackgroundHighlightBlend));
         self.menu = Some(menu);
+        self.menu_entries = entries;
+    }
 
+    /// Starts loading the images of the next few entries in the direction of scrolling.
+    fn prefetch_images(&mut self, selected: usize, forward: bool, art_type: ArtType) {
+        let indices: Vec<usize> = if forward {
+            (selected + 1..self.entries.len())
+                .take(IMAGE_PREFETCH_COUNT)
+                .collect()
+        } else {
+            (0..selected).rev().take(IMAGE_PREFETCH_COUNT).collect()
+        };
+        for i in indices {
+            if let Some(path) = self.art(i, art_type) {
+                self.image.prefetch(path);
+            }
+        }
+    }
+
+    /// Art shown for an entry. Box art is cached by the entry itself, other art types here and in
+    /// the database.
+    fn art(&mut self, index: usize, art_type: ArtType) -> Option<PathBuf> {
+        let entry = self.entries.get_mut(index)?;
+        if art_type == ArtType::Boxart {
+            return entry.image().map(Path::to_path_buf);
+        }
+        let key = (entry.path().to_path_buf(), art_type);
+        if let Some(art) = self.art.get(&key) {
+            return art.clone();
+        }
+        let art = entry.art(&self.res.get(), art_type);
+        self.art.insert(key, art.clone());
+        art
+    }
+
+    fn find_groups(&mut self) -> Result<()> {
+        self.groups = Some(self.sort.groups(
+            &self.entries,
+            &self.res.get(),
+            &self.res.get(),
+            &self.res.get(),
+        )?);
         Ok(())
     }
+
+    /// Selects the start of the next or previous group, e.g. the next letter, and shows which
+    /// group it is. Orders without groups jump a fixed number of entries instead.
+    async fn jump(&mut self, forward: bool, commands: Sender<Command>) -> Result<()> {
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+        if self.groups.is_none() {
+            self.find_groups()?;
+        }
+        let groups = self.groups.as_deref().unwrap_or_default();
+        let selected = self.list.selected();
+
+        if groups.len() != self.entries.len() {
+            let selected = if forward {
+                (selected + LISTING_JUMP_SIZE as usize).min(self.entries.len() - 1)
+            } else {
+                selected.saturating_sub(LISTING_JUMP_SIZE as usize)
+            };
+            self.select(selected);
+            return Ok(());
+        }
+
+        let index = group::jump(groups, selected, forward);
+        let text = groups[index].text(&self.res.get());
+        self.select(index);
+        commands
+            .send(Command::Toast(text, Some(JUMP_INDICATOR_DURATION)))
+            .await?;
+        Ok(())
+    }
+
+    /// Runs a file operation, then reloads the entries and shows the message it returns.
+    async fn file_operation(
+        &mut self,
+        commands: Sender<Command>,
+        operation: impl FnOnce(&ConsoleMapper, &Database, &Locale) -> Result<String>,
+    ) -> Result<()> {
+        let message = {
+            let locale = self.res.get::<Locale>();
+            match operation(&self.res.get(), &self.res.get(), &locale) {
+                Ok(message) => message,
+                Err(e) => {
+                    error!("file operation failed: {}", e);
+                    locale.t("files-failed")
+                }
+            }
+        };
+        let selected = self.list.selected();
+        self.load_entries()?;
+        self.select(selected.min(self.entries.len().saturating_sub(1)));
+        commands.send(Command::Toast(message, None)).await?;
+        commands.send(Command::Redraw).await?;
+        Ok(())
+    }
+
+    /// Reloads the entries after a bulk action, and shows how many entries it changed.
+    async fn bulk_done(
+        &mut self,
+        key: &str,
+        count: usize,
+        commands: Sender<Command>,
+    ) -> Result<()> {
+        let selected = self.list.selected();
+        self.load_entries()?;
+        self.select(selected);
+        let message = self.res.get::<Locale>().ta(
+            key,
+            &[("count".to_string(), count.into())].into_iter().collect(),
+        );
+        commands.send(Command::Toast(message, None)).await?;
+        commands.send(Command::Redraw).await?;
+        Ok(())
+    }
+
+    /// Switches to the next type of art, for every list.
+    async fn cycle_art_type(&mut self, commands: Sender<Command>) -> Result<()> {
+        let mut styles = self.res.get::<Stylesheet>().clone();
+        styles.art_type = styles.art_type.next();
+        if let Err(e) = styles.save() {
+            warn!("failed to save art type: {}", e);
+        }
+        let message = self.res.get::<Locale>().t(styles.art_type.key());
+        self.res.insert(styles);
+        self.image.set_should_draw();
+        commands.send(Command::Toast(message, None)).await?;
+        Ok(())
+    }
+}
+
+/// Index of the entry at `path`. Games are also found by the paths of their variants.
+fn position(entries: &[Entry], path: &Path) -> Option<usize> {
+    entries.iter().position(|e| match e {
+        Entry::Game(game) => game.path == path || game.variants.iter().any(|v| v.path == path),
+        _ => e.path() == path,
+    })
+}
+
+/// Paths of the entries around `selected`, nearest first, alternating between after and before it.
+fn neighbours(entries: &[Entry], selected: usize) -> Vec<PathBuf> {
+    (1..=SAVED_NEIGHBOURS)
+        .flat_map(|distance| {
+            [
+                selected.checked_add(distance),
+                selected.checked_sub(distance),
+            ]
+        })
+        .flatten()
+        .filter_map(|i| entries.get(i))
+        .map(|e| e.path().to_path_buf())
+        .collect()
+}
+
+/// Index of the entry to select when restoring a state: the entry that was selected, or else its
+/// nearest neighbour that is still there, or else the entry at the same index.
+fn find_selected<S>(entries: &[Entry], state: &EntryListState<S>) -> usize {
+    state
+        .path
+        .iter()
+        .chain(state.neighbours.iter())
+        .find_map(|path| position(entries, path))
+        .unwrap_or(state.selected)
+}
+
+/// Where the selected entry's box art is drawn. Box art thumbnails are generated at this size.
+pub fn image_rect(rect: Rect, styles: &Stylesheet) -> Rect {
+    let Rect { x, y, w, h } = rect;
+    Rect::new(
+        x + w as i32 - IMAGE_WIDTH as i32 - 24,
+        y + 8,
+        IMAGE_WIDTH,
+        h - 8 - ButtonIcon::diameter(styles) - 8,
+    )
+}
+
+/// Where the grid layout's tiles are drawn.
+pub fn grid_rect(rect: Rect, styles: &Stylesheet) -> Rect {
+    let Rect { x, y, w, h } = rect;
+    Rect::new(
+        x + 12,
+        y + 8,
+        w - 24,
+        h - 8 - ButtonIcon::diameter(styles) - 8,
+    )
 }
 
 #[async_trait(?Send)]
@@ -275,6 +1015,10 @@ where
             return child.draw(display, styles);
         }
 
+        if let Some(details) = &mut self.details {
+            return details.draw(display, styles);
+        }
+
         let mut drawn = false;
 
         if let Some(menu) = &mut self.menu {
@@ -300,16 +1044,55 @@ where
             return Ok(drawn);
         }
 
-        drawn |= self.list.should_draw() && self.list.draw(display, styles)?;
+        if self.layout == Layout::Grid {
+            for i in self.grid.visible_range() {
+                let art = self.art(i, styles.art_type);
+                self.grid.set_image(i, art);
+            }
+            drawn |= self.grid.should_draw() && self.grid.draw(display, styles)?;
+        } else {
+            drawn |= self.list.should_draw() && self.list.draw(display, styles)?;
 
-        if styles.enable_box_art {
-            // TODO: relayout list if box art is enabled/disabled
-            if let Some(entry) = self.entries.get_mut(self.list.selected()) {
-                if let Some(path) = entry.image() {
-                    self.image.set_path(Some(path.to_path_buf()));
-                } else {
-                    self.image.set_path(None);
+            if let Some(section) = self.section.as_mut() {
+                let selected = self.list.selected();
+                let text = match self.summaries.as_ref() {
+                    Some(summaries) => summaries.get(selected).cloned(),
+                    None => self
+                        .groups
+                        .as_ref()
+                        .and_then(|groups| groups.get(selected))
+                        .map(|group| group.text(&self.res.get())),
+                }
+                .unwrap_or_default();
+                section.set_text(text);
+                if section.should_draw() {
+                    let rect = section.bounding_box(styles);
+                    display.load(Rect::new(
+                        rect.x,
+                        rect.y,
+                        self.list.bounding_box(styles).w,
+                        rect.h,
+                    ))?;
+                    drawn |= section.draw(display, styles)?;
                 }
+            }
+        }
+
+        if styles.enable_box_art && self.layout == Layout::List {
+            // TODO: relayout list if box art is enabled/disabled
+            let art_type = if self.cycle_art {
+                styles.art_type
+            } else {
+                ArtType::Boxart
+            };
+            let selected = self.list.selected();
+            if selected != self.image_selected {
+                self.prefetch_images(selected, selected > self.image_selected, art_type);
+                self.image_selected = selected;
+            }
+            if selected < self.entries.len() {
+                let art = self.art(selected, art_type);
+                self.image.set_path(art);
                 if self.image.should_draw() && self.image.draw(display, styles)? {
                     drawn = true;
                 }
@@ -331,32 +1114,61 @@ where
             }
         }
 
+        if let Some(keyboard) = self.keyboard.as_mut() {
+            if drawn {
+                keyboard.set_should_draw();
+            }
+            drawn |= keyboard.should_draw() && keyboard.draw(display, styles)?;
+        }
+
         Ok(drawn)
     }
 
     fn should_draw(&self) -> bool {
         if let Some(child) = self.child.as_ref() {
             child.should_draw()
+        } else if let Some(details) = self.details.as_ref() {
+            details.should_draw()
         } else {
             self.menu
                 .as_ref()
                 .map_or(false, common::view::View::should_draw)
-                || self.list.should_draw()
-                || self.image.should_draw()
+                || match self.layout {
+                    Layout::List => {
+                        self.list.should_draw()
+                            || self.image.should_draw()
+                            || self.section.as_ref().is_some_and(Label::should_draw)
+                    }
+                    Layout::Grid => self.grid.should_draw(),
+                }
                 || self.button_hints.should_draw()
+                || self.keyboard.as_ref().is_some_and(View::should_draw)
         }
     }
 
     fn set_should_draw(&mut self) {
         if let Some(child) = self.child.as_mut() {
             child.set_should_draw();
+        } else if let Some(details) = self.details.as_mut() {
+            details.set_should_draw();
         } else {
             if let Some(menu) = self.menu.as_mut() {
                 menu.set_should_draw();
             }
-            self.list.set_should_draw();
-            self.image.set_should_draw();
+            match self.layout {
+                Layout::List => {
+                    self.list.set_should_draw();
+                    self.image.set_should_draw();
+                    if let Some(section) = self.section.as_mut() {
+                        section.set_should_draw();
+                    }
+                }
+                Layout::Grid => self.grid.set_should_draw(),
+            }
             self.button_hints.set_should_draw();
+            if let Some(keyboard) = self.keyboard.as_mut() {
+                keyboard.set_should_draw();
+            }
         }
     }
 
@@ -371,7 +1183,10 @@ where
                 true => {
                     bubble.retain_mut(|c| match c {
                         Command::CloseView => {
-                            self.child = None;
+                            if let Some(child) = self.child.take() {
+                                // Keep the sorts chosen in the child
+                                self.sorts = child.sorts;
+                            }
                             self.set_should_draw();
                             true
                         }
@@ -381,29 +1196,149 @@ where
                 }
                 false => Ok(false),
             }
+        } else if let Some(details) = self.details.as_mut() {
+            if details
+                .handle_key_event(event, commands.clone(), bubble)
+                .await?
+            {
+                let mut closed = false;
+                bubble.retain(|c| match c {
+                    Command::CloseView => {
+                        closed = true;
+                        false
+                    }
+                    _ => true,
+                });
+                if closed {
+                    self.details = None;
+                    // The game may have been deleted, favorited or had its stats reset
+                    self.load_entries()?;
+                    commands.send(Command::Redraw).await?;
+                }
+                Ok(true)
+            } else {
+                Ok(false)
+            }
+        } else if let Some(keyboard) = self.keyboard.as_mut() {
+            if keyboard
+                .handle_key_event(event, commands.clone(), bubble)
+                .await?
+            {
+                let mut name = None;
+                let mut closed = false;
+                bubble.retain(|c| match c {
+                    Command::ValueChanged(_, Value::String(value)) => {
+                        name = Some(value.clone());
+                        false
+                    }
+                    Command::ValueChanged(_, _) => false,
+                    Command::CloseView => {
+                        closed = true;
+                        false
+                    }
+                    _ => true,
+                });
+                if closed {
+                    self.keyboard = None;
+                    commands.send(Command::Redraw).await?;
+                }
+                if let (Some(name), Some(Entry::Game(game))) =
+                    (name, self.entries.get(self.list.selected()))
+                {
+                    let path = game.path.clone();
+                    self.file_operation(commands, |console_mapper, database, locale| {
+                        files::rename_game(console_mapper, database, &path, &name)?;
+                        Ok(locale.ta(
+                            "files-renamed",
+                            &[("name".to_string(), name.into())].into_iter().collect(),
+                        ))
+                    })
+                    .await?;
+                }
+                Ok(true)
+            } else {
+                Ok(false)
+            }
         } else if let Some(menu) = self.menu.as_mut() {
+            if !matches!(event, KeyEvent::Pressed(Key::A) | KeyEvent::Released(_)) {
+                if let Some(i) = self.confirm.take() {
+                    let entry = match (&self.menu_entries[i], self.target.as_ref()) {
+                        (MenuEntry::Move(_), Some(target)) => {
+                            MenuEntry::Move(target.targets[target.target].1.clone())
+                        }
+                        (entry, _) => entry.clone(),
+                    };
+                    menu.set_item(i, entry.text(&self.res.get()));
+                }
+            }
             match event {
                 KeyEvent::Pressed(Key::Left) => {
+                    if let Some(target) = self.target.as_mut() {
+                        if let MenuEntry::Move(_) = self.menu_entries[menu.selected()] {
+                            target.target = target.target.saturating_sub(1);
+                            let selected = MenuEntry::Move(target.targets[target.target].1.clone());
+                            menu.set_item(menu.selected(), selected.text(&self.res.get()));
+                        }
+                    }
+                    if let Some(variant) = self.variant.as_mut() {
+                        if let MenuEntry::Variant(_) = self.menu_entries[menu.selected()] {
+                            variant.variant = variant.variant.saturating_sub(1);
+                            menu.set_item(menu.selected(), variant.labels[variant.variant].clone());
+                        }
+                    }
+                    if let Some(console) = self.console.as_mut() {
+                        let mut selected = self.menu_entries[menu.selected()].clone();
+                        if let MenuEntry::Console(ref mut filter) = selected {
+                            console.console = console.console.saturating_sub(1);
+                            *filter = console.consoles[console.console].clone();
+                            menu.set_item(menu.selected(), selected.text(&self.res.get()));
+                        }
+                    }
                     if let Some(core) = self.core.as_mut() {
-                        let mut selected = MenuEntry::from_repr(menu.selected());
-                        if let MenuEntry::Launch(ref mut launch_core) = selected {
+                        let selected = self.menu_entries[menu.selected()].clone();
+                        if let MenuEntry::Launch(_) | MenuEntry::Core(_) = selected {
                             core.core = core.core.saturating_sub(1);
-                            let console_mapper = self.res.get::<ConsoleMapper>();
-                            *launch_core =
-                                Some(console_mapper.get_core_name(&core.cores[core.core]));
+                            let name = self
+                                .res
+                                .get::<ConsoleMapper>()
+                                .get_core_name(&core.cores[core.core]);
+                            let selected = selected.with_core(name);
                             menu.set_item(menu.selected(), selected.text(&self.res.get()));
                         }
                     }
                     Ok(true) // trap tab focus
                 }
                 KeyEvent::Pressed(Key::Right) => {
+                    if let Some(target) = self.target.as_mut() {
+                        if let MenuEntry::Move(_) = self.menu_entries[menu.selected()] {
+                            target.target = (target.target + 1).min(target.targets.len() - 1);
+                            let selected = MenuEntry::Move(target.targets[target.target].1.clone());
+                            menu.set_item(menu.selected(), selected.text(&self.res.get()));
+                        }
+                    }
+                    if let Some(variant) = self.variant.as_mut() {
+                        if let MenuEntry::Variant(_) = self.menu_entries[menu.selected()] {
+                            variant.variant = (variant.variant + 1).min(variant.variants.len() - 1);
+                            menu.set_item(menu.selected(), variant.labels[variant.variant].clone());
+                        }
+                    }
+                    if let Some(console) = self.console.as_mut() {
+                        let mut selected = self.menu_entries[menu.selected()].clone();
+                        if let MenuEntry::Console(ref mut filter) = selected {
+                            console.console = (console.console + 1).min(console.consoles.len() - 1);
+                            *filter = console.consoles[console.console].clone();
+                            menu.set_item(menu.selected(), selected.text(&self.res.get()));
+                        }
+                    }
                     if let Some(core) = self.core.as_mut() {
-                        let mut selected = MenuEntry::from_repr(menu.selected());
-                        if let MenuEntry::Launch(ref mut launch_core) = selected {
+                        let selected = self.menu_entries[menu.selected()].clone();
+                        if let MenuEntry::Launch(_) | MenuEntry::Core(_) = selected {
                             core.core = (core.core + 1).min(core.cores.len() - 1);
-                            let console_mapper = self.res.get::<ConsoleMapper>();
-                            *launch_core =
-                                Some(console_mapper.get_core_name(&core.cores[core.core]));
+                            let name = self
+                                .res
+                                .get::<ConsoleMapper>()
+                                .get_core_name(&core.cores[core.core]);
+                            let selected = selected.with_core(name);
                             menu.set_item(menu.selected(), selected.text(&self.res.get()));
                         }
                     }
@@ -411,11 +1346,37 @@ where
                 }
                 KeyEvent::Pressed(Key::Select | Key::B) => {
                     self.menu = None;
+                    self.console = None;
+                    self.variant = None;
+                    self.target = None;
                     commands.send(Command::Redraw).await?;
                     Ok(true)
                 }
                 KeyEvent::Pressed(Key::A) => {
-                    let selected = MenuEntry::from_repr(menu.selected());
+                    let index = menu.selected();
+                    let selected = self.menu_entries[index].clone();
+                    // Moving or deleting several games is confirmed first
+                    if matches!(selected, MenuEntry::Move(_) | MenuEntry::Delete)
+                        && self.confirm != Some(index)
+                    {
+                        let count = self.target_games().len();
+                        if count > 1 {
+                            let key = match selected {
+                                MenuEntry::Delete => "menu-delete-confirm",
+                                _ => "menu-move-confirm",
+                            };
+                            let text = self.res.get::<Locale>().ta(
+                                key,
+                                &[("count".to_string(), count.into())].into_iter().collect(),
+                            );
+                            if let Some(menu) = self.menu.as_mut() {
+                                menu.set_item(index, text);
+                            }
+                            self.confirm = Some(index);
+                            return Ok(true);
+                        }
+                    }
+                    self.confirm = None;
                     match selected {
                         MenuEntry::Launch(_) => {
                             let entry = self.entries.get_mut(self.list.selected()).unwrap();
@@ -428,6 +1389,33 @@ where
                             self.core = None;
                             self.select_entry(commands).await?;
                         }
+                        MenuEntry::Details => {
+                            self.core = None;
+                            if let Some(Entry::Game(game)) = self.entries.get(self.list.selected())
+                            {
+                                self.details = Some(GameDetails::new(
+                                    self.rect,
+                                    self.res.clone(),
+                                    game.clone(),
+                                )?);
+                            }
+                        }
+                        MenuEntry::Hide(hidden) => {
+                            self.core = None;
+                            let paths: Vec<PathBuf> = self
+                                .targets()
+                                .into_iter()
+                                .map(|e| e.path().to_path_buf())
+                                .collect();
+                            self.set_selection_mode(false);
+                            let database = self.res.get::<Database>();
+                            for path in &paths {
+                                database.set_hidden(path, !hidden)?;
+                            }
+                            drop(database);
+                            self.load_entries()?;
+                            commands.send(Command::Redraw).await?;
+                        }
                         MenuEntry::RemoveFromRecents => {
                             if let Some(Entry::Game(game)) = self.entries.get(self.list.selected())
                             {
@@ -463,54 +1451,281 @@ where
                             }
                             commands.send(Command::Redraw).await?;
                         }
+                        MenuEntry::Layout(_) => {
+                            let mut settings = self.res.get::<LauncherSettings>().clone();
+                            settings.set_layout(self.sort.path(), self.layout.toggle());
+                            commands
+                                .send(Command::SaveLauncherSettings(settings))
+                                .await?;
+                        }
+                        MenuEntry::Variant(_) => {
+                            if let Some(variant) = self.variant.take() {
+                                let mut game = variant.variants[variant.variant].clone();
+                                let command = self
+                                    .res
+                                    .get::<ConsoleMapper>()
+                                    .launch_game(&self.res.get(), &mut game)?
+                                    .map(|launch| launch.start(commands.clone(), &self.res.get()));
+                                if let Some(cmd) = command {
+                                    commands.send(cmd).await?;
+                                }
+                            }
+                        }
+                        MenuEntry::Flatten(flatten) => {
+                            if let Some(path) = self.sort.path() {
+                                let mut settings = self.res.get::<LauncherSettings>().clone();
+                                settings.set_flattened(path, flatten);
+                                commands
+                                    .send(Command::SaveLauncherSettings(settings))
+                                    .await?;
+                            }
+                        }
+                        MenuEntry::Console(_) => {
+                            if let Some(console) = self.console.as_ref() {
+                                let filter = console.consoles[console.console].clone();
+                                self.sort(self.sort.with_console(filter))?;
+                                commands.send(Command::Redraw).await?;
+                            }
+                        }
+                        MenuEntry::Pin(pin) => {
+                            if let Some(entry) = self.entries.get(self.list.selected()) {
+                                let mut settings = self.res.get::<LauncherSettings>().clone();
+                                settings.set_pinned(entry.path(), pin);
+                                commands
+                                    .send(Command::SaveLauncherSettings(settings))
+                                    .await?;
+                            }
+                        }
+                        direction @ (MenuEntry::MoveUp | MenuEntry::MoveDown) => {
+                            // Consoles move among the other consoles
+                            let order: Vec<PathBuf> = self
+                                .entries
+                                .iter()
+                                .filter_map(|e| match e {
+                                    Entry::Directory(dir) => Some(dir.path.clone()),
+                                    _ => None,
+                                })
+                                .collect();
+                            let selected = self.entries.get(self.list.selected()).map(Entry::path);
+                            if let Some(index) =
+                                order.iter().position(|p| Some(p.as_path()) == selected)
+                            {
+                                let mut settings = self.res.get::<LauncherSettings>().clone();
+                                settings.move_pinned(
+                                    &order,
+                                    index,
+                                    matches!(direction, MenuEntry::MoveUp),
+                                );
+                                commands
+                                    .send(Command::SaveLauncherSettings(settings))
+                                    .await?;
+                            }
+                        }
+                        MenuEntry::Rename => {
+                            if let Some(Entry::Game(game)) = self.entries.get(self.list.selected())
+                            {
+                                let name = game
+                                    .path
+                                    .file_stem()
+                                    .map(|s| s.to_string_lossy().to_string())
+                                    .unwrap_or_default();
+                                self.keyboard = Some(Keyboard::new(self.res.clone(), name, false));
+                                commands.send(Command::Redraw).await?;
+                            }
+                        }
+                        MenuEntry::Move(_) => {
+                            let games = self.target_games();
+                            self.set_selection_mode(false);
+                            if let Some(target) = self.target.take() {
+                                let (dir, label) = target.targets[target.target].clone();
+                                let paths: Vec<PathBuf> = games
+                                    .into_iter()
+                                    .map(|game| game.path)
+                                    .filter(|path| path.parent() != Some(dir.as_path()))
+                                    .collect();
+                                if !paths.is_empty() {
+                                    self.file_operation(
+                                        commands,
+                                        |console_mapper, database, locale| {
+                                            for path in &paths {
+                                                files::move_game_to(
+                                                    console_mapper,
+                                                    database,
+                                                    path,
+                                                    &dir,
+                                                )?;
+                                            }
+                                            Ok(locale.ta(
+                                                "files-moved",
+                                                &[("name".to_string(), label.into())]
+                                                    .into_iter()
+                                                    .collect(),
+                                            ))
+                                        },
+                                    )
+                                    .await?;
+                                }
+                            }
+                        }
+                        MenuEntry::Delete => {
+                            let games = self.target_games();
+                            self.set_selection_mode(false);
+                            if let Some(first) = games.first() {
+                                let name = first.name.clone();
+                                let paths: Vec<PathBuf> =
+                                    games.iter().map(|game| game.path.clone()).collect();
+                                self.file_operation(
+                                    commands,
+                                    |console_mapper, database, locale| {
+                                        Trash::new().trash(console_mapper, database, &paths)?;
+                                        Ok(locale.ta(
+                                            "files-deleted",
+                                            &[
+                                                ("name".to_string(), name.into()),
+                                                ("count".to_string(), games.len().into()),
+                                            ]
+                                            .into_iter()
+                                            .collect(),
+                                        ))
+                                    },
+                                )
+                                .await?;
+                            }
+                        }
+                        MenuEntry::UndoDelete => {
+                            self.file_operation(commands, |_, database, locale| {
+                                let restored = Trash::new().restore(database)?;
+                                let name = restored
+                                    .first()
+                                    .and_then(|path| path.file_stem())
+                                    .map(|s| s.to_string_lossy().to_string())
+                                    .unwrap_or_default();
+                                Ok(locale.ta(
+                                    "files-restored",
+                                    &[
+                                        ("name".to_string(), name.into()),
+                                        ("count".to_string(), restored.len().into()),
+                                    ]
+                                    .into_iter()
+                                    .collect(),
+                                ))
+                            })
+                            .await?;
+                        }
+                        MenuEntry::SelectMultiple => {
+                            self.set_selection_mode(true);
+                            self.toggle_marked();
+                            commands.send(Command::Redraw).await?;
+                        }
+                        MenuEntry::SelectAll => {
+                            if let Some(marked) = self.marked.as_mut() {
+                                marked.extend(self.entries.iter().map(|e| e.path().to_path_buf()));
+                            }
+                            self.update_marked();
+                            commands.send(Command::Redraw).await?;
+                        }
+                        MenuEntry::SelectNone => {
+                            if let Some(marked) = self.marked.as_mut() {
+                                marked.clear();
+                            }
+                            self.update_marked();
+                            commands.send(Command::Redraw).await?;
+                        }
+                        MenuEntry::Favorite => {
+                            let games = self.target_games();
+                            self.set_selection_mode(false);
+                            {
+                                let database = self.res.get::<Database>();
+                                for game in &games {
+                                    database.set_favorite(&game.path, true)?;
+                                }
+                            }
+                            self.bulk_done("selection-favorited", games.len(), commands)
+                                .await?;
+                        }
+                        MenuEntry::Core(_) => {
+                            let games = self.target_games();
+                            self.set_selection_mode(false);
+                            if let Some(core) = self.core.take() {
+                                let core = &core.cores[core.core];
+                                let mut count = 0;
+                                {
+                                    let database = self.res.get::<Database>();
+                                    let console_mapper = self.res.get::<ConsoleMapper>();
+                                    for game in &games {
+                                        if console_mapper
+                                            .get_console(&database, &game.path)
+                                            .is_some_and(|c| c.cores.contains(core))
+                                        {
+                                            database.set_core(&game.path, core)?;
+                                            count += 1;
+                                        }
+                                    }
+                                }
+                                self.bulk_done("selection-core-set", count, commands)
+                                    .await?;
+                            }
+                        }
+                        MenuEntry::ResetStats => {
+                            let games = self.target_games();
+                            self.set_selection_mode(false);
+                            {
+                                let database = self.res.get::<Database>();
+                                for game in &games {
+                                    database.reset_game(&game.path)?;
+                                }
+                            }
+                            self.bulk_done("selection-stats-reset", games.len(), commands)
+                                .await?;
+                        }
+                        MenuEntry::ExportGamelists => {
+                            let locale = self.res.get::<Locale>();
+                            let message = match export_gamelists(&self.res.get::<Database>()) {
+                                Ok(()) => locale.t("gamelists-exported"),
+                                Err(e) => {
+                                    error!("failed to export gamelists: {}", e);
+                                    locale.t("gamelists-export-failed")
+                                }
+                            };
+                            commands.send(Command::Toast(message, None)).await?;
+                            commands.send(Command::Redraw).await?;
+                        }
                     }
                     self.menu = None;
+                    self.core = None;
+                    self.console = None;
+                    self.variant = None;
+                    self.target = None;
                     Ok(true)
                 }
                 _ => menu.handle_key_event(event, commands, bubble).await,
             }
         } else {
             match event {
-                KeyEvent::Pressed(Key::L2) => {
-                    let selected = self.list.selected();
-                    let len = self.entries.len();
-                    let mut entries = self
-                        .entries
-                        .iter()
-                        .rev()
-                        .skip(len - selected)
-                        .map(|e| e.name().chars().next());
-                    println!("{:?}", entries.clone().collect::<Vec<_>>());
-                    let Some(char) = entries.next() else {
-                        self.list.select(0);
-                        return Ok(true);
-                    };
-
-                    println!("entries: {:?}, char: {:?}", entries, char);
-                    if let Some(i) = entries.position(|c| c != char) {
-                        self.list.select(selected - i - 1);
-                    } else {
-                        self.list.select(0);
-                    }
+                KeyEvent::Pressed(Key::L) | KeyEvent::Autorepeat(Key::L) => {
+                    self.jump(false, commands).await?;
                     Ok(true)
                 }
-                KeyEvent::Pressed(Key::R2) => {
-                    let selected = self.list.selected();
-                    let mut entries = self
-                        .entries
-                        .iter()
-                        .skip(selected)
-                        .map(|e| e.name().chars().next());
-                    let Some(char) = entries.next() else {
-                        self.list.select(self.entries.len() - 1);
-                        return Ok(true);
-                    };
-
-                    if let Some(i) = entries.position(|c| c != char) {
-                        self.list.select(selected + 1 + i);
-                    } else {
-                        self.list.select(self.entries.len() - 1);
-                    }
+                KeyEvent::Pressed(Key::R) | KeyEvent::Autorepeat(Key::R) => {
+                    self.jump(true, commands).await?;
+                    Ok(true)
+                }
+                KeyEvent::Pressed(Key::L2) | KeyEvent::Autorepeat(Key::L2) => {
+                    let selected = self.list.selected() as i32 - LISTING_JUMP_SIZE;
+                    self.select(selected.max(0) as usize);
+                    Ok(true)
+                }
+                KeyEvent::Pressed(Key::R2) | KeyEvent::Autorepeat(Key::R2) => {
+                    let selected = self.list.selected() + LISTING_JUMP_SIZE as usize;
+                    self.select(selected.min(self.entries.len().saturating_sub(1)));
+                    Ok(true)
+                }
+                KeyEvent::Pressed(Key::B) if self.marked.is_some() => {
+                    self.set_selection_mode(false);
+                    Ok(true)
+                }
+                KeyEvent::Pressed(Key::A) if self.marked.is_some() => {
+                    self.toggle_marked();
                     Ok(true)
                 }
                 KeyEvent::Pressed(Key::B) => {
@@ -529,7 +1744,22 @@ where
                     self.open_menu()?;
                     Ok(true)
                 }
-                _ => self.list.handle_key_event(event, commands, bubble).await,
+                KeyEvent::Pressed(Key::Start) if self.cycle_art => {
+                    self.cycle_art_type(commands).await?;
+                    Ok(true)
+                }
+                _ => match self.layout {
+                    Layout::List => {
+                        let handled = self.list.handle_key_event(event, commands, bubble).await?;
+                        self.grid.select(self.list.selected());
+                        Ok(handled)
+                    }
+                    Layout::Grid => {
+                        let handled = self.grid.handle_key_event(event, commands, bubble).await?;
+                        self.list.select(self.grid.selected());
+                        Ok(handled)
+                    }
+                },
             }
         }
     }
@@ -537,16 +1767,37 @@ where
     fn children(&self) -> Vec<&dyn View> {
         if let Some(child) = self.child.as_ref() {
             vec![child.as_ref() as &dyn View]
+        } else if let Some(details) = self.details.as_ref() {
+            vec![details as &dyn View]
+        } else if let Some(keyboard) = self.keyboard.as_ref() {
+            vec![keyboard as &dyn View]
+        } else if self.layout == Layout::Grid {
+            vec![&self.grid, &self.button_hints]
         } else {
-            vec![&self.list, &self.image, &self.button_hints]
+            let mut children: Vec<&dyn View> = vec![&self.list, &self.image, &self.button_hints];
+            if let Some(section) = self.section.as_ref() {
+                children.push(section);
+            }
+            children
         }
     }
 
     fn children_mut(&mut self) -> Vec<&mut dyn View> {
         if let Some(child) = self.child.as_mut() {
             vec![child.as_mut() as &mut dyn View]
+        } else if let Some(details) = self.details.as_mut() {
+            vec![details as &mut dyn View]
+        } else if let Some(keyboard) = self.keyboard.as_mut() {
+            vec![keyboard as &mut dyn View]
+        } else if self.layout == Layout::Grid {
+            vec![&mut self.grid, &mut self.button_hints]
         } else {
-            vec![&mut self.list, &mut self.image, &mut self.button_hints]
+            let mut children: Vec<&mut dyn View> =
+                vec![&mut self.list, &mut self.image, &mut self.button_hints];
+            if let Some(section) = self.section.as_mut() {
+                children.push(section);
+            }
+            children
         }
     }
 
@@ -559,19 +1810,49 @@ where
     }
 }
 
+#[derive(Debug, Clone)]
 enum MenuEntry {
     Launch(Option<String>),
+    Details,
+    /// Whether the entry is currently hidden.
+    Hide(bool),
     RemoveFromRecents,
     RepopulateDatabase,
+    ExportGamelists,
+    /// The layout to switch to.
+    Layout(Layout),
+    /// Whether to switch to listing the games of all subdirectories together.
+    Flatten(bool),
+    /// Console that the flattened listing is limited to, or all consoles.
+    Console(Option<String>),
+    /// Label of the variant of the game to launch.
+    Variant(String),
+    /// Whether to pin the console, or unpin it.
+    Pin(bool),
+    MoveUp,
+    MoveDown,
+    Rename,
+    /// Folder to move the game to, relative to the games directory.
+    Move(String),
+    Delete,
+    UndoDelete,
+    /// Enters selection mode, to act on several entries at once.
+    SelectMultiple,
+    SelectAll,
+    SelectNone,
+    Favorite,
+    /// Core to set for the marked games.
+    Core(String),
+    ResetStats,
 }
 
 impl MenuEntry {
-    fn from_repr(i: usize) -> Self {
-        match i {
-            0 => MenuEntry::Launch(None),
-            1 => MenuEntry::RemoveFromRecents,
-            2 => MenuEntry::RepopulateDatabase,
-            _ => unreachable!("invalid menu entry"),
+    /// The entry with its core changed, for entries that choose a core.
+    fn with_core(self, core: String) -> Self {
+        match self {
+            MenuEntry::Launch(_) => MenuEntry::Launch(Some(core)),
+            MenuEntry::Core(_) => MenuEntry::Core(core),
+            entry => entry,
         }
     }
 
@@ -587,8 +1868,90 @@ impl MenuEntry {
                     locale.t("menu-launch")
                 }
             }
+            MenuEntry::Details => locale.t("menu-details"),
+            MenuEntry::Hide(false) => locale.t("menu-hide"),
+            MenuEntry::Hide(true) => locale.t("menu-unhide"),
             MenuEntry::RemoveFromRecents => locale.t("menu-remove-from-recents"),
             MenuEntry::RepopulateDatabase => locale.t("menu-repopulate-database"),
+            MenuEntry::ExportGamelists => locale.t("menu-export-gamelists"),
+            MenuEntry::Layout(Layout::List) => locale.t("menu-list-view"),
+            MenuEntry::Layout(Layout::Grid) => locale.t("menu-grid-view"),
+            MenuEntry::Flatten(true) => locale.t("menu-show-all-games"),
+            MenuEntry::Flatten(false) => locale.t("menu-show-folders"),
+            MenuEntry::Console(None) => locale.t("menu-console-all"),
+            MenuEntry::Variant(label) => label.clone(),
+            MenuEntry::Pin(true) => locale.t("menu-pin"),
+            MenuEntry::Pin(false) => locale.t("menu-unpin"),
+            MenuEntry::MoveUp => locale.t("menu-move-up"),
+            MenuEntry::MoveDown => locale.t("menu-move-down"),
+            MenuEntry::Rename => locale.t("menu-rename"),
+            MenuEntry::Move(folder) => locale.ta(
+                "menu-move-to",
+                &[("folder".to_string(), folder.clone().into())]
+                    .into_iter()
+                    .collect(),
+            ),
+            MenuEntry::Delete => locale.t("menu-delete"),
+            MenuEntry::UndoDelete => locale.t("menu-undo-delete"),
+            MenuEntry::SelectMultiple => locale.t("menu-select-multiple"),
+            MenuEntry::SelectAll => locale.t("menu-select-all"),
+            MenuEntry::SelectNone => locale.t("menu-select-none"),
+            MenuEntry::Favorite => locale.t("menu-favorite"),
+            MenuEntry::Core(core) => locale.ta(
+                "menu-core",
+                &[("core".to_string(), core.clone().into())]
+                    .into_iter()
+                    .collect(),
+            ),
+            MenuEntry::ResetStats => locale.t("menu-reset-stats"),
+            MenuEntry::Console(Some(console)) => locale.ta(
+                "menu-console",
+                &[("console".to_string(), console.clone().into())]
+                    .into_iter()
+                    .collect(),
+            ),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn games(names: &[&str]) -> Vec<Entry> {
+        names
+            .iter()
+            .map(|name| Entry::Game(Game::new(PathBuf::from(format!("/Roms/GB/{}.gb", name)))))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_selected() {
+        let entries = games(&["A", "B", "C", "D", "E"]);
+        let state = EntryListState {
+            sort: (),
+            selected: 2,
+            path: Some(entries[2].path().to_path_buf()),
+            neighbours: neighbours(&entries, 2),
+            child: None,
+            sorts: HashMap::new(),
+        };
+        assert_eq!(
+            state.neighbours,
+            ["D", "B", "E", "A"]
+                .iter()
+                .map(|n| PathBuf::from(format!("/Roms/GB/{}.gb", n)))
+                .collect::<Vec<_>>()
+        );
+
+        // A game was added before the selected one
+        assert_eq!(
+            find_selected(&games(&["0", "A", "B", "C", "D", "E"]), &state),
+            3
+        );
+        // The selected game was removed, so the one after it is selected
+        assert_eq!(find_selected(&games(&["A", "B", "D", "E"]), &state), 2);
+        // Nothing is left of it, so the same index is selected
+        assert_eq!(find_selected(&games(&["V", "W", "X", "Y", "Z"]), &state), 2);
+    }
+}
@@ -0,0 +1,455 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Local, NaiveDateTime, TimeZone};
+use common::command::Command;
+use common::constants::{ALLIUM_GAMES_DIR, SELECTION_MARGIN};
+use common::database::Database;
+use common::display::Display;
+use common::geom::{Alignment, Point, Rect};
+use common::locale::Locale;
+use common::platform::{DefaultPlatform, Key, KeyEvent, Platform};
+use common::resources::Resources;
+use common::stylesheet::Stylesheet;
+use common::view::{
+    ButtonHint, ButtonIcon, Image, ImageMode, Label, Row, Select, SettingsList, Toggle, View,
+};
+use log::info;
+use tokio::sync::mpsc::Sender;
+
+use crate::consoles::ConsoleMapper;
+use crate::entry::game::Game;
+use crate::files::Trash;
+
+const LAUNCH: usize = 0;
+const CORE: usize = 1;
+const FAVORITE: usize = 2;
+const RESET_STATS: usize = 3;
+const DELETE: usize = 4;
+
+/// Full-screen view of a single game, with its art, metadata and stats, and actions to manage it.
+#[derive(Debug)]
+pub struct GameDetails {
+    rect: Rect,
+    res: Resources,
+    game: Game,
+    cores: Vec<String>,
+    image: Image,
+    list: SettingsList,
+    button_hints: Row<ButtonHint<String>>,
+    confirm_delete: bool,
+    dirty: bool,
+}
+
+impl GameDetails {
+    pub fn new(rect: Rect, res: Resources, mut game: Game) -> Result<Self> {
+        let Rect { x, y, w, h } = rect;
+
+        let styles = res.get::<Stylesheet>();
+        let locale = res.get::<Locale>();
+
+        let cores = res
+            .get::<ConsoleMapper>()
+            .get_console(&res.get(), &game.path)
+            .map(|c| c.cores.clone())
+            .unwrap_or_default();
+
+        let image_width = w * 2 / 5;
+        let mut image = Image::empty(
+            Rect::new(
+                x + 12,
+                y + 8,
+                image_width,
+                h - 8 - ButtonIcon::diameter(&styles) - 8,
+            ),
+            ImageMode::Contain,
+        );
+        image.set_border_radius(12);
+        image.set_path(game.image().map(|p| p.to_path_buf()));
+
+        let list = SettingsList::new(
+            Rect::new(
+                x + 12 + image_width as i32 + 12,
+                y + 8,
+                w - image_width - 12 - 12 - 12,
+                h - 8 - ButtonIcon::diameter(&styles) - 8,
+            ),
+            Vec::new(),
+            Vec::new(),
+            styles.ui_font.size + SELECTION_MARGIN,
+        );
+
+        let button_hints = Row::new(
+            Point::new(
+                x + w as i32 - 12,
+                y + h as i32 - ButtonIcon::diameter(&styles) as i32 - 8,
+            ),
+            vec![
+                ButtonHint::new(
+                    Point::zero(),
+                    Key::A,
+                    locale.t("button-select"),
+                    Alignment::Right,
+                ),
+                ButtonHint::new(
+                    Point::zero(),
+                    Key::B,
+                    locale.t("button-back"),
+                    Alignment::Right,
+                ),
+            ],
+            Alignment::Right,
+            12,
+        );
+
+        drop(styles);
+        drop(locale);
+
+        let mut this = Self {
+            rect,
+            res,
+            game,
+            cores,
+            image,
+            list,
+            button_hints,
+            confirm_delete: false,
+            dirty: true,
+        };
+        this.load()?;
+
+        Ok(this)
+    }
+
+    /// (Re)loads the game's stats and metadata from the database.
+    fn load(&mut self) -> Result<()> {
+        let database = self.res.get::<Database>();
+        let locale = self.res.get::<Locale>();
+        let console_mapper = self.res.get::<ConsoleMapper>();
+
+        let db_game = database.select_game(&self.game.path)?;
+        let metadata = database
+            .select_game_metadata(&self.game.path)?
+            .unwrap_or_default();
+        let last_played_at = database.get_last_played_at(&self.game.path)?;
+
+        let width = Some(self.list.bounding_box(&self.res.get()).w / 2);
+        let label = |text: String| -> Box<dyn View> {
+            Box::new(Label::new(Point::zero(), text, Alignment::Right, width))
+        };
+        let scrolling_label = |text: String| -> Box<dyn View> {
+            let mut label = Label::new(Point::zero(), text, Alignment::Right, width);
+            label.scroll(true);
+            Box::new(label)
+        };
+
+        let mut left = vec![
+            locale.t("game-details-launch"),
+            locale.t("game-details-core"),
+            locale.t("game-details-favorite"),
+            locale.t("game-details-reset-stats"),
+            locale.t("game-details-delete"),
+        ];
+        let mut right: Vec<Box<dyn View>> = vec![
+            label(String::new()),
+            if self.cores.is_empty() {
+                label("-".to_string())
+            } else {
+                let core = self
+                    .game
+                    .core
+                    .as_ref()
+                    .and_then(|core| self.cores.iter().position(|c| c == core))
+                    .unwrap_or_default();
+                Box::new(Select::new(
+                    Point::zero(),
+                    core,
+                    self.cores
+                        .iter()
+                        .map(|c| console_mapper.get_core_name(c))
+                        .collect(),
+                    Alignment::Right,
+                ))
+            },
+            Box::new(Toggle::new(
+                Point::zero(),
+                db_game.as_ref().is_some_and(|g| g.favorite),
+                Alignment::Right,
+            )),
+            label(String::new()),
+            label(if self.confirm_delete {
+                locale.t("game-details-delete-confirm")
+            } else {
+                String::new()
+            }),
+        ];
+
+        let mut info = |key: &str, value: Box<dyn View>| {
+            left.push(locale.t(key));
+            right.push(value);
+        };
+
+        info("game-details-name", scrolling_label(self.game.name.clone()));
+        if let Some(description) = metadata.description {
+            info(
+                "game-details-description",
+                scrolling_label(description.replace('\n', " ")),
+            );
+        }
+        if let Some(genre) = metadata.genre {
+            info("game-details-genre", label(genre));
+        }
+        if let Some(developer) = metadata.developer {
+            info("game-details-developer", label(developer));
+        }
+        if let Some(publisher) = metadata.publisher {
+            info("game-details-publisher", label(publisher));
+        }
+        if let Some(release_date) = metadata
+            .release_date
+            .and_then(|d| NaiveDateTime::parse_from_str(&d, "%Y%m%dT%H%M%S").ok())
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .or_else(|| metadata.release_year.map(|y| y.to_string()))
+        {
+            info("game-details-release-date", label(release_date));
+        }
+        if let Some(players) = metadata.players {
+            info("game-details-players", label(players));
+        }
+        if let Some(rating) = metadata.rating {
+            info(
+                "game-details-rating",
+                label(format!("{:.1} / 5", rating * 5.0)),
+            );
+        }
+
+        let (play_count, play_time) = db_game
+            .as_ref()
+            .map(|g| (g.play_count, g.play_time))
+            .unwrap_or_default();
+        info("game-details-play-count", label(play_count.to_string()));
+        info(
+            "game-details-play-time",
+            label(locale.ta(
+                "game-details-play-time-value",
+                &HashMap::from([
+                    ("hours".to_string(), play_time.num_hours().into()),
+                    ("minutes".to_string(), (play_time.num_minutes() % 60).into()),
+                ]),
+            )),
+        );
+        info(
+            "game-details-last-played",
+            label(
+                last_played_at
+                    .and_then(|t| Local.timestamp_opt(t, 0).single())
+                    .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| locale.t("game-details-never-played")),
+            ),
+        );
+        if let Ok(file) = fs::metadata(&self.game.path) {
+            info("game-details-file-size", label(format_size(file.len())));
+        }
+        info(
+            "game-details-path",
+            scrolling_label(
+                self.game
+                    .path
+                    .strip_prefix(ALLIUM_GAMES_DIR.as_path())
+                    .unwrap_or(&self.game.path)
+                    .display()
+                    .to_string(),
+            ),
+        );
+
+        self.list.set_items(left, right);
+
+        Ok(())
+    }
+
+    fn set_confirm_delete(&mut self, confirm_delete: bool) {
+        if self.confirm_delete == confirm_delete {
+            return;
+        }
+        self.confirm_delete = confirm_delete;
+        let text = if confirm_delete {
+            self.res.get::<Locale>().t("game-details-delete-confirm")
+        } else {
+            String::new()
+        };
+        let width = Some(self.list.bounding_box(&self.res.get()).w / 2);
+        self.list.set_right(
+            DELETE,
+            Box::new(Label::new(Point::zero(), text, Alignment::Right, width)),
+        );
+    }
+
+    async fn select_action(
+        &mut self,
+        commands: Sender<Command>,
+        bubble: &mut VecDeque<Command>,
+    ) -> Result<()> {
+        match self.list.selected() {
+            LAUNCH => {
+                let command = self
+                    .res
+                    .get::<ConsoleMapper>()
+                    .launch_game(&self.res.get(), &mut self.game)?
+                    .map(|launch| launch.start(commands.clone(), &self.res.get()));
+                if let Some(cmd) = command {
+                    commands.send(cmd).await?;
+                }
+            }
+            RESET_STATS => {
+                self.res.get::<Database>().reset_game(&self.game.path)?;
+                let selected = self.list.selected();
+                self.load()?;
+                self.list.select(selected);
+            }
+            DELETE if self.confirm_delete => {
+                info!("deleting game: {}", self.game.path.display());
+                Trash::new().trash(
+                    &self.res.get(),
+                    &self.res.get(),
+                    std::slice::from_ref(&self.game.path),
+                )?;
+                bubble.push_back(Command::CloseView);
+            }
+            DELETE => self.set_confirm_delete(true),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl View for GameDetails {
+    fn draw(
+        &mut self,
+        display: &mut <DefaultPlatform as Platform>::Display,
+        styles: &Stylesheet,
+    ) -> Result<bool> {
+        let mut drawn = false;
+
+        if self.dirty {
+            display.load(self.rect)?;
+            self.image.set_should_draw();
+            self.list.set_should_draw();
+            self.button_hints.set_should_draw();
+            self.dirty = false;
+            drawn = true;
+        }
+
+        drawn |= self.image.should_draw() && self.image.draw(display, styles)?;
+        drawn |= self.list.should_draw() && self.list.draw(display, styles)?;
+        drawn |= self.button_hints.should_draw() && self.button_hints.draw(display, styles)?;
+
+        Ok(drawn)
+    }
+
+    fn should_draw(&self) -> bool {
+        self.dirty
+            || self.image.should_draw()
+            || self.list.should_draw()
+            || self.button_hints.should_draw()
+    }
+
+    fn set_should_draw(&mut self) {
+        self.dirty = true;
+    }
+
+    async fn handle_key_event(
+        &mut self,
+        event: KeyEvent,
+        commands: Sender<Command>,
+        bubble: &mut VecDeque<Command>,
+    ) -> Result<bool> {
+        if !matches!(event, KeyEvent::Pressed(Key::A) | KeyEvent::Released(_)) {
+            self.set_confirm_delete(false);
+        }
+
+        if self
+            .list
+            .handle_key_event(event, commands.clone(), bubble)
+            .await?
+        {
+            let mut changes = Vec::new();
+            bubble.retain(|cmd| match cmd {
+                Command::ValueChanged(i, val) => {
+                    changes.push((*i, val.clone()));
+                    false
+                }
+                _ => true,
+            });
+            for (i, val) in changes {
+                match i {
+                    CORE => {
+                        if let Some(core) = val
+                            .as_int()
+                            .and_then(|i| self.cores.get(i as usize).cloned())
+                        {
+                            self.res
+                                .get::<Database>()
+                                .set_core(&self.game.path, &core)?;
+                            self.game.core = Some(core);
+                        }
+                    }
+                    FAVORITE => {
+                        if let Some(favorite) = val.as_bool() {
+                            self.res
+                                .get::<Database>()
+                                .set_favorite(&self.game.path, favorite)?;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            return Ok(true);
+        }
+
+        match event {
+            KeyEvent::Pressed(Key::A) => {
+                self.select_action(commands, bubble).await?;
+                Ok(true)
+            }
+            KeyEvent::Pressed(Key::B) => {
+                bubble.push_back(Command::CloseView);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn children(&self) -> Vec<&dyn View> {
+        vec![&self.image, &self.list, &self.button_hints]
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn View> {
+        vec![&mut self.image, &mut self.list, &mut self.button_hints]
+    }
+
+    fn bounding_box(&mut self, _styles: &Stylesheet) -> Rect {
+        self.rect
+    }
+
+    fn set_position(&mut self, _point: Point) {
+        unimplemented!()
+    }
+}
+
+/// Formats a number of bytes as a human-readable size, e.g. "1.5 MB".
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
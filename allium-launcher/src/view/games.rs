@@ -1,12 +1,15 @@
+use std::cmp::Reverse;
 use std::collections::VecDeque;
+use std::path::Path;
 
 use anyhow::Result;
 use async_trait::async_trait;
-use chrono::Duration;
+use chrono::{Duration, Local};
 use common::command::Command;
 use common::constants::ALLIUM_GAMES_DIR;
 use common::database::Database;
 use common::geom::{Alignment, Point, Rect};
+use common::launcher::LauncherSettings;
 use common::locale::Locale;
 use common::platform::{DefaultPlatform, Key, KeyEvent, Platform};
 use common::resources::Resources;
@@ -16,8 +19,11 @@ use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::Sender;
 
+use crate::arcade::ArcadeSet;
 use crate::consoles::ConsoleMapper;
 use crate::entry::directory::Directory;
+use crate::entry::group::{self, Group};
+use crate::entry::variant;
 use crate::entry::{Entry, Sort};
 use crate::view::entry_list::{EntryList, EntryListState};
 
@@ -63,10 +69,7 @@ impl Games {
 
     pub fn load_or_new(rect: Rect, res: Resources, state: Option<GamesState>) -> Result<Self> {
         let list = if let Some(state) = state {
-            let selected = state.selected;
-            let mut list = EntryList::load(rect, res.clone(), state)?;
-            list.select(selected);
-            list
+            EntryList::load(rect, res.clone(), state)?
         } else {
             EntryList::new(
                 rect,
@@ -81,6 +84,14 @@ impl Games {
     pub fn save(&self) -> GamesState {
         self.list.save()
     }
+
+    /// Selects the game or directory at `path`, opening the directories on the way to it. See
+    /// [`EntryList::reveal`].
+    pub fn reveal(&mut self, path: &Path, open: bool) -> Result<bool> {
+        let found = self.list.reveal(path, open)?;
+        self.list.set_should_draw();
+        Ok(found)
+    }
 }
 
 #[async_trait(?Send)]
@@ -147,15 +158,30 @@ pub enum GamesSort {
     Alphabetical(Directory),
     LastPlayed(Directory),
     MostPlayed(Directory),
+    ReleaseYear(Directory),
+    Rating(Directory),
+    RecentlyAdded(Directory),
+    Console(Directory),
     Random(Directory),
 }
 
 impl GamesSort {
+    /// Whether the directory is listed as its consoles, with a summary of each.
+    fn console_view(&self, settings: &LauncherSettings) -> bool {
+        settings.console_view
+            && self.directory().path == *ALLIUM_GAMES_DIR
+            && !settings.is_flattened(self.path())
+    }
+
     pub fn directory(&self) -> &Directory {
         match self {
             GamesSort::Alphabetical(d) => d,
             GamesSort::LastPlayed(d) => d,
             GamesSort::MostPlayed(d) => d,
+            GamesSort::ReleaseYear(d) => d,
+            GamesSort::Rating(d) => d,
+            GamesSort::RecentlyAdded(d) => d,
+            GamesSort::Console(d) => d,
             GamesSort::Random(d) => d,
         }
     }
@@ -167,6 +193,10 @@ impl Sort for GamesSort {
             GamesSort::Alphabetical(_) => locale.t("sort-alphabetical"),
             GamesSort::LastPlayed(_) => locale.t("sort-last-played"),
             GamesSort::MostPlayed(_) => locale.t("sort-most-played"),
+            GamesSort::ReleaseYear(_) => locale.t("sort-release-year"),
+            GamesSort::Rating(_) => locale.t("sort-rating"),
+            GamesSort::RecentlyAdded(_) => locale.t("sort-recently-added"),
+            GamesSort::Console(_) => locale.t("sort-console"),
             GamesSort::Random(_) => locale.t("sort-random"),
         }
     }
@@ -175,7 +205,11 @@ impl Sort for GamesSort {
         match self {
             GamesSort::Alphabetical(d) => GamesSort::LastPlayed(d.clone()),
             GamesSort::LastPlayed(d) => GamesSort::MostPlayed(d.clone()),
-            GamesSort::MostPlayed(d) => GamesSort::Random(d.clone()),
+            GamesSort::MostPlayed(d) => GamesSort::ReleaseYear(d.clone()),
+            GamesSort::ReleaseYear(d) => GamesSort::Rating(d.clone()),
+            GamesSort::Rating(d) => GamesSort::RecentlyAdded(d.clone()),
+            GamesSort::RecentlyAdded(d) => GamesSort::Console(d.clone()),
+            GamesSort::Console(d) => GamesSort::Random(d.clone()),
             GamesSort::Random(d) => GamesSort::Alphabetical(d.clone()),
         }
     }
@@ -185,82 +219,258 @@ impl Sort for GamesSort {
             GamesSort::Alphabetical(_) => GamesSort::Alphabetical(directory),
             GamesSort::LastPlayed(_) => GamesSort::LastPlayed(directory),
             GamesSort::MostPlayed(_) => GamesSort::MostPlayed(directory),
+            GamesSort::ReleaseYear(_) => GamesSort::ReleaseYear(directory),
+            GamesSort::Rating(_) => GamesSort::Rating(directory),
+            GamesSort::RecentlyAdded(_) => GamesSort::RecentlyAdded(directory),
+            GamesSort::Console(_) => GamesSort::Console(directory),
             GamesSort::Random(_) => GamesSort::Random(directory),
         }
     }
 
+    fn path(&self) -> Option<&Path> {
+        Some(&self.directory().path)
+    }
+
+    fn is_console_view(&self, settings: &LauncherSettings) -> bool {
+        self.console_view(settings)
+    }
+
+    fn summaries(
+        &self,
+        entries: &[Entry],
+        database: &Database,
+        locale: &Locale,
+        settings: &LauncherSettings,
+    ) -> Result<Option<Vec<String>>> {
+        if !self.console_view(settings) {
+            return Ok(None);
+        }
+
+        let now = Local::now();
+        entries
+            .iter()
+            .map(|entry| {
+                let Entry::Directory(dir) = entry else {
+                    return Ok(String::new());
+                };
+                let games = database.count_games_under_directory(&dir.path)?;
+                Ok(match database.last_played_under_directory(&dir.path)? {
+                    Some(last_played_at) => locale.ta(
+                        "console-summary-played",
+                        &[
+                            ("games".to_string(), games.into()),
+                            (
+                                "played".to_string(),
+                                Group::date(last_played_at, now).text(locale).into(),
+                            ),
+                        ]
+                        .into_iter()
+                        .collect(),
+                    ),
+                    None => locale.ta(
+                        "console-summary",
+                        &[("games".to_string(), games.into())].into_iter().collect(),
+                    ),
+                })
+            })
+            .collect::<Result<_>>()
+            .map(Some)
+    }
+
+    fn console(&self) -> Option<&str> {
+        self.directory().console.as_deref()
+    }
+
+    fn with_console(&self, console: Option<String>) -> Self {
+        self.with_directory(Directory {
+            console,
+            ..self.directory().clone()
+        })
+    }
+
+    fn groups(
+        &self,
+        entries: &[Entry],
+        database: &Database,
+        console_mapper: &ConsoleMapper,
+        settings: &LauncherSettings,
+    ) -> Result<Vec<Group>> {
+        if settings.is_flattened(self.path()) {
+            // Flattened listings are sectioned by console whatever the order
+            return group::by_console(entries, database, console_mapper);
+        }
+
+        match self {
+            GamesSort::Alphabetical(_) => Ok(group::by_letter(entries, settings.ignore_articles)),
+            GamesSort::LastPlayed(_) => group::by_last_played(entries, database),
+            GamesSort::MostPlayed(_) => group::by_play_time(entries, database),
+            GamesSort::ReleaseYear(_) => group::by_release_year(entries, database),
+            GamesSort::Rating(_) => group::by_rating(entries, database),
+            GamesSort::RecentlyAdded(_) => group::by_added(entries, database),
+            GamesSort::Console(_) => group::by_console(entries, database, console_mapper),
+            GamesSort::Random(_) => Ok(Vec::new()),
+        }
+    }
+
     fn entries(
         &self,
         database: &Database,
         console_mapper: &ConsoleMapper,
         locale: &Locale,
+        settings: &LauncherSettings,
     ) -> Result<Vec<Entry>> {
-        let mut entries = self.directory().entries(database, console_mapper, locale)?;
+        let flattened = settings.is_flattened(self.path());
+        let mut entries = if flattened {
+            self.directory()
+                .flattened_entries(database, console_mapper)?
+        } else {
+            self.directory().entries(database, console_mapper, locale)?
+        };
 
-        match self {
-            GamesSort::Alphabetical(_) => {
-                entries.sort_unstable();
-            }
-            GamesSort::LastPlayed(_) => {
-                // With this current implementation, apps will appear before games.
-                // TOOD: think about whether this is OK?
-                let mut games = Vec::with_capacity(entries.len());
-                let mut i = 0;
-                while i < entries.len() {
-                    if matches!(entries[i], Entry::Game(_)) {
-                        match entries.remove(i) {
-                            Entry::Game(game) => games.push(game),
-                            _ => unreachable!(),
-                        }
-                    } else {
-                        i += 1;
+        if settings.hide_arcade_clones {
+            entries.retain(|entry| match entry {
+                Entry::Game(game) => !console_mapper
+                    .arcade_set(database, &game.path)
+                    .is_some_and(ArcadeSet::is_clone_or_bios),
+                _ => true,
+            });
+        }
+
+        let console_view = self.console_view(settings);
+        if console_view {
+            // Consoles without games are left out, and the rest are named after their console
+            entries.retain(|entry| match entry {
+                Entry::Directory(dir) => database
+                    .count_games_under_directory(&dir.path)
+                    .is_ok_and(|count| count > 0),
+                _ => true,
+            });
+            for entry in entries.iter_mut() {
+                if let Entry::Directory(dir) = entry {
+                    if let Some(console) = console_mapper.get_console_by_dir(&dir.path) {
+                        dir.name.clone_from(&console.name);
+                        dir.full_name.clone_from(&console.name);
                     }
                 }
+            }
+        }
 
-                let db_games = database
-                    .select_games(&games.iter().map(|g| g.path.as_path()).collect::<Vec<_>>())?;
+        // Games with equal keys stay in alphabetical order
+        entries.sort_by(|a, b| a.cmp_by_name(b, settings.ignore_articles));
 
-                let mut games = games.into_iter().zip(db_games).collect::<Vec<_>>();
-                games.sort_unstable_by_key(|(_, db_game)| {
-                    db_game.as_ref().map(|g| -g.last_played).unwrap_or_default()
-                });
-                entries.retain(|e| matches!(e, Entry::Directory(_) | Entry::App(_)));
-                entries.sort_unstable();
-                entries.extend(games.into_iter().map(|(game, _)| Entry::Game(game)));
+        match self {
+            GamesSort::Alphabetical(_) => {}
+            GamesSort::LastPlayed(_) => {
+                // With this current implementation, apps will appear before games.
+                sort_games_by(&mut entries, |paths| {
+                    Ok(database
+                        .select_games(paths)?
+                        .into_iter()
+                        .map(|game| game.map(|g| -g.last_played).unwrap_or_default())
+                        .collect())
+                })?;
             }
             GamesSort::MostPlayed(_) => {
-                let mut games = Vec::with_capacity(entries.len());
-                let mut i = 0;
-                while i < entries.len() {
-                    if matches!(entries[i], Entry::Game(_)) {
-                        match entries.remove(i) {
-                            Entry::Game(game) => games.push(game),
-                            _ => unreachable!(),
-                        }
-                    } else {
-                        i += 1;
-                    }
-                }
-
-                let db_games = database
-                    .select_games(&games.iter().map(|g| g.path.as_path()).collect::<Vec<_>>())?;
-
-                let mut games = games.into_iter().zip(db_games).collect::<Vec<_>>();
-                games.sort_unstable_by_key(|(_, db_game)| {
-                    db_game
-                        .as_ref()
-                        .map(|g| -g.play_time)
-                        .unwrap_or(Duration::zero())
-                });
-                entries.retain(|e| matches!(e, Entry::Directory(_) | Entry::App(_)));
-                entries.sort_unstable();
-                entries.extend(games.into_iter().map(|(game, _)| Entry::Game(game)));
+                sort_games_by(&mut entries, |paths| {
+                    Ok(database
+                        .select_games(paths)?
+                        .into_iter()
+                        .map(|game| game.map(|g| -g.play_time).unwrap_or(Duration::zero()))
+                        .collect())
+                })?;
+            }
+            GamesSort::ReleaseYear(_) => {
+                // Games without a known year go last
+                sort_games_by(&mut entries, |paths| {
+                    Ok(database
+                        .select_games_metadata(paths)?
+                        .into_iter()
+                        .map(|m| m.and_then(|m| m.release_year).unwrap_or(i32::MAX))
+                        .collect())
+                })?;
+            }
+            GamesSort::Rating(_) => {
+                sort_games_by(&mut entries, |paths| {
+                    Ok(database
+                        .select_games_metadata(paths)?
+                        .into_iter()
+                        .map(|m| {
+                            let rating = m.and_then(|m| m.rating).unwrap_or(-1.0);
+                            Reverse((rating * 100.0) as i32)
+                        })
+                        .collect())
+                })?;
+            }
+            GamesSort::RecentlyAdded(_) => {
+                sort_games_by(&mut entries, |paths| {
+                    Ok(database
+                        .select_added_at(paths)?
+                        .into_iter()
+                        .map(|added_at| Reverse(added_at.unwrap_or_default()))
+                        .collect())
+                })?;
             }
+            GamesSort::Console(_) => sort_games_by_console(&mut entries, database, console_mapper)?,
             GamesSort::Random(_) => {
                 entries.shuffle(&mut rand::thread_rng());
             }
         }
 
+        if flattened {
+            // Keeps the games of each console together, in the chosen order within the console
+            sort_games_by_console(&mut entries, database, console_mapper)?;
+        }
+
+        if settings.collapse_variants {
+            entries = variant::collapse(entries, database, &settings.regions())?;
+        }
+
+        if console_view {
+            entries.sort_by_key(|entry| {
+                settings
+                    .pinned
+                    .iter()
+                    .position(|p| p == entry.path())
+                    .unwrap_or(usize::MAX)
+            });
+        }
+
         Ok(entries)
     }
 }
+
+/// Sorts the games by the name of their console. Games whose console isn't known go last.
+fn sort_games_by_console(
+    entries: &mut Vec<Entry>,
+    database: &Database,
+    console_mapper: &ConsoleMapper,
+) -> Result<()> {
+    sort_games_by(entries, |paths| {
+        Ok(paths
+            .iter()
+            .map(|path| {
+                let console = console_mapper.get_console(database, path);
+                (console.is_none(), console.map(|c| c.name.clone()))
+            })
+            .collect())
+    })
+}
+
+/// Sorts the games by key, keeping their current order for equal keys. Directories and apps stay
+/// before the games. `keys` is given the paths of the games and returns a key for each.
+fn sort_games_by<K: Ord>(
+    entries: &mut Vec<Entry>,
+    keys: impl FnOnce(&[&Path]) -> Result<Vec<K>>,
+) -> Result<()> {
+    let start = entries
+        .iter()
+        .position(|e| matches!(e, Entry::Game(_)))
+        .unwrap_or(entries.len());
+    let games: Vec<Entry> = entries.drain(start..).collect();
+    let keys = keys(&games.iter().map(Entry::path).collect::<Vec<_>>())?;
+
+    let mut games: Vec<(K, Entry)> = keys.into_iter().zip(games).collect();
+    games.sort_by(|(a, _), (b, _)| a.cmp(b));
+    entries.extend(games.into_iter().map(|(_, game)| game));
+    Ok(())
+}
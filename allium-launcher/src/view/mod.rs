@@ -1,13 +1,15 @@
 mod app;
 mod apps;
 mod entry_list;
+mod game_details;
 mod games;
 mod recents;
 mod settings;
 mod toast;
 
-pub use app::App;
+pub use app::{tab_rect, App};
 pub use apps::Apps;
+pub use entry_list::{grid_rect, image_rect};
 pub use games::Games;
 pub use recents::Recents;
 pub use settings::Settings;
@@ -7,6 +7,7 @@ use common::command::{Command, Value};
 use common::constants::RECENT_GAMES_LIMIT;
 use common::database::Database;
 use common::geom::{Alignment, Point, Rect};
+use common::launcher::LauncherSettings;
 use common::locale::Locale;
 use common::platform::{DefaultPlatform, Key, KeyEvent, Platform};
 use common::resources::Resources;
@@ -18,6 +19,7 @@ use tokio::sync::mpsc::Sender;
 use crate::consoles::ConsoleMapper;
 use crate::entry::directory::Directory;
 use crate::entry::game::Game;
+use crate::entry::group::{self, Group};
 use crate::entry::lazy_image::LazyImage;
 use crate::entry::{Entry, Sort};
 use crate::view::entry_list::{EntryList, EntryListState};
@@ -70,10 +72,7 @@ impl Recents {
 
     pub fn load_or_new(rect: Rect, res: Resources, state: Option<RecentsState>) -> Result<Self> {
         let list = if let Some(state) = state {
-            let selected = state.selected;
-            let mut list = EntryList::load(rect, res.clone(), state)?;
-            list.select(selected);
-            list
+            EntryList::load(rect, res.clone(), state)?
         } else {
             EntryList::new(rect, res.clone(), RecentsSort::LastPlayed)?
         };
@@ -244,11 +243,26 @@ impl Sort for RecentsSort {
         unimplemented!();
     }
 
+    fn groups(
+        &self,
+        entries: &[Entry],
+        database: &Database,
+        _console_mapper: &ConsoleMapper,
+        _settings: &LauncherSettings,
+    ) -> Result<Vec<Group>> {
+        match self {
+            RecentsSort::LastPlayed => group::by_last_played(entries, database),
+            RecentsSort::MostPlayed => group::by_play_time(entries, database),
+            RecentsSort::Random | RecentsSort::Search(_) => Ok(Vec::new()),
+        }
+    }
+
     fn entries(
         &self,
         database: &Database,
         _console_mapper: &ConsoleMapper,
         _locale: &Locale,
+        _settings: &LauncherSettings,
     ) -> Result<Vec<Entry>> {
         let games = match self {
             RecentsSort::LastPlayed => database.select_last_played(RECENT_GAMES_LIMIT),
@@ -277,7 +291,7 @@ impl Sort for RecentsSort {
 
                 let full_name = game.name.clone();
 
-                let image = LazyImage::from_path(&game.path, game.image);
+                let image = LazyImage::from_db(&game.path, game.image, game.image_searched);
 
                 Entry::Game(Game {
                     name: game.name,
@@ -286,6 +300,7 @@ impl Sort for RecentsSort {
                     image,
                     extension,
                     core: game.core,
+                    variants: Vec::new(),
                 })
             })
             .collect())
@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use common::command::Command;
+use common::constants::SELECTION_MARGIN;
+
+use common::geom::{Alignment, Point, Rect};
+use common::launcher::{LauncherSettings, Layout, DEFAULT_REGIONS};
+use common::locale::Locale;
+use common::platform::{DefaultPlatform, Key, KeyEvent, Platform};
+use common::resources::Resources;
+use common::stylesheet::Stylesheet;
+use common::view::{ButtonHint, ButtonIcon, Row, Select, SettingsList, Toggle, View};
+
+use tokio::sync::mpsc::Sender;
+
+use crate::view::settings::{ChildState, SettingsChild};
+
+pub struct Library {
+    rect: Rect,
+    settings: LauncherSettings,
+    list: SettingsList,
+    button_hints: Row<ButtonHint<String>>,
+}
+
+impl Library {
+    pub fn new(rect: Rect, res: Resources, state: Option<ChildState>) -> Self {
+        let Rect { x, y, w, h } = rect;
+
+        let settings = res.get::<LauncherSettings>().clone();
+
+        let locale = res.get::<Locale>();
+        let styles = res.get::<Stylesheet>();
+
+        let mut list = SettingsList::new(
+            Rect::new(
+                x + 12,
+                y + 8,
+                w - 24,
+                h - 8 - ButtonIcon::diameter(&styles) - 8,
+            ),
+            vec![
+                locale.t("settings-library-show-hidden"),
+                locale.t("settings-library-grid-view"),
+                locale.t("settings-library-ignore-articles"),
+                locale.t("settings-library-console-view"),
+                locale.t("settings-library-collapse-variants"),
+                locale.t("settings-library-preferred-region"),
+                locale.t("settings-library-hide-arcade-clones"),
+            ],
+            vec![
+                Box::new(Toggle::new(
+                    Point::zero(),
+                    settings.show_hidden,
+                    Alignment::Right,
+                )),
+                Box::new(Toggle::new(
+                    Point::zero(),
+                    settings.layout == Layout::Grid,
+                    Alignment::Right,
+                )),
+                Box::new(Toggle::new(
+                    Point::zero(),
+                    settings.ignore_articles,
+                    Alignment::Right,
+                )),
+                Box::new(Toggle::new(
+                    Point::zero(),
+                    settings.console_view,
+                    Alignment::Right,
+                )),
+                Box::new(Toggle::new(
+                    Point::zero(),
+                    settings.collapse_variants,
+                    Alignment::Right,
+                )),
+                Box::new(Select::new(
+                    Point::zero(),
+                    DEFAULT_REGIONS
+                        .iter()
+                        .position(|r| *r == settings.regions()[0])
+                        .unwrap_or_default(),
+                    DEFAULT_REGIONS.iter().map(|r| r.to_string()).collect(),
+                    Alignment::Right,
+                )),
+                Box::new(Toggle::new(
+                    Point::zero(),
+                    settings.hide_arcade_clones,
+                    Alignment::Right,
+                )),
+            ],
+            styles.ui_font.size + SELECTION_MARGIN,
+        );
+        if let Some(state) = state {
+            list.select(state.selected);
+        }
+
+        let button_hints = Row::new(
+            Point::new(
+                rect.x + rect.w as i32 - 12,
+                rect.y + rect.h as i32 - ButtonIcon::diameter(&styles) as i32 - 8,
+            ),
+            vec![
+                ButtonHint::new(
+                    Point::zero(),
+                    Key::A,
+                    locale.t("button-edit"),
+                    Alignment::Right,
+                ),
+                ButtonHint::new(
+                    Point::zero(),
+                    Key::B,
+                    locale.t("button-back"),
+                    Alignment::Right,
+                ),
+            ],
+            Alignment::Right,
+            12,
+        );
+
+        Self {
+            rect,
+            settings,
+            list,
+            button_hints,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl View for Library {
+    fn draw(
+        &mut self,
+        display: &mut <DefaultPlatform as Platform>::Display,
+        styles: &Stylesheet,
+    ) -> Result<bool> {
+        let mut drawn = false;
+
+        if self.list.should_draw() && self.list.draw(display, styles)? {
+            drawn = true;
+        }
+
+        if self.button_hints.should_draw() && self.button_hints.draw(display, styles)? {
+            drawn = true;
+        }
+
+        Ok(drawn)
+    }
+
+    fn should_draw(&self) -> bool {
+        self.list.should_draw() || self.button_hints.should_draw()
+    }
+
+    fn set_should_draw(&mut self) {
+        self.list.set_should_draw();
+        self.button_hints.set_should_draw();
+    }
+
+    async fn handle_key_event(
+        &mut self,
+        event: KeyEvent,
+        commands: Sender<Command>,
+        bubble: &mut VecDeque<Command>,
+    ) -> Result<bool> {
+        if self
+            .list
+            .handle_key_event(event, commands.clone(), bubble)
+            .await?
+        {
+            while let Some(command) = bubble.pop_front() {
+                if let Command::ValueChanged(i, val) = command {
+                    match i {
+                        0 => self.settings.show_hidden = val.as_bool().unwrap(),
+                        1 => {
+                            self.settings.layout = if val.as_bool().unwrap() {
+                                Layout::Grid
+                            } else {
+                                Layout::List
+                            }
+                        }
+                        2 => self.settings.ignore_articles = val.as_bool().unwrap(),
+                        3 => self.settings.console_view = val.as_bool().unwrap(),
+                        4 => self.settings.collapse_variants = val.as_bool().unwrap(),
+                        5 => self
+                            .settings
+                            .prefer_region(DEFAULT_REGIONS[val.as_int().unwrap() as usize]),
+                        6 => self.settings.hide_arcade_clones = val.as_bool().unwrap(),
+                        _ => unreachable!("Invalid index"),
+                    }
+
+                    commands
+                        .send(Command::SaveLauncherSettings(self.settings.clone()))
+                        .await?;
+                }
+            }
+            return Ok(true);
+        }
+
+        match event {
+            KeyEvent::Pressed(Key::B) => {
+                bubble.push_back(Command::CloseView);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn children(&self) -> Vec<&dyn View> {
+        vec![&self.list, &self.button_hints]
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn View> {
+        vec![&mut self.list, &mut self.button_hints]
+    }
+
+    fn bounding_box(&mut self, _styles: &Stylesheet) -> Rect {
+        self.rect
+    }
+
+    fn set_position(&mut self, _point: Point) {
+        unimplemented!()
+    }
+}
+
+impl SettingsChild for Library {
+    fn save(&self) -> ChildState {
+        ChildState {
+            selected: self.list.selected(),
+        }
+    }
+}
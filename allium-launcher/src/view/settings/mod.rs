@@ -2,6 +2,7 @@ mod about;
 mod clock;
 mod display;
 mod language;
+mod library;
 mod theme;
 mod wifi;
 
@@ -10,6 +11,7 @@ use crate::view::settings::clock::Clock;
 use self::about::About;
 use self::display::Display;
 use self::language::Language;
+use self::library::Library;
 use self::theme::Theme;
 use self::wifi::Wifi;
 
@@ -70,7 +72,7 @@ impl Settings {
         let styles = res.get::<Stylesheet>();
 
         let has_wifi = DefaultPlatform::has_wifi();
-        let mut labels = Vec::with_capacity(7);
+        let mut labels = Vec::with_capacity(8);
         if has_wifi {
             labels.push(locale.t("settings-wifi"));
         }
@@ -78,6 +80,7 @@ impl Settings {
         labels.push(locale.t("settings-display"));
         labels.push(locale.t("settings-theme"));
         labels.push(locale.t("settings-language"));
+        labels.push(locale.t("settings-library"));
         labels.push(locale.t("settings-about"));
 
         let mut list = ScrollList::new(
@@ -99,7 +102,8 @@ impl Settings {
                 2 => Some(Box::new(Display::new(rect, res.clone(), Some(child)))),
                 3 => Some(Box::new(Theme::new(rect, res.clone(), Some(child)))),
                 4 => Some(Box::new(Language::new(rect, res.clone(), Some(child)))),
-                5 => Some(Box::new(About::new(rect, res.clone(), Some(child)))),
+                5 => Some(Box::new(Library::new(rect, res.clone(), Some(child)))),
+                6 => Some(Box::new(About::new(rect, res.clone(), Some(child)))),
                 _ => None,
             }
         } else {
@@ -160,7 +164,8 @@ impl Settings {
             2 => self.child = Some(Box::new(Display::new(self.rect, self.res.clone(), None))),
             3 => self.child = Some(Box::new(Theme::new(self.rect, self.res.clone(), None))),
             4 => self.child = Some(Box::new(Language::new(self.rect, self.res.clone(), None))),
-            5 => self.child = Some(Box::new(About::new(self.rect, self.res.clone(), None))),
+            5 => self.child = Some(Box::new(Library::new(self.rect, self.res.clone(), None))),
+            6 => self.child = Some(Box::new(About::new(self.rect, self.res.clone(), None))),
             _ => unreachable!("Invalid index"),
         }
         self.dirty = true;
@@ -9,7 +9,7 @@ use common::geom::{Alignment, Point, Rect};
 use common::locale::Locale;
 use common::platform::{DefaultPlatform, Key, KeyEvent, Platform};
 use common::resources::Resources;
-use common::stylesheet::{Stylesheet, StylesheetFont};
+use common::stylesheet::{ArtType, Stylesheet, StylesheetFont};
 use common::view::{
     ButtonHint, ButtonIcon, ColorPicker, Number, Row, Select, SettingsList, Toggle, View,
 };
@@ -66,6 +66,7 @@ impl Theme {
                 locale.t("settings-theme-button-b-color"),
                 locale.t("settings-theme-button-x-color"),
                 locale.t("settings-theme-button-y-color"),
+                locale.t("settings-theme-art-type"),
             ],
             vec![
                 Box::new(Toggle::new(
@@ -145,6 +146,15 @@ impl Theme {
                     stylesheet.button_y_color,
                     Alignment::Right,
                 )),
+                Box::new(Select::new(
+                    Point::zero(),
+                    ArtType::ALL
+                        .iter()
+                        .position(|t| *t == stylesheet.art_type)
+                        .unwrap_or_default(),
+                    ArtType::ALL.iter().map(|t| locale.t(t.key())).collect(),
+                    Alignment::Right,
+                )),
             ],
             res.get::<Stylesheet>().ui_font.size + SELECTION_MARGIN,
         );
@@ -305,6 +315,9 @@ impl View for Theme {
                         10 => self.stylesheet.button_b_color = val.as_color().unwrap(),
                         11 => self.stylesheet.button_x_color = val.as_color().unwrap(),
                         12 => self.stylesheet.button_y_color = val.as_color().unwrap(),
+                        13 => {
+                            self.stylesheet.art_type = ArtType::ALL[val.as_int().unwrap() as usize]
+                        }
                         _ => unreachable!("Invalid index"),
                     }
 
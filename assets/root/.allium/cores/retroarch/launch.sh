@@ -1,3 +1,5 @@
 #!/bin/sh
 DIR=/mnt/SDCARD/RetroArch
-HOME=/mnt/SDCARD/RetroArch exec "$DIR/retroarch" -v -L "$DIR/.retroarch/cores/$1_libretro.so" "$2"
+CORE=$1
+shift
+HOME=/mnt/SDCARD/RetroArch exec "$DIR/retroarch" -v -L "$DIR/.retroarch/cores/${CORE}_libretro.so" "$@"
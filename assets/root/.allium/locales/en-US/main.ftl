@@ -8,17 +8,123 @@ sort-alphabetical = Sort: A-Z
 sort-last-played = Sort: Recent
 sort-most-played = Sort: Playtime
 sort-random = Sort: Random
+sort-release-year = Sort: Year
+sort-rating = Sort: Rating
+sort-recently-added = Sort: Added
+sort-console = Sort: Console
 sort-search = Search
 
+group-today = Today
+group-yesterday = Yesterday
+group-this-week = This Week
+group-never-played = Never Played
+group-under-an-hour = Under 1 Hour
+group-hours = { $hours }+ Hours
+group-unknown = Unknown
+
+art-type-boxart = Box Art
+art-type-title = Title Screen
+art-type-snap = Snapshot
+
 populating-database = Populating database...
     This may take several minutes.
     Go grab a coffee!
 populating-games = Populating games... ({ $directory })
+populating-metadata = Populating metadata...
 
 menu-launch = Launch
 menu-launch-with-core = Launch with { $core }
+menu-details = Details
+menu-hide = Hide
+menu-unhide = Unhide
 menu-remove-from-recents = Remove from Recents
 menu-repopulate-database = Repopulate Database
+menu-export-gamelists = Export gamelist.xml
+menu-grid-view = Show as Grid
+menu-list-view = Show as List
+menu-show-all-games = Show All Games
+menu-show-folders = Show Folders
+menu-console-all = Console: All
+menu-console = Console: { $console }
+menu-variant = Version: { $variant }
+menu-variant-played = Version: { $variant } ({ $hours }h { $minutes }m played)
+menu-pin = Pin to Top
+menu-unpin = Unpin
+menu-move-up = Move Up
+menu-move-down = Move Down
+menu-rename = Rename
+menu-move-to = Move to: { $folder }
+menu-move-confirm = Press A again to move { $count } games
+menu-delete = Delete
+menu-delete-confirm = Press A again to delete { $count } games
+menu-undo-delete = Undo Delete
+menu-select-multiple = Select Multiple
+menu-select-all = Select All
+menu-select-none = Select None
+menu-favorite = Add to Favorites
+menu-core = Core: { $core }
+menu-reset-stats = Reset Stats
+console-summary = { $games ->
+    [one] 1 game
+   *[other] { $games } games
+}
+console-summary-played = { $games ->
+    [one] 1 game
+   *[other] { $games } games
+}, last played: { $played }
+gamelists-exported = Exported gamelist.xml
+gamelists-export-failed = Failed to export gamelist.xml
+
+launch-preparing = Preparing game...
+launch-failed = Failed to launch game
+
+files-renamed = Renamed to { $name }
+files-moved = Moved to { $name }
+files-deleted = { $count ->
+    [one] Moved { $name } to the trash
+   *[other] Moved { $count } games to the trash
+}
+files-restored = { $count ->
+    [one] Restored { $name }
+   *[other] Restored { $count } games
+}
+files-failed = Failed to change files
+
+selection-count = { $count } selected
+selection-favorited = { $count ->
+    [one] Added 1 game to favorites
+   *[other] Added { $count } games to favorites
+}
+selection-core-set = { $count ->
+    [one] Set the core of 1 game
+   *[other] Set the core of { $count } games
+}
+selection-stats-reset = { $count ->
+    [one] Reset the stats of 1 game
+   *[other] Reset the stats of { $count } games
+}
+
+game-details-launch = Launch
+game-details-core = Core
+game-details-favorite = Favorite
+game-details-reset-stats = Reset Stats
+game-details-delete = Delete
+game-details-delete-confirm = Press A again to delete
+game-details-name = Name
+game-details-description = Description
+game-details-genre = Genre
+game-details-developer = Developer
+game-details-publisher = Publisher
+game-details-release-date = Release Date
+game-details-players = Players
+game-details-rating = Rating
+game-details-play-count = Play Count
+game-details-play-time = Play Time
+game-details-play-time-value = { $hours }h { $minutes }m
+game-details-last-played = Last Played
+game-details-never-played = Never
+game-details-file-size = File Size
+game-details-path = Path
 
 settings-wifi = Wi-Fi
 settings-wifi-wifi-enabled = Wi-Fi Enabled
@@ -58,10 +164,20 @@ settings-theme-button-a-color = Button A Color
 settings-theme-button-b-color = Button B Color
 settings-theme-button-x-color = Button X Color
 settings-theme-button-y-color = Button Y Color
+settings-theme-art-type = Game Art
 
 settings-language = Language
 settings-language-language = Language
 
+settings-library = Library
+settings-library-show-hidden = Show Hidden Items
+settings-library-grid-view = Grid View
+settings-library-ignore-articles = Ignore "The" When Sorting
+settings-library-console-view = Show Consoles With Games Only
+settings-library-collapse-variants = Combine Regions and Revisions
+settings-library-preferred-region = Preferred Region
+settings-library-hide-arcade-clones = Hide Arcade Clones and BIOS
+
 settings-files = Files
 
 settings-about = About
@@ -106,6 +222,8 @@ button-back = Back
 button-confirm = Confirm
 button-edit = Edit
 button-select = Select
+button-mark = Mark
+button-art = Art
 
 keyboard-button-backspace = Backspace
 keyboard-button-shift = Shift
@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 use crate::display::color::Color;
+use crate::launcher::LauncherSettings;
 use crate::locale::LocaleSettings;
 use crate::{display::settings::DisplaySettings, stylesheet::Stylesheet};
 
@@ -11,6 +12,7 @@ pub enum Command {
     SaveStylesheet(Box<Stylesheet>),
     SaveDisplaySettings(Box<DisplaySettings>),
     SaveLocaleSettings(LocaleSettings),
+    SaveLauncherSettings(LauncherSettings),
     CloseView,
     ValueChanged(usize, Value),
     TrapFocus,
@@ -29,9 +29,20 @@ lazy_static! {
     pub static ref ALLIUM_FONTS_DIR: PathBuf = ALLIUM_BASE_DIR.join("fonts");
     pub static ref ALLIUM_LOCALES_DIR: PathBuf = ALLIUM_BASE_DIR.join("locales");
     pub static ref ALLIUM_IMAGES_DIR: PathBuf = ALLIUM_BASE_DIR.join("images");
+    pub static ref ALLIUM_ICONS_DIR: PathBuf = ALLIUM_IMAGES_DIR.join("icons");
+    pub static ref ALLIUM_RDB_DIR: PathBuf = ALLIUM_SD_ROOT.join("RetroArch/.retroarch/database/rdb");
+    pub static ref ALLIUM_EXTRACT_CACHE_DIR: PathBuf = ALLIUM_BASE_DIR.join("cache/extracted");
+    pub static ref ALLIUM_THUMBNAILS_DIR: PathBuf = ALLIUM_BASE_DIR.join("cache/thumbnails");
+    pub static ref ALLIUM_SAVES_DIR: PathBuf = ALLIUM_SD_ROOT.join("Saves/CurrentProfile/saves");
+    pub static ref ALLIUM_STATES_DIR: PathBuf = ALLIUM_SD_ROOT.join("Saves/CurrentProfile/states");
+    pub static ref ALLIUM_TRASH_DIR: PathBuf = ALLIUM_SD_ROOT.join(".trash");
 
     // Config
     pub static ref ALLIUM_CONFIG_CONSOLES: PathBuf = ALLIUM_BASE_DIR.join("config/consoles.toml");
+    pub static ref ALLIUM_CONFIG_ARCADE: PathBuf = ALLIUM_BASE_DIR.join("config/arcade.toml");
+    pub static ref ALLIUM_CONFIG_ARCADE_USER: PathBuf =
+        ALLIUM_BASE_DIR.join("config/arcade.user.toml");
+    pub static ref ALLIUM_CONFIG_ARCADE_DATS: PathBuf = ALLIUM_BASE_DIR.join("config/arcade");
 
     // State
     pub static ref ALLIUMD_STATE: PathBuf = ALLIUM_BASE_DIR.join("state/alliumd.json");
@@ -43,6 +54,7 @@ lazy_static! {
     pub static ref ALLIUM_STYLESHEET: PathBuf = ALLIUM_BASE_DIR.join("state/stylesheet.json");
     pub static ref ALLIUM_DISPLAY_SETTINGS: PathBuf = ALLIUM_BASE_DIR.join("state/display.json");
     pub static ref ALLIUM_LOCALE_SETTINGS: PathBuf = ALLIUM_BASE_DIR.join("state/locale.json");
+    pub static ref ALLIUM_LAUNCHER_SETTINGS: PathBuf = ALLIUM_BASE_DIR.join("state/launcher.json");
     pub static ref ALLIUM_WIFI_SETTINGS: PathBuf = ALLIUM_BASE_DIR.join("state/wifi.json");
     pub static ref ALLIUM_TIMEZONE: PathBuf = ALLIUM_BASE_DIR.join("state/timezone");
 
@@ -60,6 +72,8 @@ lazy_static! {
 // Styles
 pub const IMAGE_WIDTH: u32 = 250;
 pub const SELECTION_MARGIN: u32 = 8;
+/// Number of tiles per row in the grid layout.
+pub const GRID_COLUMNS: usize = 4;
 
 /// After the battery level drops below this threshold, the device will shut down.
 pub const BATTERY_SHUTDOWN_THRESHOLD: i32 = 5;
@@ -73,12 +87,30 @@ pub const CLOCK_UPDATE_INTERVAL: Duration = Duration::from_secs(60);
 /// The number of items to jump when pressing left/right in a listing.
 pub const LISTING_JUMP_SIZE: i32 = 5;
 
+/// How long the group jumped to with L/R is shown for.
+pub const JUMP_INDICATOR_DURATION: Duration = Duration::from_millis(800);
+
+/// The number of entries ahead of the selection whose box art is loaded in advance.
+pub const IMAGE_PREFETCH_COUNT: usize = 3;
+
 /// If a key autorepeat is received after this duration, it will be ignored.
 pub const MAXIMUM_FRAME_TIME: Duration = Duration::from_millis(100);
 
 /// Maximum number of recent games to retrieve from the database.
 pub const RECENT_GAMES_LIMIT: i64 = 100;
 
+/// Games larger than this are not checksummed when looking up libretro database metadata.
+pub const RDB_MAX_CRC_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Maximum total size of games extracted from archives for cores that can't load them.
+pub const EXTRACT_CACHE_MAX_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Extracted games are evicted to keep at least this much space free on the SD card.
+pub const EXTRACT_CACHE_MIN_FREE_SPACE: u64 = 512 * 1024 * 1024;
+
+/// Maximum total size of cached box art thumbnails.
+pub const THUMBNAIL_CACHE_MAX_SIZE: u64 = 128 * 1024 * 1024;
+
 /// RetroArch network command interface.
 pub const RETROARCH_UDP_SOCKET: &str = "127.0.0.1:55355";
 
@@ -1,6 +1,9 @@
 use std::{
+    collections::HashSet,
+    fs,
     path::{Path, PathBuf},
     rc::Rc,
+    time::UNIX_EPOCH,
 };
 
 use anyhow::{Context, Result};
@@ -9,7 +12,8 @@ use log::{info, trace};
 use rusqlite::{params, Connection, OptionalExtension, Row};
 use rusqlite_migration::{Migrations, M};
 
-use crate::constants::{ALLIUM_BASE_DIR, ALLIUM_DATABASE};
+use crate::constants::{ALLIUM_BASE_DIR, ALLIUM_DATABASE, ALLIUM_TRASH_DIR};
+use crate::stylesheet::ArtType;
 
 #[derive(Debug, Clone, Default)]
 pub struct Database {
@@ -25,6 +29,48 @@ pub struct Game {
     pub play_time: Duration,
     pub last_played: i64,
     pub core: Option<String>,
+    pub favorite: bool,
+    /// Whether the image has been looked for. If so, `image` being None means there is none.
+    pub image_searched: bool,
+}
+
+/// Descriptive metadata about a game, e.g. from a libretro database or gamelist.xml.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameMetadata {
+    pub genre: Option<String>,
+    pub developer: Option<String>,
+    pub publisher: Option<String>,
+    pub release_year: Option<i32>,
+    pub players: Option<String>,
+    pub description: Option<String>,
+    /// Rating between 0 and 1.
+    pub rating: Option<f32>,
+    /// Release date in EmulationStation format, e.g. "19910101T000000".
+    pub release_date: Option<String>,
+    pub marquee: Option<PathBuf>,
+    pub video: Option<PathBuf>,
+}
+
+/// Play stats of a game imported from a gamelist.xml. Fields that are None are unknown.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GameStats {
+    pub favorite: Option<bool>,
+    pub play_count: Option<i64>,
+    /// Play time in seconds.
+    pub play_time: Option<i64>,
+    /// Unix timestamp of when the game was last played.
+    pub last_played_at: Option<i64>,
+}
+
+/// A file stored inside an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Path of the file inside the archive.
+    pub path: PathBuf,
+    /// CRC32 of the uncompressed file.
+    pub crc32: u32,
+    /// Uncompressed size of the file.
+    pub size: u64,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -106,28 +152,100 @@ CREATE TABLE IF NOT EXISTS directories (
     path TEXT NOT NULL UNIQUE,
     gamelist_fingerprint INTEGER
 )
+"),
+M::up("
+ALTER TABLE games ADD COLUMN genre TEXT;
+ALTER TABLE games ADD COLUMN developer TEXT;
+ALTER TABLE games ADD COLUMN publisher TEXT;
+ALTER TABLE games ADD COLUMN release_year INTEGER;
+ALTER TABLE games ADD COLUMN players TEXT;
+ALTER TABLE games ADD COLUMN description TEXT;
+"),
+M::up("
+ALTER TABLE games ADD COLUMN rating REAL;
+ALTER TABLE games ADD COLUMN release_date TEXT;
+ALTER TABLE games ADD COLUMN marquee TEXT;
+ALTER TABLE games ADD COLUMN video TEXT;
+"),
+M::up("
+ALTER TABLE games ADD COLUMN favorite INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE games ADD COLUMN last_played_at INTEGER;
+"),
+M::up("
+CREATE TABLE IF NOT EXISTS archives (
+    path TEXT PRIMARY KEY,
+    fingerprint INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS archive_entries (
+    archive TEXT NOT NULL,
+    path TEXT NOT NULL,
+    crc32 INTEGER NOT NULL,
+    size INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS archive_entries_archive ON archive_entries(archive);
+"),
+M::up("
+CREATE TABLE IF NOT EXISTS hidden (
+    path TEXT PRIMARY KEY
+);
+"),
+M::up("
+ALTER TABLE games ADD COLUMN image_searched INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE directories ADD COLUMN image TEXT;
+ALTER TABLE directories ADD COLUMN image_searched INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE directories ADD COLUMN mtime INTEGER;
+"),
+M::up("
+ALTER TABLE games ADD COLUMN added_at INTEGER;
+"),
+M::up("
+CREATE TABLE IF NOT EXISTS art (
+    path TEXT NOT NULL,
+    art_type TEXT NOT NULL,
+    image TEXT,
+    PRIMARY KEY (path, art_type)
+);
+"),
+M::up("
+CREATE TABLE IF NOT EXISTS crcs (
+    path TEXT PRIMARY KEY,
+    fingerprint INTEGER NOT NULL,
+    crc32 INTEGER NOT NULL
+);
+"),
+M::up("
+ALTER TABLE games ADD COLUMN renamed INTEGER NOT NULL DEFAULT 0;
 ")
         ])
     }
 
     pub fn reset_game(&self, path: &Path) -> Result<()> {
         self.conn.as_ref().unwrap().execute(
-            "UPDATE games SET play_count = 0, play_time = 0, last_played = 0 WHERE path = ?",
+            "UPDATE games SET play_count = 0, play_time = 0, last_played = 0, last_played_at = NULL WHERE path = ?",
             params![path.display().to_string()],
         )?;
         Ok(())
     }
 
+    /// Moves a game to a new path, keeping it hidden if it was.
     pub fn update_game_path(&self, old: &Path, new: &Path) -> Result<()> {
-        let mut stmt = self
-            .conn
-            .as_ref()
-            .unwrap()
-            .prepare("UPDATE games SET path = ? WHERE path = ?")?;
-        stmt.execute(params![
-            new.display().to_string(),
-            old.display().to_string()
-        ])?;
+        let tx = self.conn.as_ref().unwrap().unchecked_transaction()?; // safe because single-threaded
+
+        let (old, new) = (old.display().to_string(), new.display().to_string());
+        tx.execute("UPDATE games SET path = ? WHERE path = ?", [&new, &old])?;
+        tx.execute(
+            "UPDATE OR REPLACE hidden SET path = ? WHERE path = ?",
+            [&new, &old],
+        )?;
+        tx.execute(
+            "UPDATE OR REPLACE crcs SET path = ? WHERE path = ?",
+            [&new, &old],
+        )?;
+        // Art is found next to the game, so it's looked for again at the new path
+        tx.execute("DELETE FROM art WHERE path = ?", [&old])?;
+
+        tx.commit()?;
+
         Ok(())
     }
 
@@ -136,17 +254,21 @@ CREATE TABLE IF NOT EXISTS directories (
 
         let mut stmt = tx.prepare(
             "
-INSERT INTO games (name, path, image, play_count, play_time, last_played, core)
-VALUES (?, ?, ?, 0, 0, 0, ?)
-ON CONFLICT(path) DO UPDATE SET name = ?, image = ?, core = ?",
+INSERT INTO games (name, path, image, play_count, play_time, last_played, core, image_searched, added_at)
+VALUES (?1, ?2, ?3, 0, 0, 0, ?4, ?3 IS NOT NULL, COALESCE(?5, strftime('%s', 'now')))
+ON CONFLICT(path) DO UPDATE SET name = ?1, image = ?3, core = ?4, image_searched = ?3 IS NOT NULL OR (image_searched AND image IS NULL), added_at = COALESCE(added_at, ?5, strftime('%s', 'now'))",
         )?;
 
         for game in games {
             let path = game.path.display().to_string();
             let image = game.image.as_ref().map(|p| p.display().to_string());
-            stmt.execute(params![
-                game.name, path, image, game.core, game.name, image, game.core
-            ])?;
+            // The file's modification time is when it was copied onto the SD card
+            let added_at = fs::metadata(&game.path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64);
+            stmt.execute(params![game.name, path, image, game.core, added_at])?;
         }
 
         drop(stmt);
@@ -162,10 +284,10 @@ ON CONFLICT(path) DO UPDATE SET name = ?, image = ?, core = ?",
             .conn
             .as_ref()
             .unwrap()
-            .prepare("SELECT name, path, image, play_count, play_time, last_played, core FROM games WHERE last_played > 0 ORDER BY play_time DESC LIMIT ?")?;
+            .prepare("SELECT name, path, image, play_count, play_time, last_played, core, favorite, image_searched FROM games WHERE last_played > 0 AND path NOT LIKE ? ORDER BY play_time DESC LIMIT ?")?;
 
         let results = stmt
-            .query_map([limit], map_game)?
+            .query_map(params![trash_pattern(), limit], map_game)?
             .filter_map(|r| r.ok())
             .collect();
 
@@ -178,10 +300,10 @@ ON CONFLICT(path) DO UPDATE SET name = ?, image = ?, core = ?",
             .conn
             .as_ref()
             .unwrap()
-            .prepare("SELECT name, path, image, play_count, play_time, last_played, core FROM games WHERE last_played > 0 ORDER BY last_played DESC LIMIT ?")?;
+            .prepare("SELECT name, path, image, play_count, play_time, last_played, core, favorite, image_searched FROM games WHERE last_played > 0 AND path NOT LIKE ? ORDER BY last_played DESC LIMIT ?")?;
 
         let results = stmt
-            .query_map([limit], map_game)?
+            .query_map(params![trash_pattern(), limit], map_game)?
             .filter_map(|r| r.ok())
             .collect();
 
@@ -194,10 +316,10 @@ ON CONFLICT(path) DO UPDATE SET name = ?, image = ?, core = ?",
             .conn
             .as_ref()
             .unwrap()
-            .prepare("SELECT name, path, image, play_count, play_time, last_played, core FROM games WHERE id IN (SELECT id FROM games ORDER BY RANDOM() LIMIT ?)")?;
+            .prepare("SELECT name, path, image, play_count, play_time, last_played, core, favorite, image_searched FROM games WHERE id IN (SELECT id FROM games WHERE path NOT LIKE ? ORDER BY RANDOM() LIMIT ?)")?;
 
         let results = stmt
-            .query_map([limit], map_game)?
+            .query_map(params![trash_pattern(), limit], map_game)?
             .filter_map(|r| r.ok())
             .collect();
 
@@ -212,10 +334,13 @@ ON CONFLICT(path) DO UPDATE SET name = ?, image = ?, core = ?",
 
         let conn = self.conn.as_ref().unwrap();
 
-        let mut stmt = conn.prepare("SELECT games.name, games.path, image, play_count, play_time, last_played, core FROM games JOIN games_fts ON games.id = games_fts.rowid WHERE games_fts.name MATCH ? LIMIT ?")?;
+        let mut stmt = conn.prepare("SELECT games.name, games.path, image, play_count, play_time, last_played, core, favorite, image_searched FROM games JOIN games_fts ON games.id = games_fts.rowid WHERE games_fts.name MATCH ? AND games.path NOT LIKE ? LIMIT ?")?;
 
         let results = stmt
-            .query_map(params![format!("\"{}\" * ", query), limit], map_game)?
+            .query_map(
+                params![format!("\"{}\" * ", query), trash_pattern(), limit],
+                map_game,
+            )?
             .filter_map(|r| r.ok())
             .collect();
 
@@ -226,7 +351,7 @@ ON CONFLICT(path) DO UPDATE SET name = ?, image = ?, core = ?",
         trace!("select_games_in_directory({:?})", path);
         let conn = self.conn.as_ref().unwrap();
 
-        let mut stmt = conn.prepare("SELECT games.name, games.path, image, play_count, play_time, last_played, core FROM games JOIN games_fts ON games.id = games_fts.rowid WHERE games_fts.path LIKE ? AND games_fts.path NOT LIKE ?")?;
+        let mut stmt = conn.prepare("SELECT games.name, games.path, image, play_count, play_time, last_played, core, favorite, image_searched FROM games JOIN games_fts ON games.id = games_fts.rowid WHERE games_fts.path LIKE ? AND games_fts.path NOT LIKE ?")?;
 
         let results = stmt
             .query_map(
@@ -242,12 +367,49 @@ ON CONFLICT(path) DO UPDATE SET name = ?, image = ?, core = ?",
         Ok(results)
     }
 
+    /// Selects the games in a directory and all of its subdirectories.
+    pub fn select_games_under_directory(&self, path: &Path) -> Result<Vec<Game>> {
+        let mut stmt = self.conn.as_ref().unwrap().prepare(
+            "SELECT name, path, image, play_count, play_time, last_played, core, favorite, image_searched FROM games WHERE path LIKE ?",
+        )?;
+
+        let results = stmt
+            .query_map(params![format!("{}/%", path.display())], map_game)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(results)
+    }
+
+    /// Counts the games in a directory and all of its subdirectories.
+    pub fn count_games_under_directory(&self, path: &Path) -> Result<i64> {
+        let count = self.conn.as_ref().unwrap().query_row(
+            "SELECT COUNT(*) FROM games WHERE path LIKE ?",
+            params![format!("{}/%", path.display())],
+            |row| row.get(0),
+        )?;
+
+        Ok(count)
+    }
+
+    /// Returns the unix timestamp of when a game in a directory or its subdirectories was last
+    /// launched, if any has been.
+    pub fn last_played_under_directory(&self, path: &Path) -> Result<Option<i64>> {
+        let last_played_at = self.conn.as_ref().unwrap().query_row(
+            "SELECT MAX(last_played_at) FROM games WHERE path LIKE ?",
+            params![format!("{}/%", path.display())],
+            |row| row.get::<_, Option<i64>>(0),
+        )?;
+
+        Ok(last_played_at)
+    }
+
     pub fn select_game(&self, path: &Path) -> Result<Option<Game>> {
         let game = self
             .conn
             .as_ref()
             .unwrap()
-            .query_row("SELECT name, path, image, play_count, play_time, last_played, core FROM games WHERE path = ? LIMIT 1", [path.display().to_string()], map_game)
+            .query_row("SELECT name, path, image, play_count, play_time, last_played, core, favorite, image_searched FROM games WHERE path = ? LIMIT 1", [path.display().to_string()], map_game)
             .optional()?;
 
         Ok(game)
@@ -258,7 +420,7 @@ ON CONFLICT(path) DO UPDATE SET name = ?, image = ?, core = ?",
             .conn
             .as_ref()
             .unwrap()
-            .prepare("SELECT name, path, image, play_count, play_time, last_played, core FROM games WHERE path = ?")?;
+            .prepare("SELECT name, path, image, play_count, play_time, last_played, core, favorite, image_searched FROM games WHERE path = ?")?;
 
         let mut results = vec![None; paths.len()];
         for (i, path) in paths.iter().enumerate() {
@@ -272,9 +434,30 @@ ON CONFLICT(path) DO UPDATE SET name = ?, image = ?, core = ?",
         Ok(results)
     }
 
+    /// Returns the unix timestamps of when games were added, in the same order as `paths`.
+    pub fn select_added_at(&self, paths: &[&Path]) -> Result<Vec<Option<i64>>> {
+        let mut stmt = self
+            .conn
+            .as_ref()
+            .unwrap()
+            .prepare("SELECT added_at FROM games WHERE path = ?")?;
+
+        let mut results = vec![None; paths.len()];
+        for (i, path) in paths.iter().enumerate() {
+            results[i] = stmt
+                .query_row(params![path.display().to_string()], |row| {
+                    row.get::<_, Option<i64>>(0)
+                })
+                .optional()?
+                .flatten();
+        }
+
+        Ok(results)
+    }
+
     pub fn select_all_games(&self) -> Result<Vec<Game>> {
         let mut stmt = self.conn.as_ref().unwrap().prepare(
-            "SELECT name, path, image, play_count, play_time, last_played, core FROM games",
+            "SELECT name, path, image, play_count, play_time, last_played, core, favorite, image_searched FROM games",
         )?;
 
         let results = stmt
@@ -309,7 +492,7 @@ ON CONFLICT(path) DO UPDATE SET play_count = play_count + 1;",
         )?;
 
         self.conn.as_ref().unwrap().execute(
-            "UPDATE games SET last_played = (SELECT MAX(last_played) FROM games) + 1 WHERE path = ?",
+            "UPDATE games SET last_played = (SELECT MAX(last_played) FROM games) + 1, last_played_at = strftime('%s', 'now') WHERE path = ?",
         [path.display().to_string()])?;
 
         Ok(())
@@ -325,6 +508,57 @@ ON CONFLICT(path) DO UPDATE SET play_count = play_count + 1;",
         Ok(())
     }
 
+    /// Returns the unix timestamp of when a game was last launched, if it has been.
+    pub fn get_last_played_at(&self, path: &Path) -> Result<Option<i64>> {
+        let last_played_at = self
+            .conn
+            .as_ref()
+            .unwrap()
+            .query_row(
+                "SELECT last_played_at FROM games WHERE path = ?",
+                [path.display().to_string()],
+                |row| row.get::<_, Option<i64>>(0),
+            )
+            .optional()?
+            .flatten();
+
+        Ok(last_played_at)
+    }
+
+    pub fn set_favorite(&self, path: &Path, favorite: bool) -> Result<()> {
+        self.conn.as_ref().unwrap().execute(
+            "UPDATE games SET favorite = ? WHERE path = ?",
+            params![favorite, path.display().to_string()],
+        )?;
+
+        Ok(())
+    }
+
+    /// Hides or reveals a game or directory in listings.
+    pub fn set_hidden(&self, path: &Path, hidden: bool) -> Result<()> {
+        let conn = self.conn.as_ref().unwrap();
+        let path = path.display().to_string();
+        if hidden {
+            conn.execute("INSERT OR IGNORE INTO hidden (path) VALUES (?)", [path])?;
+        } else {
+            conn.execute("DELETE FROM hidden WHERE path = ?", [path])?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the paths of every hidden game and directory.
+    pub fn select_hidden(&self) -> Result<HashSet<PathBuf>> {
+        let conn = self.conn.as_ref().unwrap();
+
+        let mut stmt = conn.prepare("SELECT path FROM hidden")?;
+        let hidden = stmt
+            .query_map([], |row| Ok(PathBuf::from(row.get::<_, String>(0)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(hidden)
+    }
+
     pub fn get_guide_cursor(&self, path: &Path) -> Result<u64> {
         let cursor = self
             .conn
@@ -352,10 +586,12 @@ ON CONFLICT(path) DO UPDATE SET play_count = play_count + 1;",
 
     /// Deletes a game from the database.
     pub fn delete_game(&self, path: &Path) -> Result<()> {
-        self.conn.as_ref().unwrap().execute(
-            "DELETE FROM games WHERE path = ?",
-            [path.display().to_string()],
-        )?;
+        let conn = self.conn.as_ref().unwrap();
+        let path = path.display().to_string();
+        conn.execute("DELETE FROM games WHERE path = ?", [&path])?;
+        conn.execute("DELETE FROM hidden WHERE path = ?", [&path])?;
+        conn.execute("DELETE FROM art WHERE path = ?", [&path])?;
+        conn.execute("DELETE FROM crcs WHERE path = ?", [&path])?;
 
         Ok(())
     }
@@ -432,6 +668,313 @@ ON CONFLICT(path) DO UPDATE SET play_count = play_count + 1;",
         Ok(fingerprint)
     }
 
+    /// Updates the metadata of games. Fields that are None are left unchanged.
+    pub fn update_game_metadata(&self, games: &[(PathBuf, GameMetadata)]) -> Result<()> {
+        let tx = self.conn.as_ref().unwrap().unchecked_transaction()?; // safe because single-threaded
+
+        let mut stmt = tx.prepare(
+            "
+UPDATE games SET
+    genre = COALESCE(?, genre),
+    developer = COALESCE(?, developer),
+    publisher = COALESCE(?, publisher),
+    release_year = COALESCE(?, release_year),
+    players = COALESCE(?, players),
+    description = COALESCE(?, description),
+    rating = COALESCE(?, rating),
+    release_date = COALESCE(?, release_date),
+    marquee = COALESCE(?, marquee),
+    video = COALESCE(?, video)
+WHERE path = ?",
+        )?;
+
+        for (path, metadata) in games {
+            stmt.execute(params![
+                metadata.genre,
+                metadata.developer,
+                metadata.publisher,
+                metadata.release_year,
+                metadata.players,
+                metadata.description,
+                metadata.rating,
+                metadata.release_date,
+                metadata.marquee.as_ref().map(|p| p.display().to_string()),
+                metadata.video.as_ref().map(|p| p.display().to_string()),
+                path.display().to_string(),
+            ])?;
+        }
+
+        drop(stmt);
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Merges imported play stats into games. Counts and times only ever increase, so importing a
+    /// gamelist that Allium exported doesn't lose play time recorded since.
+    pub fn update_game_stats(&self, games: &[(PathBuf, GameStats)]) -> Result<()> {
+        let tx = self.conn.as_ref().unwrap().unchecked_transaction()?; // safe because single-threaded
+
+        let mut stmt = tx.prepare(
+            "
+UPDATE games SET
+    favorite = COALESCE(?1, favorite),
+    play_count = MAX(COALESCE(?2, 0), play_count),
+    play_time = MAX(COALESCE(?3, 0), play_time),
+    last_played_at = MAX(COALESCE(?4, last_played_at), COALESCE(last_played_at, ?4))
+WHERE path = ?5",
+        )?;
+
+        for (path, stats) in games {
+            stmt.execute(params![
+                stats.favorite,
+                stats.play_count,
+                stats.play_time,
+                stats.last_played_at,
+                path.display().to_string(),
+            ])?;
+        }
+
+        drop(stmt);
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    pub fn select_game_metadata(&self, path: &Path) -> Result<Option<GameMetadata>> {
+        let metadata = self
+            .conn
+            .as_ref()
+            .unwrap()
+            .query_row(
+                "SELECT genre, developer, publisher, release_year, players, description, rating, release_date, marquee, video FROM games WHERE path = ?",
+                [path.display().to_string()],
+                map_game_metadata,
+            )
+            .optional()?;
+
+        Ok(metadata)
+    }
+
+    /// Selects the metadata of many games at once, e.g. for sorting. Games that aren't in the
+    /// database have no metadata.
+    pub fn select_games_metadata(&self, paths: &[&Path]) -> Result<Vec<Option<GameMetadata>>> {
+        let mut stmt = self
+            .conn
+            .as_ref()
+            .unwrap()
+            .prepare("SELECT genre, developer, publisher, release_year, players, description, rating, release_date, marquee, video FROM games WHERE path = ?")?;
+
+        let mut results = vec![None; paths.len()];
+        for (i, path) in paths.iter().enumerate() {
+            results[i] = stmt
+                .query_row(params![path.display().to_string()], map_game_metadata)
+                .optional()?;
+        }
+
+        Ok(results)
+    }
+
+    /// Selects games that have no metadata at all.
+    pub fn select_games_without_metadata(&self) -> Result<Vec<Game>> {
+        let mut stmt = self.conn.as_ref().unwrap().prepare(
+            "SELECT name, path, image, play_count, play_time, last_played, core, favorite, image_searched FROM games WHERE genre IS NULL AND developer IS NULL AND publisher IS NULL AND release_year IS NULL AND players IS NULL AND description IS NULL",
+        )?;
+
+        let results = stmt
+            .query_map([], map_game)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(results)
+    }
+
+    /// Stores the results of looking for games' images, including games without one.
+    pub fn update_game_images(&self, images: &[(PathBuf, Option<PathBuf>)]) -> Result<()> {
+        let tx = self.conn.as_ref().unwrap().unchecked_transaction()?; // safe because single-threaded
+
+        let mut stmt =
+            tx.prepare("UPDATE games SET image = ?, image_searched = 1 WHERE path = ?")?;
+        for (path, image) in images {
+            stmt.execute(params![
+                image.as_ref().map(|p| p.display().to_string()),
+                path.display().to_string()
+            ])?;
+        }
+
+        drop(stmt);
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Stores the results of looking for directories' images, including directories without one.
+    pub fn update_directory_images(&self, images: &[(PathBuf, Option<PathBuf>)]) -> Result<()> {
+        let tx = self.conn.as_ref().unwrap().unchecked_transaction()?; // safe because single-threaded
+
+        let mut stmt = tx.prepare(
+            "INSERT INTO directories (path, image, image_searched) VALUES (?, ?, 1) ON CONFLICT(path) DO UPDATE SET image = ?, image_searched = 1",
+        )?;
+        for (path, image) in images {
+            let image = image.as_ref().map(|p| p.display().to_string());
+            stmt.execute(params![path.display().to_string(), image, image])?;
+        }
+
+        drop(stmt);
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Stores the results of looking for art other than box art, including games and directories
+    /// without any.
+    pub fn update_art(
+        &self,
+        art_type: ArtType,
+        images: &[(PathBuf, Option<PathBuf>)],
+    ) -> Result<()> {
+        let tx = self.conn.as_ref().unwrap().unchecked_transaction()?; // safe because single-threaded
+
+        let mut stmt =
+            tx.prepare("INSERT OR REPLACE INTO art (path, art_type, image) VALUES (?, ?, ?)")?;
+        for (path, image) in images {
+            stmt.execute(params![
+                path.display().to_string(),
+                art_type_name(art_type),
+                image.as_ref().map(|p| p.display().to_string()),
+            ])?;
+        }
+
+        drop(stmt);
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Returns the art of a type found for a game or directory. None if it hasn't been looked for,
+    /// Some(None) if there is none.
+    pub fn get_art(&self, path: &Path, art_type: ArtType) -> Result<Option<Option<PathBuf>>> {
+        let image = self
+            .conn
+            .as_ref()
+            .unwrap()
+            .query_row(
+                "SELECT image FROM art WHERE path = ? AND art_type = ?",
+                params![path.display().to_string(), art_type_name(art_type)],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?;
+
+        Ok(image.map(|image| image.map(PathBuf::from)))
+    }
+
+    /// Returns the image found for a directory. None if it hasn't been looked for,
+    /// Some(None) if the directory has no image.
+    pub fn get_directory_image(&self, path: &Path) -> Result<Option<Option<PathBuf>>> {
+        let image = self
+            .conn
+            .as_ref()
+            .unwrap()
+            .query_row(
+                "SELECT image FROM directories WHERE path = ? AND image_searched = 1",
+                [path.display().to_string()],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?;
+
+        Ok(image.map(|image| image.map(PathBuf::from)))
+    }
+
+    /// Forgets that games and directories directly inside `path` have no image, and the images
+    /// found for them that have since been deleted, so that they are looked for again.
+    pub fn clear_missing_images(&self, path: &Path) -> Result<()> {
+        let conn = self.conn.as_ref().unwrap();
+        let children = format!("{}/%", path.display());
+        let descendants = format!("{}/%/%", path.display());
+        // Images that were found but have since been deleted
+        for table in ["games", "directories"] {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT path, image FROM {} WHERE image IS NOT NULL AND path LIKE ? AND path NOT LIKE ?",
+                table
+            ))?;
+            let missing: Vec<String> = stmt
+                .query_map(params![children, descendants], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .filter_map(|row| row.ok())
+                .filter(|(_, image)| !Path::new(image).exists())
+                .map(|(path, _)| path)
+                .collect();
+            for path in missing {
+                conn.execute(
+                    &format!(
+                        "UPDATE {} SET image = NULL, image_searched = 0 WHERE path = ?",
+                        table
+                    ),
+                    [path],
+                )?;
+            }
+        }
+        let mut stmt = conn.prepare(
+            "SELECT rowid, image FROM art WHERE image IS NOT NULL AND path LIKE ? AND path NOT LIKE ?",
+        )?;
+        let missing: Vec<i64> = stmt
+            .query_map(params![children, descendants], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .filter_map(|row| row.ok())
+            .filter(|(_, image)| !Path::new(image).exists())
+            .map(|(id, _)| id)
+            .collect();
+        for id in missing {
+            conn.execute("DELETE FROM art WHERE rowid = ?", [id])?;
+        }
+        conn.execute(
+            "DELETE FROM art WHERE image IS NULL AND path LIKE ? AND path NOT LIKE ?",
+            params![children, descendants],
+        )?;
+        conn.execute(
+            "UPDATE games SET image_searched = 0 WHERE image IS NULL AND path LIKE ? AND path NOT LIKE ?",
+            params![children, descendants],
+        )?;
+        conn.execute(
+            "UPDATE directories SET image_searched = 0 WHERE image IS NULL AND path LIKE ? AND path NOT LIKE ?",
+            params![children, descendants],
+        )?;
+        Ok(())
+    }
+
+    /// Modification time of a directory when its images were last looked for.
+    pub fn get_directory_mtime(&self, path: &Path) -> Result<Option<i64>> {
+        let mtime = self
+            .conn
+            .as_ref()
+            .unwrap()
+            .query_row(
+                "SELECT mtime FROM directories WHERE path = ?",
+                [path.display().to_string()],
+                |row| row.get::<_, Option<i64>>(0),
+            )
+            .optional()?
+            .flatten();
+
+        Ok(mtime)
+    }
+
+    pub fn set_directory_mtime(&self, path: &Path, mtime: i64) -> Result<()> {
+        self.conn.as_ref().unwrap().execute(
+            "INSERT INTO directories (path, mtime) VALUES (?, ?) ON CONFLICT(path) DO UPDATE SET mtime = ?",
+            params![path.display().to_string(), mtime, mtime],
+        )?;
+
+        Ok(())
+    }
+
     pub fn get_core(&self, path: &Path) -> Result<Option<String>> {
         let core = self
             .conn
@@ -448,6 +991,33 @@ ON CONFLICT(path) DO UPDATE SET play_count = play_count + 1;",
         Ok(core)
     }
 
+    /// Renames a game, as the user did in Allium.
+    pub fn set_name(&self, path: &Path, name: &str) -> Result<()> {
+        self.conn.as_ref().unwrap().execute(
+            "UPDATE games SET name = ?, renamed = 1 WHERE path = ?",
+            params![name, path.display().to_string()],
+        )?;
+
+        Ok(())
+    }
+
+    /// Whether the user has renamed the game in Allium.
+    pub fn is_renamed(&self, path: &Path) -> Result<bool> {
+        let renamed = self
+            .conn
+            .as_ref()
+            .unwrap()
+            .query_row(
+                "SELECT renamed FROM games WHERE path = ?",
+                [path.display().to_string()],
+                |row| row.get(0),
+            )
+            .optional()?
+            .unwrap_or_default();
+
+        Ok(renamed)
+    }
+
     pub fn set_core(&self, path: &Path, core: &str) -> Result<()> {
         self.conn.as_ref().unwrap().execute(
             "UPDATE games SET core = ? WHERE path = ?",
@@ -456,6 +1026,123 @@ ON CONFLICT(path) DO UPDATE SET play_count = play_count + 1;",
 
         Ok(())
     }
+
+    /// Returns the cached listing of an archive, or None if it hasn't been cached or the archive
+    /// has changed since.
+    pub fn select_archive_entries(
+        &self,
+        path: &Path,
+        fingerprint: u64,
+    ) -> Result<Option<Vec<ArchiveEntry>>> {
+        let conn = self.conn.as_ref().unwrap();
+        let path = path.display().to_string();
+
+        let cached = conn
+            .query_row(
+                "SELECT fingerprint FROM archives WHERE path = ?",
+                [&path],
+                |row| row.get::<_, u64>(0),
+            )
+            .optional()?;
+        if cached != Some(fingerprint) {
+            return Ok(None);
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT path, crc32, size FROM archive_entries WHERE archive = ? ORDER BY rowid",
+        )?;
+        let entries = stmt
+            .query_map([&path], |row| {
+                Ok(ArchiveEntry {
+                    path: PathBuf::from(row.get::<_, String>(0)?),
+                    crc32: row.get(1)?,
+                    size: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(Some(entries))
+    }
+
+    /// Returns the cached CRC32 of a file, or None if it hasn't been cached or the file has
+    /// changed since.
+    pub fn select_crc(&self, path: &Path, fingerprint: u64) -> Result<Option<u32>> {
+        let crc = self
+            .conn
+            .as_ref()
+            .unwrap()
+            .query_row(
+                "SELECT crc32 FROM crcs WHERE path = ? AND fingerprint = ?",
+                params![path.display().to_string(), fingerprint],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(crc)
+    }
+
+    /// Caches the CRC32 of a file, so that it isn't read again while it's unchanged.
+    pub fn update_crc(&self, path: &Path, fingerprint: u64, crc32: u32) -> Result<()> {
+        self.conn.as_ref().unwrap().execute(
+            "INSERT INTO crcs (path, fingerprint, crc32) VALUES (?, ?, ?) ON CONFLICT(path) DO UPDATE SET fingerprint = ?, crc32 = ?",
+            params![
+                path.display().to_string(),
+                fingerprint,
+                crc32,
+                fingerprint,
+                crc32
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Caches the listing of an archive, replacing any previous listing.
+    pub fn update_archive_entries(
+        &self,
+        path: &Path,
+        fingerprint: u64,
+        entries: &[ArchiveEntry],
+    ) -> Result<()> {
+        let tx = self.conn.as_ref().unwrap().unchecked_transaction()?; // safe because single-threaded
+        let path = path.display().to_string();
+
+        tx.execute(
+            "INSERT INTO archives (path, fingerprint) VALUES (?, ?) ON CONFLICT(path) DO UPDATE SET fingerprint = ?",
+            params![path, fingerprint, fingerprint],
+        )?;
+        tx.execute("DELETE FROM archive_entries WHERE archive = ?", [&path])?;
+
+        let mut stmt = tx.prepare(
+            "INSERT INTO archive_entries (archive, path, crc32, size) VALUES (?, ?, ?, ?)",
+        )?;
+        for entry in entries {
+            stmt.execute(params![
+                path,
+                entry.path.display().to_string(),
+                entry.crc32,
+                entry.size
+            ])?;
+        }
+        drop(stmt);
+
+        tx.commit()?;
+
+        Ok(())
+    }
+}
+
+/// Pattern for `NOT LIKE` that leaves out games in the trash, which are kept in the database so
+/// that restoring them keeps their play history.
+fn trash_pattern() -> String {
+    format!("{}/%", ALLIUM_TRASH_DIR.display())
+}
+
+/// Name of an art type in the art table.
+fn art_type_name(art_type: ArtType) -> &'static str {
+    match art_type {
+        ArtType::Boxart => "boxart",
+        ArtType::Title => "title",
+        ArtType::Snap => "snap",
+    }
 }
 
 #[cfg(test)]
@@ -563,6 +1250,14 @@ mod tests {
         assert_eq!(last_played.len(), 2);
         assert_eq!(last_played[0].path, games[0].path);
         assert_eq!(last_played[1].path, games[1].path);
+
+        // Trashed games don't take up any of the limit
+        database
+            .update_game_path(&games[0].path, &ALLIUM_TRASH_DIR.join("1/Game One.rom"))
+            .unwrap();
+        let last_played = database.select_last_played(1).unwrap();
+        assert_eq!(last_played.len(), 1);
+        assert_eq!(last_played[0].path, games[1].path);
     }
 
     #[test]
@@ -699,6 +1394,29 @@ mod tests {
         assert_eq!(results.len(), 0);
     }
 
+    #[test]
+    fn test_set_name() -> Result<()> {
+        let db = Database::in_memory()?;
+        let path = Path::new("test_directory/Game.rom");
+        db.update_games(&[NewGame {
+            name: "Game".to_string(),
+            path: path.to_path_buf(),
+            image: None,
+            core: None,
+        }])?;
+        assert!(!db.is_renamed(path)?);
+
+        db.set_name(path, "Renamed")?;
+        assert!(db.is_renamed(path)?);
+        assert_eq!(
+            db.select_games(&[path])?[0].as_ref().unwrap().name,
+            "Renamed"
+        );
+        assert!(!db.is_renamed(Path::new("missing"))?);
+
+        Ok(())
+    }
+
     #[test]
     fn test_set_core() -> Result<()> {
         let db = Database::in_memory().unwrap();
@@ -730,6 +1448,352 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_favorite() -> Result<()> {
+        let db = Database::in_memory().unwrap();
+
+        let games = vec![NewGame {
+            name: "Game One".to_string(),
+            path: PathBuf::from("test_directory/Game One.rom"),
+            image: None,
+            core: None,
+        }];
+
+        db.update_games(&games).unwrap();
+        assert!(!db.select_game(&games[0].path)?.unwrap().favorite);
+
+        db.set_favorite(&games[0].path, true)?;
+        assert!(db.select_game(&games[0].path)?.unwrap().favorite);
+
+        assert_eq!(db.get_last_played_at(&games[0].path)?, None);
+        db.increment_play_count(&games[0].name, &games[0].path, None)?;
+        assert!(db.get_last_played_at(&games[0].path)?.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hidden() -> Result<()> {
+        let db = Database::in_memory()?;
+        let path = Path::new("test_directory/BIOS");
+
+        db.set_hidden(path, true)?;
+        db.set_hidden(path, true)?;
+        assert_eq!(db.select_hidden()?, HashSet::from([path.to_path_buf()]));
+
+        db.set_hidden(path, false)?;
+        assert!(db.select_hidden()?.is_empty());
+
+        // Hidden games stay hidden when they move, and are forgotten when deleted
+        let game = Path::new("test_directory/Game.gb");
+        let moved = Path::new("test_directory/Moved/Game.gb");
+        db.set_hidden(game, true)?;
+        db.update_game_path(game, moved)?;
+        assert_eq!(db.select_hidden()?, HashSet::from([moved.to_path_buf()]));
+        db.delete_game(moved)?;
+        assert!(db.select_hidden()?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_image_lookups() -> Result<()> {
+        let db = Database::in_memory()?;
+        let dir = Path::new("test_directory");
+        let game = dir.join("Game.rom");
+        let image =
+            std::env::temp_dir().join(format!("allium-test-image-{}.png", std::process::id()));
+        fs::write(&image, "png")?;
+        let subdir = dir.join("Subdirectory");
+        db.update_games(&[NewGame {
+            name: "Game".to_string(),
+            path: game.clone(),
+            image: None,
+            core: None,
+        }])?;
+
+        let select = |path: &Path| -> Result<(Option<PathBuf>, bool)> {
+            let game = db.select_game(path)?.unwrap();
+            Ok((game.image, game.image_searched))
+        };
+        assert_eq!(select(&game)?, (None, false));
+        assert_eq!(db.get_directory_image(&subdir)?, None);
+
+        // Missing images are remembered
+        db.update_game_images(&[(game.clone(), None)])?;
+        db.update_directory_images(&[(subdir.clone(), None)])?;
+        assert_eq!(select(&game)?, (None, true));
+        assert_eq!(db.get_directory_image(&subdir)?, Some(None));
+
+        // Until the directory changes
+        db.clear_missing_images(dir)?;
+        assert_eq!(select(&game)?, (None, false));
+        assert_eq!(db.get_directory_image(&subdir)?, None);
+
+        // Found images are kept
+        db.update_game_images(&[(game.clone(), Some(image.clone()))])?;
+        db.clear_missing_images(dir)?;
+        assert_eq!(select(&game)?, (Some(image.clone()), true));
+
+        // Unless they have been deleted
+        fs::remove_file(&image)?;
+        db.clear_missing_images(dir)?;
+        assert_eq!(select(&game)?, (None, false));
+
+        // Other art types are stored alongside
+        assert_eq!(db.get_art(&game, ArtType::Title)?, None);
+        db.update_art(ArtType::Title, &[(game.clone(), None)])?;
+        db.update_art(ArtType::Snap, &[(game.clone(), Some(image.clone()))])?;
+        assert_eq!(db.get_art(&game, ArtType::Title)?, Some(None));
+        assert_eq!(db.get_art(&game, ArtType::Snap)?, Some(Some(image.clone())));
+        db.clear_missing_images(dir)?;
+        assert_eq!(db.get_art(&game, ArtType::Title)?, None);
+        assert_eq!(db.get_art(&game, ArtType::Snap)?, None);
+
+        db.set_directory_mtime(dir, 42)?;
+        assert_eq!(db.get_directory_mtime(dir)?, Some(42));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_games_under_directory() -> Result<()> {
+        let db = Database::in_memory()?;
+        let game = |path: &str| NewGame {
+            name: path.to_string(),
+            path: PathBuf::from(path),
+            image: None,
+            core: None,
+        };
+        db.update_games(&[
+            game("Roms/SNES/Game.sfc"),
+            game("Roms/SNES/Hacks/Hack.sfc"),
+            game("Roms/SNES2/Other.sfc"),
+        ])?;
+
+        let mut paths: Vec<_> = db
+            .select_games_under_directory(Path::new("Roms/SNES"))?
+            .into_iter()
+            .map(|g| g.path)
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("Roms/SNES/Game.sfc"),
+                PathBuf::from("Roms/SNES/Hacks/Hack.sfc")
+            ]
+        );
+        assert_eq!(db.count_games_under_directory(Path::new("Roms"))?, 3);
+        assert_eq!(db.count_games_under_directory(Path::new("Roms/GBA"))?, 0);
+
+        assert_eq!(
+            db.last_played_under_directory(Path::new("Roms/SNES"))?,
+            None
+        );
+        db.increment_play_count("Hack", Path::new("Roms/SNES/Hacks/Hack.sfc"), None)?;
+        assert!(db
+            .last_played_under_directory(Path::new("Roms/SNES"))?
+            .is_some());
+        assert_eq!(
+            db.last_played_under_directory(Path::new("Roms/SNES2"))?,
+            None
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_added_at() -> Result<()> {
+        let db = Database::in_memory()?;
+        let file = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let mtime = fs::metadata(&file)?
+            .modified()?
+            .duration_since(UNIX_EPOCH)?
+            .as_secs() as i64;
+        let game = NewGame {
+            name: "Game".to_string(),
+            path: file.clone(),
+            image: None,
+            core: None,
+        };
+        // Updating the game again keeps when it was first added
+        db.update_games(std::slice::from_ref(&game))?;
+        db.update_games(&[game])?;
+
+        assert_eq!(
+            db.select_added_at(&[&file, Path::new("missing")])?,
+            vec![Some(mtime), None]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_archive_entries() -> Result<()> {
+        let database = Database::in_memory()?;
+        let path = Path::new("test_directory/Game.zip");
+        let entries = vec![
+            ArchiveEntry {
+                path: PathBuf::from("Game.cue"),
+                crc32: 0x12345678,
+                size: 100,
+            },
+            ArchiveEntry {
+                path: PathBuf::from("Game.bin"),
+                crc32: 0xffffffff,
+                size: 1_000_000,
+            },
+        ];
+
+        assert_eq!(database.select_archive_entries(path, 1)?, None);
+
+        database.update_archive_entries(path, 1, &entries)?;
+        assert_eq!(
+            database.select_archive_entries(path, 1)?,
+            Some(entries.clone())
+        );
+        assert_eq!(database.select_archive_entries(path, 2)?, None);
+
+        database.update_archive_entries(path, 2, &entries[..1])?;
+        assert_eq!(
+            database.select_archive_entries(path, 2)?,
+            Some(entries[..1].to_vec())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_crcs() -> Result<()> {
+        let database = Database::in_memory()?;
+        let path = Path::new("test_directory/Game.gb");
+
+        assert_eq!(database.select_crc(path, 1)?, None);
+        database.update_crc(path, 1, 0xcbf43926)?;
+        assert_eq!(database.select_crc(path, 1)?, Some(0xcbf43926));
+        assert_eq!(database.select_crc(path, 2)?, None);
+
+        database.update_crc(path, 2, 0x12345678)?;
+        assert_eq!(database.select_crc(path, 2)?, Some(0x12345678));
+
+        database.delete_game(path)?;
+        assert_eq!(database.select_crc(path, 2)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_game_metadata() -> Result<()> {
+        let db = Database::in_memory().unwrap();
+
+        let games = vec![
+            NewGame {
+                name: "Game One".to_string(),
+                path: PathBuf::from("test_directory/Game One.rom"),
+                image: None,
+                core: None,
+            },
+            NewGame {
+                name: "Game Two".to_string(),
+                path: PathBuf::from("test_directory/Game Two.rom"),
+                image: None,
+                core: None,
+            },
+        ];
+
+        db.update_games(&games).unwrap();
+        assert_eq!(db.select_games_without_metadata()?.len(), 2);
+
+        let metadata = GameMetadata {
+            genre: Some("Platform".to_string()),
+            developer: Some("Developer".to_string()),
+            release_year: Some(1991),
+            ..Default::default()
+        };
+        db.update_game_metadata(&[(games[0].path.clone(), metadata.clone())])?;
+        assert_eq!(db.select_game_metadata(&games[0].path)?, Some(metadata));
+
+        // Missing fields don't overwrite existing ones
+        db.update_game_metadata(&[(
+            games[0].path.clone(),
+            GameMetadata {
+                players: Some("2".to_string()),
+                ..Default::default()
+            },
+        )])?;
+        let metadata = db.select_game_metadata(&games[0].path)?.unwrap();
+        assert_eq!(metadata.genre.as_deref(), Some("Platform"));
+        assert_eq!(metadata.players.as_deref(), Some("2"));
+
+        let without = db.select_games_without_metadata()?;
+        assert_eq!(without.len(), 1);
+        assert_eq!(without[0].path, games[1].path);
+
+        db.update_game_metadata(&[(
+            games[1].path.clone(),
+            GameMetadata {
+                rating: Some(0.8),
+                release_date: Some("19960227T000000".to_string()),
+                video: Some(PathBuf::from("test_directory/videos/Game Two.mp4")),
+                ..Default::default()
+            },
+        )])?;
+        let metadata = db.select_games_metadata(&[&games[0].path, &games[1].path])?;
+        assert_eq!(metadata[0].as_ref().unwrap().release_year, Some(1991));
+        assert_eq!(metadata[1].as_ref().unwrap().rating, Some(0.8));
+        assert_eq!(
+            metadata[1].as_ref().unwrap().release_date.as_deref(),
+            Some("19960227T000000")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_game_stats() -> Result<()> {
+        let db = Database::in_memory().unwrap();
+
+        let games = vec![NewGame {
+            name: "Game One".to_string(),
+            path: PathBuf::from("test_directory/Game One.rom"),
+            image: None,
+            core: None,
+        }];
+        db.update_games(&games).unwrap();
+        db.add_play_time(&games[0].path, Duration::seconds(100))?;
+
+        db.update_game_stats(&[(
+            games[0].path.clone(),
+            GameStats {
+                favorite: Some(true),
+                play_count: Some(3),
+                play_time: Some(50),
+                last_played_at: Some(1000),
+            },
+        )])?;
+        let game = db.select_game(&games[0].path)?.unwrap();
+        assert!(game.favorite);
+        assert_eq!(game.play_count, 3);
+        assert_eq!(game.play_time, Duration::seconds(100));
+        assert_eq!(db.get_last_played_at(&games[0].path)?, Some(1000));
+
+        // Unknown stats are left unchanged
+        db.update_game_stats(&[(
+            games[0].path.clone(),
+            GameStats {
+                last_played_at: Some(500),
+                ..Default::default()
+            },
+        )])?;
+        let game = db.select_game(&games[0].path)?.unwrap();
+        assert!(game.favorite);
+        assert_eq!(game.play_count, 3);
+        assert_eq!(db.get_last_played_at(&games[0].path)?, Some(1000));
+
+        Ok(())
+    }
 }
 
 fn map_game(row: &Row<'_>) -> rusqlite::Result<Game> {
@@ -741,5 +1805,22 @@ fn map_game(row: &Row<'_>) -> rusqlite::Result<Game> {
         play_time: Duration::seconds(row.get(4)?),
         last_played: row.get(5)?,
         core: row.get(6)?,
+        favorite: row.get(7)?,
+        image_searched: row.get(8)?,
+    })
+}
+
+fn map_game_metadata(row: &Row<'_>) -> rusqlite::Result<GameMetadata> {
+    Ok(GameMetadata {
+        genre: row.get(0)?,
+        developer: row.get(1)?,
+        publisher: row.get(2)?,
+        release_year: row.get(3)?,
+        players: row.get(4)?,
+        description: row.get(5)?,
+        rating: row.get(6)?,
+        release_date: row.get(7)?,
+        marquee: row.get::<_, Option<String>>(8)?.map(PathBuf::from),
+        video: row.get::<_, Option<String>>(9)?.map(PathBuf::from),
     })
 }
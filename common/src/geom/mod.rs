@@ -34,7 +34,7 @@ impl From<Point> for embedded_graphics::prelude::Point {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Size {
     pub w: u32,
     pub h: u32,
@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::constants::ALLIUM_LAUNCHER_SETTINGS;
+
+/// How entries are laid out in the game list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Layout {
+    /// One entry per line, with the selected entry's art beside the list.
+    #[default]
+    List,
+    /// Tiles of art, with the selected entry's name beneath.
+    Grid,
+}
+
+impl Layout {
+    pub fn toggle(self) -> Self {
+        match self {
+            Layout::List => Layout::Grid,
+            Layout::Grid => Layout::List,
+        }
+    }
+}
+
+/// Regions in order of preference, unless the user chose otherwise.
+pub const DEFAULT_REGIONS: [&str; 4] = ["USA", "World", "Europe", "Japan"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LauncherSettings {
+    /// Whether games and directories hidden from the menu are listed anyway.
+    pub show_hidden: bool,
+    /// Whether a leading "The", "A" or "An" is ignored when sorting alphabetically.
+    pub ignore_articles: bool,
+    /// Layout of directories that haven't been given their own.
+    pub layout: Layout,
+    /// Layouts chosen for individual directories.
+    pub layouts: HashMap<PathBuf, Layout>,
+    /// Directories that list the games of all their subdirectories together.
+    pub flattened: HashSet<PathBuf>,
+    /// Whether the Games tab lists only consoles that have games, with their stats.
+    pub console_view: bool,
+    /// Consoles listed first in the console view, in this order.
+    pub pinned: Vec<PathBuf>,
+    /// Whether variants of a game for other regions or revisions are listed as one entry.
+    pub collapse_variants: bool,
+    /// Regions in order of preference, for choosing which variant of a game is listed.
+    pub regions: Vec<String>,
+    /// Whether clones and BIOS sets of arcade games are left out of listings.
+    pub hide_arcade_clones: bool,
+}
+
+impl LauncherSettings {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Layout of a directory, or of lists that aren't a directory.
+    pub fn layout(&self, directory: Option<&Path>) -> Layout {
+        directory
+            .and_then(|d| self.layouts.get(d))
+            .copied()
+            .unwrap_or(self.layout)
+    }
+
+    /// Sets the layout of a directory, or the default layout if there's no directory.
+    pub fn set_layout(&mut self, directory: Option<&Path>, layout: Layout) {
+        match directory {
+            Some(directory) => {
+                self.layouts.insert(directory.to_path_buf(), layout);
+            }
+            None => self.layout = layout,
+        }
+    }
+
+    /// Whether a directory lists the games of all its subdirectories together.
+    pub fn is_flattened(&self, directory: Option<&Path>) -> bool {
+        directory.is_some_and(|d| self.flattened.contains(d))
+    }
+
+    pub fn set_flattened(&mut self, directory: &Path, flattened: bool) {
+        if flattened {
+            self.flattened.insert(directory.to_path_buf());
+        } else {
+            self.flattened.remove(directory);
+        }
+    }
+
+    pub fn is_pinned(&self, console: &Path) -> bool {
+        self.pinned.iter().any(|p| p == console)
+    }
+
+    /// Pins a console after the other pinned consoles, or unpins it.
+    pub fn set_pinned(&mut self, console: &Path, pinned: bool) {
+        self.pinned.retain(|p| p != console);
+        if pinned {
+            self.pinned.push(console.to_path_buf());
+        }
+    }
+
+    /// Moves the console at `index` of the listed `order` up or down one place. Every console up
+    /// to its new place is pinned, so that the order stays as arranged.
+    pub fn move_pinned(&mut self, order: &[PathBuf], index: usize, up: bool) {
+        let other = if up {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1).filter(|&i| i < order.len())
+        };
+        let Some(other) = other else {
+            return;
+        };
+
+        let mut order = order.to_vec();
+        order.swap(index, other);
+        order.truncate(index.max(other) + 1);
+        let pinned: Vec<PathBuf> = self
+            .pinned
+            .iter()
+            .filter(|p| !order.contains(p))
+            .cloned()
+            .collect();
+        self.pinned = order;
+        self.pinned.extend(pinned);
+    }
+
+    /// Regions in order of preference, starting with the chosen ones.
+    pub fn regions(&self) -> Vec<String> {
+        let mut regions = self.regions.clone();
+        for region in DEFAULT_REGIONS {
+            if !regions.iter().any(|r| r == region) {
+                regions.push(region.to_string());
+            }
+        }
+        regions
+    }
+
+    /// Makes a region the most preferred.
+    pub fn prefer_region(&mut self, region: &str) {
+        let mut regions = self.regions();
+        regions.retain(|r| r != region);
+        regions.insert(0, region.to_string());
+        self.regions = regions;
+    }
+
+    pub fn load() -> Result<Self> {
+        if ALLIUM_LAUNCHER_SETTINGS.exists() {
+            debug!("found state, loading from file");
+            let file = File::open(ALLIUM_LAUNCHER_SETTINGS.as_path())?;
+            if let Ok(json) = serde_json::from_reader(file) {
+                return Ok(json);
+            }
+            warn!("failed to read launcher settings file, removing");
+            fs::remove_file(ALLIUM_LAUNCHER_SETTINGS.as_path())?;
+        }
+        Ok(Self::new())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let file = File::create(ALLIUM_LAUNCHER_SETTINGS.as_path())?;
+        serde_json::to_writer(file, &self)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let mut settings = LauncherSettings::new();
+        let snes = Path::new("Roms/SNES");
+        assert_eq!(settings.layout(Some(snes)), Layout::List);
+
+        settings.set_layout(None, Layout::Grid);
+        assert_eq!(settings.layout(None), Layout::Grid);
+        assert_eq!(settings.layout(Some(snes)), Layout::Grid);
+
+        settings.set_layout(Some(snes), Layout::List);
+        assert_eq!(settings.layout(Some(snes)), Layout::List);
+        assert_eq!(settings.layout(Some(Path::new("Roms/GBA"))), Layout::Grid);
+    }
+
+    #[test]
+    fn test_pinned() {
+        let order: Vec<PathBuf> = ["Roms/GBA", "Roms/NES", "Roms/SNES"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let mut settings = LauncherSettings::new();
+
+        settings.set_pinned(&order[2], true);
+        assert!(settings.is_pinned(&order[2]));
+        settings.set_pinned(&order[2], false);
+        assert!(settings.pinned.is_empty());
+
+        settings.move_pinned(&order, 2, true);
+        assert_eq!(
+            settings.pinned,
+            ["Roms/GBA", "Roms/SNES", "Roms/NES"].map(PathBuf::from)
+        );
+
+        settings.move_pinned(&settings.pinned.clone(), 0, true);
+        assert_eq!(
+            settings.pinned,
+            ["Roms/GBA", "Roms/SNES", "Roms/NES"].map(PathBuf::from)
+        );
+
+        settings.set_pinned(&order[1], false);
+        settings.move_pinned(&order, 0, false);
+        assert_eq!(
+            settings.pinned,
+            ["Roms/NES", "Roms/GBA", "Roms/SNES"].map(PathBuf::from)
+        );
+    }
+}
@@ -8,10 +8,12 @@ pub mod database;
 pub mod display;
 pub mod game_info;
 pub mod geom;
+pub mod launcher;
 pub mod locale;
 pub mod platform;
 pub mod resources;
 pub mod retroarch;
 pub mod stylesheet;
+pub mod thumbnail;
 pub mod view;
 pub mod wifi;
@@ -119,9 +119,45 @@ impl StylesheetFont {
     }
 }
 
+/// The kind of art shown for games.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ArtType {
+    #[default]
+    Boxart,
+    Title,
+    Snap,
+}
+
+impl ArtType {
+    pub const ALL: [Self; 3] = [Self::Boxart, Self::Title, Self::Snap];
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Boxart => Self::Title,
+            Self::Title => Self::Snap,
+            Self::Snap => Self::Boxart,
+        }
+    }
+
+    /// Locale key of the art type's name.
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Boxart => "art-type-boxart",
+            Self::Title => "art-type-title",
+            Self::Snap => "art-type-snap",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Stylesheet {
     pub enable_box_art: bool,
+    #[serde(default)]
+    pub art_type: ArtType,
+    /// Folder of console and folder icons that comes with the theme. Icons that it doesn't have
+    /// are taken from the default icons folder.
+    #[serde(default)]
+    pub icons_dir: Option<PathBuf>,
     #[serde(default = "Stylesheet::default_foreground_color")]
     pub foreground_color: Color,
     #[serde(default = "Stylesheet::default_background_color")]
@@ -343,6 +379,8 @@ impl Default for Stylesheet {
     fn default() -> Self {
         Self {
             enable_box_art: true,
+            art_type: ArtType::default(),
+            icons_dir: None,
             foreground_color: Self::default_foreground_color(),
             background_color: Self::default_background_color(),
             highlight_color: Self::default_highlight_color(),
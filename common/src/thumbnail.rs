@@ -0,0 +1,304 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, RgbaImage};
+use log::{debug, info, warn};
+
+use crate::constants::{ALLIUM_THUMBNAILS_DIR, THUMBNAIL_CACHE_MAX_SIZE};
+use crate::geom::Size;
+use crate::view::ImageMode;
+
+/// Identifies a thumbnail file, so that a truncated or foreign file is never drawn.
+const MAGIC: &[u8; 4] = b"ATHB";
+/// Magic, width and height.
+const HEADER_SIZE: usize = 12;
+
+/// Images resized to the size they are drawn at, stored as raw RGBA so that loading them is a
+/// single read instead of decoding and scaling a full size PNG. Thumbnails are kept in a
+/// directory per size, so that changing the theme's layout doesn't draw stale sizes. Only sizes
+/// registered with `register_sizes`, i.e. the list and grid layouts' box art, are kept on disk.
+/// Images drawn at any other size are resized in memory only.
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    max_size: u64,
+}
+
+impl Default for ThumbnailCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ThumbnailCache {
+    pub fn new() -> Self {
+        Self {
+            dir: ALLIUM_THUMBNAILS_DIR.clone(),
+            max_size: THUMBNAIL_CACHE_MAX_SIZE,
+        }
+    }
+
+    /// Returns the image resized to fit `size`, generating it if needed. The thumbnail is cached
+    /// on disk if the size is registered.
+    pub fn get(&self, image: &Path, size: Size, mode: ImageMode) -> Option<RgbaImage> {
+        let path = self.path(image, size, mode);
+        if let Some(thumbnail) = path.as_deref().and_then(read) {
+            return Some(thumbnail);
+        }
+
+        let thumbnail = resize(open(image)?, size, mode);
+        if let Some(path) = path {
+            if let Err(e) = write(&path, &thumbnail) {
+                warn!("failed to write thumbnail {}: {}", path.display(), e);
+            }
+        }
+        Some(thumbnail)
+    }
+
+    /// Generates thumbnails of each registered size for images that don't have one yet. Meant to
+    /// be run in the background.
+    pub fn generate(
+        &self,
+        images: impl IntoIterator<Item = PathBuf>,
+        sizes: &[Size],
+        mode: ImageMode,
+    ) {
+        let mut generated = 0;
+        for image in images {
+            let paths: Vec<_> = sizes
+                .iter()
+                .filter_map(|&size| Some((self.path(&image, size, mode)?, size)))
+                .filter(|(path, _)| !path.exists())
+                .collect();
+            if paths.is_empty() {
+                continue;
+            }
+            let Some(source) = open(&image) else {
+                continue;
+            };
+            for (path, size) in paths {
+                if let Err(e) = write(&path, &resize(source.clone(), size, mode)) {
+                    warn!("failed to write thumbnail {}: {}", path.display(), e);
+                    continue;
+                }
+                generated += 1;
+            }
+        }
+        info!("generated {} thumbnails", generated);
+    }
+
+    /// Sets the sizes that are cached on disk, removing the thumbnails of every other size.
+    /// Called at startup and when the layout changes.
+    pub fn register_sizes(&self, sizes: &[Size]) -> Result<()> {
+        let keep: Vec<String> = sizes.iter().map(|&size| size_dir_name(size)).collect();
+        for name in &keep {
+            fs::create_dir_all(self.dir.join(name))?;
+        }
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if !entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| keep.iter().any(|k| k == name))
+            {
+                debug!("removing thumbnails in {}", entry.path().display());
+                if entry.file_type()?.is_dir() {
+                    fs::remove_dir_all(entry.path())?;
+                } else {
+                    fs::remove_file(entry.path())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Path of the thumbnail for an image, or None if the size isn't registered. The image's
+    /// modification time is part of the name, so editing an image misses the old thumbnail
+    /// instead of drawing it.
+    fn path(&self, image: &Path, size: Size, mode: ImageMode) -> Option<PathBuf> {
+        let dir = self.dir.join(size_dir_name(size));
+        if !dir.is_dir() {
+            return None;
+        }
+        let modified = fs::metadata(image).and_then(|m| m.modified()).ok()?;
+        let mut hasher = DefaultHasher::new();
+        image.hash(&mut hasher);
+        modified
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        mode.hash(&mut hasher);
+        Some(dir.join(format!("{:016x}.raw", hasher.finish())))
+    }
+
+    /// Removes the oldest thumbnails if the cache has grown too large. Thumbnails may be in use
+    /// by the image loader or being generated, so this is only run at startup, before anything
+    /// is drawn.
+    pub fn evict(&self) -> Result<()> {
+        let Ok(dirs) = fs::read_dir(&self.dir) else {
+            return Ok(());
+        };
+        let mut thumbnails = Vec::new();
+        for dir in dirs {
+            for entry in fs::read_dir(dir?.path())? {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+                thumbnails.push((
+                    entry.path(),
+                    metadata.len(),
+                    metadata.modified().unwrap_or(UNIX_EPOCH),
+                ));
+            }
+        }
+        for path in evictions(thumbnails, self.max_size) {
+            debug!("evicting thumbnail {}", path.display());
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Picks the oldest thumbnails to remove so that the rest fit in `max_size`.
+fn evictions(mut thumbnails: Vec<(PathBuf, u64, SystemTime)>, max_size: u64) -> Vec<PathBuf> {
+    thumbnails.sort_by_key(|(_, _, modified)| Reverse(*modified));
+    let mut total: u64 = thumbnails.iter().map(|(_, size, _)| size).sum();
+    let mut evicted = Vec::new();
+    while total > max_size {
+        let Some((path, size, _)) = thumbnails.pop() else {
+            break;
+        };
+        total -= size;
+        evicted.push(path);
+    }
+    evicted
+}
+
+fn size_dir_name(size: Size) -> String {
+    format!("{}x{}", size.w, size.h)
+}
+
+fn open(path: &Path) -> Option<DynamicImage> {
+    ::image::open(path)
+        .map_err(|e| warn!("failed to load image at {}: {}", path.display(), e))
+        .ok()
+}
+
+/// Scales an image according to the mode.
+fn resize(image: DynamicImage, size: Size, mode: ImageMode) -> RgbaImage {
+    let image = match mode {
+        ImageMode::Raw => image,
+        ImageMode::Cover => image.resize_to_fill(size.w, size.h, FilterType::Nearest),
+        ImageMode::Contain => {
+            let new_height = size.h.min(size.w * image.height() / image.width());
+            image.resize_to_fill(size.w, new_height, FilterType::Nearest)
+        }
+    };
+    image.to_rgba8()
+}
+
+fn read(path: &Path) -> Option<RgbaImage> {
+    let data = fs::read(path).ok()?;
+    decode(data)
+}
+
+fn decode(mut data: Vec<u8>) -> Option<RgbaImage> {
+    if data.len() < HEADER_SIZE || &data[..4] != MAGIC {
+        return None;
+    }
+    let width = u32::from_le_bytes(data[4..8].try_into().ok()?);
+    let height = u32::from_le_bytes(data[8..12].try_into().ok()?);
+    data.drain(..HEADER_SIZE);
+    RgbaImage::from_raw(width, height, data)
+}
+
+fn encode(image: &RgbaImage) -> Vec<u8> {
+    let mut data = Vec::with_capacity(HEADER_SIZE + image.as_raw().len());
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&image.width().to_le_bytes());
+    data.extend_from_slice(&image.height().to_le_bytes());
+    data.extend_from_slice(image.as_raw());
+    data
+}
+
+/// Writes to a temporary file first, so that a thumbnail being generated in the background is
+/// never read half written. The size's directory isn't created, so that a size that was
+/// unregistered in the meantime isn't cached again.
+fn write(path: &Path, image: &RgbaImage) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, encode(image))?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        let image = RgbaImage::from_fn(3, 2, |x, y| image::Rgba([x as u8, y as u8, 7, 255]));
+        assert_eq!(decode(encode(&image)), Some(image));
+        assert_eq!(decode(b"ATHB".to_vec()), None);
+        assert_eq!(decode(b"PNG\0\x01\0\0\0\x01\0\0\0\0\0\0\0".to_vec()), None);
+    }
+
+    #[test]
+    fn test_register_sizes() -> Result<()> {
+        let dir =
+            std::env::temp_dir().join(format!("allium-test-thumbnails-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        let image = dir.join("image.png");
+        RgbaImage::from_pixel(4, 4, image::Rgba([1, 2, 3, 255])).save(&image)?;
+
+        let cache = ThumbnailCache {
+            dir: dir.join("thumbnails"),
+            max_size: THUMBNAIL_CACHE_MAX_SIZE,
+        };
+        let list = Size::new(2, 2);
+        let grid = Size::new(3, 3);
+
+        // Unregistered sizes are only resized in memory
+        fs::create_dir_all(&cache.dir)?;
+        let thumbnail = cache.get(&image, list, ImageMode::Cover).unwrap();
+        assert_eq!(thumbnail.dimensions(), (2, 2));
+        assert_eq!(fs::read_dir(&cache.dir)?.count(), 0);
+
+        cache.register_sizes(&[list])?;
+        cache.get(&image, list, ImageMode::Cover).unwrap();
+        assert_eq!(fs::read_dir(cache.dir.join("2x2"))?.count(), 1);
+
+        cache.register_sizes(&[grid])?;
+        assert!(!cache.dir.join("2x2").exists());
+        cache.get(&image, list, ImageMode::Cover).unwrap();
+        assert!(!cache.dir.join("2x2").exists());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_evictions() {
+        let thumbnail = |name: &str, modified: u64| {
+            (
+                PathBuf::from(name),
+                10,
+                UNIX_EPOCH + Duration::from_secs(modified),
+            )
+        };
+        let thumbnails = vec![thumbnail("b", 2), thumbnail("a", 1), thumbnail("c", 3)];
+
+        assert!(evictions(thumbnails.clone(), 30).is_empty());
+        assert_eq!(
+            evictions(thumbnails, 15),
+            vec![PathBuf::from("a"), PathBuf::from("b")]
+        );
+    }
+}
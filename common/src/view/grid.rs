@@ -0,0 +1,328 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use embedded_graphics::prelude::Size;
+use embedded_graphics::primitives::{Primitive, PrimitiveStyle, Rectangle, RoundedRectangle};
+use embedded_graphics::Drawable;
+use tokio::sync::mpsc::Sender;
+
+use crate::display::Display;
+use crate::geom::{self, Alignment, Point, Rect};
+use crate::platform::{DefaultPlatform, Key, KeyEvent, Platform};
+use crate::stylesheet::{Stylesheet, StylesheetColor};
+use crate::view::{Command, Image, ImageMode, Label, View};
+
+/// Space between tiles.
+const GAP: u32 = 12;
+/// Space between a tile's edge and its image, where the selection border is drawn.
+const PADDING: u32 = 6;
+/// Width of the selection border.
+const BORDER_WIDTH: u32 = 4;
+
+/// Tiles of images laid out in rows, with the title of the selected tile shown beneath.
+#[derive(Debug, Clone)]
+pub struct Grid {
+    rect: Rect,
+    /// Titles of all entries.
+    items: Vec<String>,
+    columns: usize,
+    rows: usize,
+    tile_size: geom::Size,
+    label_height: u32,
+    /// Index of the first visible entry. Always the start of a row.
+    top: usize,
+    selected: usize,
+    /// Images of the visible entries.
+    tiles: Vec<Image>,
+    label: Label<String>,
+    dirty: bool,
+}
+
+impl Grid {
+    pub fn new(rect: Rect, columns: usize, label_height: u32) -> Self {
+        let tile_size = Self::tile_size(rect, columns);
+        let rows = ((rect.h.saturating_sub(label_height) + GAP) / (tile_size.h + GAP)).max(1);
+        let label = Label::new(
+            Point::new(
+                rect.x + rect.w as i32 / 2,
+                rect.y + (rows * (tile_size.h + GAP)) as i32,
+            ),
+            String::new(),
+            Alignment::Center,
+            Some(rect.w - 24),
+        );
+
+        Self {
+            rect,
+            items: Vec::new(),
+            columns,
+            rows: rows as usize,
+            tile_size,
+            label_height,
+            top: 0,
+            selected: 0,
+            tiles: Vec::new(),
+            label,
+            dirty: true,
+        }
+    }
+
+    /// Size of each tile in a grid of the given rect. Tiles are square.
+    pub fn tile_size(rect: Rect, columns: usize) -> geom::Size {
+        let columns = columns.max(1) as u32;
+        let w = rect.w.saturating_sub(GAP * (columns - 1)) / columns;
+        geom::Size::new(w, w)
+    }
+
+    /// Size that tile images are drawn at. Thumbnails are generated at this size.
+    pub fn image_size(rect: Rect, columns: usize) -> geom::Size {
+        let tile = Self::tile_size(rect, columns);
+        geom::Size::new(
+            tile.w.saturating_sub(PADDING * 2),
+            tile.h.saturating_sub(PADDING * 2),
+        )
+    }
+
+    pub fn set_items(&mut self, items: Vec<String>, preserve_selection: bool) {
+        let selected = if preserve_selection { self.selected } else { 0 };
+        self.items = items;
+        self.top = 0;
+        self.tiles.clear();
+        for i in 0..self.visible_range().len() {
+            let column = (i % self.columns) as u32;
+            let row = (i / self.columns) as u32;
+            let mut image = Image::empty(
+                Rect::new(
+                    self.rect.x + (column * (self.tile_size.w + GAP) + PADDING) as i32,
+                    self.rect.y + (row * (self.tile_size.h + GAP) + PADDING) as i32,
+                    self.tile_size.w - PADDING * 2,
+                    self.tile_size.h - PADDING * 2,
+                ),
+                ImageMode::Contain,
+            );
+            image.set_border_radius(8);
+            self.tiles.push(image);
+        }
+        self.select(selected.min(self.items.len().saturating_sub(1)));
+        self.dirty = true;
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if self.items.is_empty() {
+            self.label.set_text(String::new());
+            return;
+        }
+
+        let index = index.min(self.items.len() - 1);
+        let row = index / self.columns;
+        let top_row = self.top / self.columns;
+        let top_row = if row < top_row {
+            row
+        } else if row >= top_row + self.rows {
+            row + 1 - self.rows
+        } else {
+            top_row
+        };
+        if top_row * self.columns != self.top {
+            self.top = top_row * self.columns;
+            // Images are set again for the new page
+            for tile in self.tiles.iter_mut() {
+                tile.set_path(None);
+            }
+        }
+
+        self.label.scroll(false);
+        self.selected = index;
+        self.label.set_text(self.items[index].clone());
+        self.label.scroll(true);
+        self.dirty = true;
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Indices of the entries currently on screen.
+    pub fn visible_range(&self) -> Range<usize> {
+        let end = (self.top + self.columns * self.rows).min(self.items.len());
+        self.top..end
+    }
+
+    /// Sets the image of an entry, if it's on screen.
+    pub fn set_image(&mut self, index: usize, path: Option<PathBuf>) {
+        if self.visible_range().contains(&index) {
+            self.tiles[index - self.top].set_path(path);
+        }
+    }
+
+    fn tile_rect(&self, index: usize) -> Rect {
+        let i = index - self.top;
+        let column = (i % self.columns) as u32;
+        let row = (i / self.columns) as u32;
+        Rect::new(
+            self.rect.x + (column * (self.tile_size.w + GAP)) as i32,
+            self.rect.y + (row * (self.tile_size.h + GAP)) as i32,
+            self.tile_size.w,
+            self.tile_size.h,
+        )
+    }
+
+    fn label_rect(&self) -> Rect {
+        Rect::new(
+            self.rect.x,
+            self.rect.y + (self.rows as u32 * (self.tile_size.h + GAP)) as i32,
+            self.rect.w,
+            self.label_height,
+        )
+    }
+}
+
+#[async_trait(?Send)]
+impl View for Grid {
+    fn draw(
+        &mut self,
+        display: &mut <DefaultPlatform as Platform>::Display,
+        styles: &Stylesheet,
+    ) -> Result<bool> {
+        if self.dirty {
+            display.load(self.rect)?;
+
+            let visible = self.visible_range().len();
+            for tile in self.tiles.iter_mut().take(visible) {
+                tile.set_should_draw();
+                tile.draw(display, styles)?;
+                if tile.path().is_none() {
+                    // Entries without art still take up a tile
+                    let rect = tile.bounding_box(styles);
+                    RoundedRectangle::with_equal_corners(rect.into(), Size::new_equal(8))
+                        .into_styled(PrimitiveStyle::with_fill(
+                            StylesheetColor::BackgroundHighlightBlend.to_color(styles),
+                        ))
+                        .draw(display)?;
+                }
+            }
+
+            if self.visible_range().contains(&self.selected) {
+                let rect = self.tile_rect(self.selected);
+                RoundedRectangle::with_equal_corners(
+                    Rectangle::new(
+                        embedded_graphics::prelude::Point::new(
+                            rect.x + BORDER_WIDTH as i32 / 2,
+                            rect.y + BORDER_WIDTH as i32 / 2,
+                        ),
+                        Size::new(rect.w - BORDER_WIDTH, rect.h - BORDER_WIDTH),
+                    ),
+                    Size::new_equal(12),
+                )
+                .into_styled(PrimitiveStyle::with_stroke(
+                    styles.highlight_color,
+                    BORDER_WIDTH,
+                ))
+                .draw(display)?;
+            }
+
+            self.label.draw(display, styles)?;
+
+            self.dirty = false;
+            return Ok(true);
+        }
+
+        let mut drawn = false;
+        for tile in self.tiles.iter_mut() {
+            if tile.should_draw() && tile.draw(display, styles)? {
+                drawn = true;
+            }
+        }
+        if self.label.should_draw() {
+            display.load(self.label_rect())?;
+            drawn |= self.label.draw(display, styles)?;
+        }
+
+        Ok(drawn)
+    }
+
+    fn should_draw(&self) -> bool {
+        self.dirty || self.label.should_draw() || self.tiles.iter().any(|t| t.should_draw())
+    }
+
+    fn set_should_draw(&mut self) {
+        self.dirty = true;
+        self.label.set_should_draw();
+        for tile in &mut self.tiles {
+            tile.set_should_draw();
+        }
+    }
+
+    async fn handle_key_event(
+        &mut self,
+        event: KeyEvent,
+        _command: Sender<Command>,
+        _bubble: &mut VecDeque<Command>,
+    ) -> Result<bool> {
+        if self.items.is_empty() {
+            return Ok(false);
+        }
+
+        let len = self.items.len();
+        let column = self.selected % self.columns;
+        match event {
+            KeyEvent::Pressed(Key::Up) | KeyEvent::Autorepeat(Key::Up) => {
+                if self.selected >= self.columns {
+                    self.select(self.selected - self.columns);
+                } else {
+                    // Wrap around to the same column of the last row
+                    let last_row = (len - 1) / self.columns;
+                    self.select((last_row * self.columns + column).min(len - 1));
+                }
+                Ok(true)
+            }
+            KeyEvent::Pressed(Key::Down) | KeyEvent::Autorepeat(Key::Down) => {
+                if self.selected + self.columns < len {
+                    self.select(self.selected + self.columns);
+                } else if self.selected / self.columns < (len - 1) / self.columns {
+                    // The row below is partly empty
+                    self.select(len - 1);
+                } else {
+                    self.select(column);
+                }
+                Ok(true)
+            }
+            // At the edges of a row, left and right fall through to switch tabs
+            KeyEvent::Pressed(Key::Left) | KeyEvent::Autorepeat(Key::Left) if column > 0 => {
+                self.select(self.selected - 1);
+                Ok(true)
+            }
+            KeyEvent::Pressed(Key::Right) | KeyEvent::Autorepeat(Key::Right)
+                if column + 1 < self.columns && self.selected + 1 < len =>
+            {
+                self.select(self.selected + 1);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn children(&self) -> Vec<&dyn View> {
+        let mut children: Vec<&dyn View> = self.tiles.iter().map(|t| t as &dyn View).collect();
+        children.push(&self.label);
+        children
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn View> {
+        let mut children: Vec<&mut dyn View> =
+            self.tiles.iter_mut().map(|t| t as &mut dyn View).collect();
+        children.push(&mut self.label);
+        children
+    }
+
+    fn bounding_box(&mut self, _styles: &Stylesheet) -> Rect {
+        self.rect
+    }
+
+    fn set_position(&mut self, _point: Point) {
+        unimplemented!()
+    }
+}
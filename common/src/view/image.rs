@@ -4,9 +4,11 @@ use std::path::{Path, PathBuf};
 use anyhow::Result;
 use async_trait::async_trait;
 use embedded_graphics::image::ImageRaw;
+use embedded_graphics::prelude::Size;
+use embedded_graphics::primitives::{Primitive, PrimitiveStyle, Rectangle, RoundedRectangle};
 use embedded_graphics::Drawable;
-use image::{GenericImageView, RgbaImage};
-use log::{error, trace};
+use image::RgbaImage;
+use log::trace;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::Sender;
 
@@ -16,10 +18,11 @@ use crate::display::image::round;
 use crate::display::Display;
 use crate::geom::{Point, Rect};
 use crate::platform::{DefaultPlatform, KeyEvent, Platform};
-use crate::stylesheet::Stylesheet;
+use crate::stylesheet::{Stylesheet, StylesheetColor};
+use crate::view::image_loader::{self, Handle, Key};
 use crate::view::View;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ImageMode {
     /// Don't scale the image
     Raw,
@@ -29,12 +32,14 @@ pub enum ImageMode {
     Contain,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Image {
     rect: Rect,
     path: Option<PathBuf>,
     #[serde(skip)]
     image: Option<RgbaImage>,
+    #[serde(skip)]
+    loading: Option<Handle>,
     mode: ImageMode,
     border_radius: u32,
     dirty: bool,
@@ -46,6 +51,7 @@ impl Image {
             rect,
             path: Some(path),
             image: None,
+            loading: None,
             mode,
             border_radius: 0,
             dirty: true,
@@ -63,6 +69,7 @@ impl Image {
             rect,
             path: None,
             image: None,
+            loading: None,
             mode,
             border_radius: 0,
             dirty: true,
@@ -72,11 +79,52 @@ impl Image {
     pub fn set_path(&mut self, path: Option<PathBuf>) -> &mut Self {
         if path != self.path {
             self.image = None;
+            // Dropping the handle cancels the load if it hasn't started yet
+            self.loading = None;
             self.dirty = true;
             self.path = path;
         }
         self
     }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Loads an image that is likely to be shown next, so that it appears without delay.
+    pub fn prefetch(&self, path: PathBuf) {
+        image_loader::prefetch(self.key(path));
+    }
+
+    fn key(&self, path: PathBuf) -> Key {
+        Key {
+            path,
+            size: self.rect.size(),
+            mode: self.mode,
+        }
+    }
+
+    fn set_image(&mut self, mut image: RgbaImage) {
+        if self.border_radius != 0 {
+            round(&mut image, self.border_radius);
+        }
+        self.image = Some(image);
+    }
+}
+
+impl Clone for Image {
+    fn clone(&self) -> Self {
+        Self {
+            rect: self.rect,
+            path: self.path.clone(),
+            image: self.image.clone(),
+            // The clone starts its own load if it needs one
+            loading: None,
+            mode: self.mode,
+            border_radius: self.border_radius,
+            dirty: self.dirty,
+        }
+    }
 }
 
 #[async_trait(?Send)]
@@ -84,11 +132,20 @@ impl View for Image {
     fn draw(
         &mut self,
         display: &mut <DefaultPlatform as Platform>::Display,
-        _styles: &Stylesheet,
+        styles: &Stylesheet,
     ) -> Result<bool> {
-        if self.image.is_none() {
-            if let Some(ref path) = self.path {
-                self.image = image(path, self.rect, self.mode, self.border_radius);
+        if let Some(result) = self.loading.as_ref().and_then(Handle::take) {
+            self.loading = None;
+            if let Some(image) = result {
+                self.set_image(image);
+            }
+        } else if self.image.is_none() && self.loading.is_none() {
+            if let Some(path) = self.path.clone() {
+                let key = self.key(path);
+                match image_loader::cached(&key) {
+                    Some(image) => self.set_image(image),
+                    None => self.loading = Some(image_loader::load(key)),
+                }
             }
         }
 
@@ -98,6 +155,17 @@ impl View for Image {
             let image = embedded_graphics::image::Image::new(&image, self.rect.top_left().into());
             trace!("drawing image: {:?}", self.rect);
             image.draw(display)?;
+        } else if self.loading.is_some() {
+            // Placeholder until the image has loaded
+            let size = Size::new(self.rect.w, self.rect.h.min(self.rect.w));
+            RoundedRectangle::with_equal_corners(
+                Rectangle::new(self.rect.top_left().into(), size),
+                Size::new_equal(self.border_radius),
+            )
+            .into_styled(PrimitiveStyle::with_fill(
+                StylesheetColor::BackgroundHighlightBlend.to_color(styles),
+            ))
+            .draw(display)?;
         }
 
         self.dirty = false;
@@ -105,7 +173,7 @@ impl View for Image {
     }
 
     fn should_draw(&self) -> bool {
-        self.dirty
+        self.dirty || self.loading.as_ref().is_some_and(Handle::is_ready)
     }
 
     fn set_should_draw(&mut self) {
@@ -139,24 +207,3 @@ impl View for Image {
         self.dirty = true;
     }
 }
-
-fn image(path: &Path, rect: Rect, mode: ImageMode, border_radius: u32) -> Option<RgbaImage> {
-    let mut image = ::image::open(path)
-        .map_err(|e| error!("Failed to load image at {}: {}", path.display(), e))
-        .ok()?;
-    match mode {
-        ImageMode::Raw => {}
-        ImageMode::Cover => {
-            image = image.resize_to_fill(rect.w, rect.h, image::imageops::FilterType::Nearest);
-        }
-        ImageMode::Contain => {
-            let new_height = rect.h.min(rect.w * image.height() / image.width());
-            image = image.resize_to_fill(rect.w, new_height, image::imageops::FilterType::Nearest);
-        }
-    }
-    let mut image = image.to_rgba8();
-    if border_radius != 0 {
-        round(&mut image, border_radius);
-    }
-    Some(image)
-}
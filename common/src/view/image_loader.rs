@@ -0,0 +1,171 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use image::RgbaImage;
+use lazy_static::lazy_static;
+use log::{error, trace};
+
+use crate::geom::Size;
+use crate::thumbnail::ThumbnailCache;
+use crate::view::ImageMode;
+
+/// Number of decoded images kept in memory, so that scrolling back and forth and prefetched
+/// images don't hit the disk again.
+const MEMORY_CACHE_SIZE: usize = 8;
+
+lazy_static! {
+    static ref LOADER: Loader = Loader::spawn();
+}
+
+/// An image scaled to a size.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub path: PathBuf,
+    pub size: Size,
+    pub mode: ImageMode,
+}
+
+/// The result of a background load, shared with the worker. Loads whose handle has been
+/// dropped are skipped, so scrolling past an image cancels it.
+#[derive(Debug, Default)]
+pub struct Handle(Arc<Mutex<Option<Option<RgbaImage>>>>);
+
+impl Handle {
+    /// Whether the load has finished, successfully or not.
+    pub fn is_ready(&self) -> bool {
+        self.0.lock().unwrap().is_some()
+    }
+
+    /// Takes the loaded image. Returns `None` while still loading, and `Some(None)` if the image
+    /// couldn't be loaded.
+    pub fn take(&self) -> Option<Option<RgbaImage>> {
+        self.0.lock().unwrap().take()
+    }
+
+    fn is_cancelled(&self) -> bool {
+        Arc::strong_count(&self.0) == 1
+    }
+}
+
+enum Request {
+    Load(Key, Handle),
+    Prefetch(Key),
+}
+
+struct Loader {
+    sender: Mutex<Sender<Request>>,
+    cache: Arc<Mutex<VecDeque<(Key, RgbaImage)>>>,
+}
+
+impl Loader {
+    fn spawn() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cache = Arc::new(Mutex::new(VecDeque::with_capacity(MEMORY_CACHE_SIZE)));
+        {
+            let cache = Arc::clone(&cache);
+            thread::spawn(move || run(receiver, cache));
+        }
+        Self {
+            sender: Mutex::new(sender),
+            cache,
+        }
+    }
+
+    fn send(&self, request: Request) {
+        if self.sender.lock().unwrap().send(request).is_err() {
+            error!("image loader thread has stopped");
+        }
+    }
+}
+
+/// Returns the image if it has already been loaded into memory.
+pub fn cached(key: &Key) -> Option<RgbaImage> {
+    cached_in(&LOADER.cache, key)
+}
+
+/// Loads the image in the background.
+pub fn load(key: Key) -> Handle {
+    let handle = Handle::default();
+    LOADER.send(Request::Load(key, Handle(Arc::clone(&handle.0))));
+    handle
+}
+
+/// Loads the image into memory in the background, if nothing more urgent is waiting.
+pub fn prefetch(key: Key) {
+    LOADER.send(Request::Prefetch(key));
+}
+
+fn run(receiver: Receiver<Request>, cache: Arc<Mutex<VecDeque<(Key, RgbaImage)>>>) {
+    let mut queue = VecDeque::new();
+    loop {
+        if queue.is_empty() {
+            let Ok(request) = receiver.recv() else {
+                return;
+            };
+            queue.push_back(request);
+        }
+        queue.extend(receiver.try_iter());
+        let mut prefetches = queue
+            .iter()
+            .filter(|request| matches!(request, Request::Prefetch(_)))
+            .count();
+        queue.retain(|request| match request {
+            Request::Load(_, handle) => !handle.is_cancelled(),
+            // Older prefetches would be pushed out of the memory cache by newer ones anyway
+            Request::Prefetch(_) if prefetches > MEMORY_CACHE_SIZE => {
+                prefetches -= 1;
+                false
+            }
+            Request::Prefetch(_) => true,
+        });
+
+        // The newest load is what's on screen, and prefetches only matter once it's shown
+        let next = queue
+            .iter()
+            .rposition(|request| matches!(request, Request::Load(..)))
+            .unwrap_or(0);
+        let Some(request) = queue.remove(next) else {
+            continue;
+        };
+
+        let (key, handle) = match request {
+            Request::Load(key, handle) => (key, Some(handle)),
+            Request::Prefetch(key) => (key, None),
+        };
+        let image = cached_in(&cache, &key).or_else(|| {
+            trace!("loading image: {}", key.path.display());
+            let image = decode(&key)?;
+            let mut cache = cache.lock().unwrap();
+            if cache.len() >= MEMORY_CACHE_SIZE {
+                cache.pop_front();
+            }
+            cache.push_back((key, image.clone()));
+            Some(image)
+        });
+        if let Some(handle) = handle {
+            *handle.0.lock().unwrap() = Some(image);
+        }
+    }
+}
+
+fn cached_in(cache: &Mutex<VecDeque<(Key, RgbaImage)>>, key: &Key) -> Option<RgbaImage> {
+    cache
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, image)| image.clone())
+}
+
+fn decode(key: &Key) -> Option<RgbaImage> {
+    match key.mode {
+        ImageMode::Raw => ::image::open(&key.path)
+            .map_err(|e| error!("Failed to load image at {}: {}", key.path.display(), e))
+            .ok()
+            .map(|image| image.to_rgba8()),
+        _ => ThumbnailCache::new().get(&key.path, key.size, key.mode),
+    }
+}
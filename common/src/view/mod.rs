@@ -2,7 +2,9 @@ mod battery_indicator;
 mod button_hint;
 mod button_icon;
 mod clock;
+mod grid;
 mod image;
+mod image_loader;
 mod input;
 mod label;
 mod list;
@@ -19,6 +21,7 @@ pub use self::battery_indicator::BatteryIndicator;
 pub use self::button_hint::ButtonHint;
 pub use self::button_icon::ButtonIcon;
 pub use self::clock::Clock;
+pub use self::grid::Grid;
 pub use self::image::{Image, ImageMode};
 pub use self::input::button::Button;
 pub use self::input::color_picker::ColorPicker;
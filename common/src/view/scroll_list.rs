@@ -1,4 +1,5 @@
 use std::collections::VecDeque;
+use std::path::PathBuf;
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -10,11 +11,12 @@ use embedded_graphics::Drawable;
 
 use tokio::sync::mpsc::Sender;
 
+use crate::constants::{LISTING_JUMP_SIZE, SELECTION_MARGIN};
 use crate::display::Display;
 use crate::geom::{Alignment, Point, Rect};
 use crate::platform::{DefaultPlatform, Key, KeyEvent, Platform};
 use crate::stylesheet::{Stylesheet, StylesheetColor};
-use crate::view::{Command, Label, View};
+use crate::view::{Command, Image, ImageMode, Label, View};
 
 /// A listing of selectable entries. Assumes that all entries have the same size.
 #[derive(Debug, Clone)]
@@ -24,6 +26,10 @@ pub struct ScrollList {
     items: Vec<String>,
     /// Visible entries.
     children: Vec<Label<String>>,
+    /// Icons drawn before each entry, if any.
+    icons: Vec<Option<PathBuf>>,
+    /// Icons of the visible entries.
+    icon_views: Vec<Image>,
     alignment: Alignment,
     entry_height: u32,
     top: usize,
@@ -52,6 +58,8 @@ impl ScrollList {
             rect,
             items: Vec::new(),
             children: Vec::new(),
+            icons: Vec::new(),
+            icon_views: Vec::new(),
             alignment,
             entry_height,
             top: 0,
@@ -80,10 +88,17 @@ impl ScrollList {
         self.dirty = true;
     }
 
+    /// Sets the icons drawn before each item, by index. Call before `set_items`, so that the
+    /// items are laid out to make room for them.
+    pub fn set_icons(&mut self, icons: Vec<Option<PathBuf>>) {
+        self.icons = icons;
+    }
+
     pub fn set_items(&mut self, items: Vec<String>, preserve_selection: bool) {
         if items.is_empty() {
             self.items = items;
             self.children.clear();
+            self.icon_views.clear();
             self.dirty = true;
             return;
         }
@@ -99,14 +114,22 @@ impl ScrollList {
         self.items = items;
 
         self.children.clear();
+        self.icon_views.clear();
+        let offset = self.icon_offset();
         let mut y = self.rect.y + 4;
         for i in 0..self.visible_count() {
             self.children.push(Label::new(
-                Point::new(self.rect.x + 12 * self.alignment.sign(), y),
+                Point::new(self.rect.x + 12 * self.alignment.sign() + offset as i32, y),
                 self.items[i].to_owned(),
                 self.alignment,
-                Some(self.rect.w - 24),
+                Some(self.rect.w - 24 - offset),
             ));
+            if offset > 0 {
+                self.icon_views.push(Image::empty(
+                    Rect::new(self.rect.x, y, self.icon_size(), self.icon_size()),
+                    ImageMode::Contain,
+                ));
+            }
             y += self.entry_height as i32;
         }
 
@@ -153,6 +176,22 @@ impl ScrollList {
         for (i, child) in self.children.iter_mut().enumerate() {
             child.set_text(self.items[self.top + i].to_owned());
         }
+        for (i, icon) in self.icon_views.iter_mut().enumerate() {
+            icon.set_path(self.icons.get(self.top + i).cloned().flatten());
+        }
+    }
+
+    fn icon_size(&self) -> u32 {
+        self.entry_height.saturating_sub(SELECTION_MARGIN)
+    }
+
+    /// Space left before the items for icons. Icons sit outside of the selection highlight.
+    fn icon_offset(&self) -> u32 {
+        if self.icons.iter().any(Option::is_some) {
+            self.icon_size() + 12
+        } else {
+            0
+        }
     }
 }
 
@@ -204,6 +243,11 @@ impl View for ScrollList {
                 child.draw(display, styles)?;
             }
 
+            for icon in self.icon_views.iter_mut() {
+                icon.set_should_draw();
+                icon.draw(display, styles)?;
+            }
+
             self.dirty = false;
 
             return Ok(true);
@@ -215,12 +259,19 @@ impl View for ScrollList {
                 drawn = true;
             }
         }
+        for icon in self.icon_views.iter_mut() {
+            if icon.should_draw() && icon.draw(display, styles)? {
+                drawn = true;
+            }
+        }
 
         Ok(drawn)
     }
 
     fn should_draw(&self) -> bool {
-        self.dirty || self.children.iter().any(|v| v.should_draw())
+        self.dirty
+            || self.children.iter().any(|v| v.should_draw())
+            || self.icon_views.iter().any(|v| v.should_draw())
     }
 
     fn set_should_draw(&mut self) {
@@ -228,6 +279,9 @@ impl View for ScrollList {
         for entry in &mut self.children {
             entry.set_should_draw();
         }
+        for icon in &mut self.icon_views {
+            icon.set_should_draw();
+        }
     }
 
     async fn handle_key_event(
@@ -252,14 +306,17 @@ impl View for ScrollList {
                 }
                 KeyEvent::Pressed(Key::L) | KeyEvent::Autorepeat(Key::L) => {
                     self.select(
-                        (self.selected as isize - 5).clamp(0, self.items.len() as isize - 1)
+                        (self.selected as isize - LISTING_JUMP_SIZE as isize)
+                            .clamp(0, self.items.len() as isize - 1)
                             as usize,
                     );
                     self.dirty = true;
                     Ok(true)
                 }
                 KeyEvent::Pressed(Key::R) | KeyEvent::Autorepeat(Key::R) => {
-                    self.select((self.selected + 5).clamp(0, self.items.len() - 1));
+                    self.select(
+                        (self.selected + LISTING_JUMP_SIZE as usize).clamp(0, self.items.len() - 1),
+                    );
                     self.dirty = true;
                     Ok(true)
                 }
@@ -302,9 +359,16 @@ impl View for ScrollList {
     fn set_position(&mut self, point: Point) {
         self.rect.x = point.x;
         self.rect.y = point.y;
+        let offset = self.icon_offset() as i32;
         for (i, child) in self.children.iter_mut().enumerate() {
             child.set_position(Point::new(
-                point.x + 12,
+                point.x + 12 + offset,
+                point.y + 8 + i as i32 * self.entry_height as i32,
+            ));
+        }
+        for (i, icon) in self.icon_views.iter_mut().enumerate() {
+            icon.set_position(Point::new(
+                point.x,
                 point.y + 8 + i as i32 * self.entry_height as i32,
             ));
         }